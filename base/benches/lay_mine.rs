@@ -1,4 +1,4 @@
-use ms_toollib::{laymine_solvable_thread, laymine_solvable, laymine, laymine_op};
+use ms_toollib::{laymine_solvable_thread, laymine_solvable, laymine, laymine_op, laymine_op_seed, laymine_solvable_seed, is_solvable};
 #[macro_use]
 extern crate bencher;
 use bencher::Bencher;
@@ -14,5 +14,15 @@ fn bench_laymine_op(bencher: &mut Bencher) {
     bencher.iter(|| laymine_op(16, 30, 99, 8, 15));
 } // 11,522 ns/iter
 
-benchmark_group!(lay_mine, bench_laymine, bench_laymine_op);
+fn bench_is_solvable(bencher: &mut Bencher) {
+    // 雷密度较低的高级局面，多数能扫完，判雷的轮数多
+    let boards: Vec<_> = (0..20).map(|seed| laymine_op_seed(16, 30, 60, 8, 15, seed)).collect();
+    bencher.iter(|| boards.iter().filter(|b| is_solvable(b, 8, 15)).count());
+} // 3,214,684 ns/iter；改用增量约束前 9,760,330 ns/iter
+
+fn bench_laymine_solvable_seed(bencher: &mut Bencher) {
+    bencher.iter(|| laymine_solvable_seed(16, 30, 70, 8, 15, 10000, 1));
+} // 219,789 ns/iter；改用增量约束前 1,317,253 ns/iter
+
+benchmark_group!(lay_mine, bench_laymine, bench_laymine_op, bench_is_solvable, bench_laymine_solvable_seed);
 benchmark_main!(lay_mine);
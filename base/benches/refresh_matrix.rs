@@ -1,12 +1,12 @@
-use ms_toollib::{refresh_matrixs};
+use ms_toollib::{refresh_matrixs, ConstraintSystem};
 #[macro_use]
 extern crate bencher;
 use bencher::Bencher;
 
 // 测试计算矩阵性能
 // cargo bench --bench refresh_matrix
-fn bench_sample_3BVs_exp(bencher: &mut Bencher) {
-    let game_board = vec![
+fn sample_game_board() -> Vec<Vec<i32>> {
+    vec![
         vec![10, 10, 10,  1,  1,  0,  0,  1, 11,  1,  0,  0,  0,  0,  1, 10, 10, 10,  2, 10, 10, 10, 10, 10, 10, 10, 10, 10, 10, 10],
         vec![10, 10, 10, 11,  2,  0,  0,  1,  1,  1,  1,  2,  2,  1,  1, 10, 10, 10, 10, 10, 10, 10, 10, 10, 10, 10, 10, 10, 10, 10],
         vec![10, 10, 10, 11,  4,  2,  1,  0,  0,  0,  1, 11, 11, 10, 10, 10, 10, 10, 10, 10, 10, 10, 10, 10, 10, 10, 10, 10, 10, 10],
@@ -23,9 +23,36 @@ fn bench_sample_3BVs_exp(bencher: &mut Bencher) {
         vec![ 1,  1,  1,  1,  2, 10, 10, 10, 10, 10, 10, 10, 10, 10, 10, 10, 10, 10, 10, 10, 10, 10, 10,  0, 10,  0, 10, 10, 10, 10],
         vec![ 0,  0,  2, 10, 10, 10, 10, 10, 10, 10, 10, 10, 10, 10, 10, 10, 10, 10, 10, 10, 10, 10, 10,  0,  0,  0, 10, 10, 10, 10],
         vec![ 0,  0,  2, 10, 10, 10, 10, 10, 10, 10, 10, 10, 10, 10, 10, 10, 10, 10, 10, 10, 10, 10, 10, 10, 10, 10, 10, 10, 10, 10],
-    ];
+    ]
+}
+
+fn bench_sample_3BVs_exp(bencher: &mut Bencher) {
+    let game_board = sample_game_board();
     bencher.iter(|| refresh_matrixs(&game_board));
 } // 15,048 ns/iter
 
-benchmark_group!(refresh_matrix, bench_sample_3BVs_exp);
+// 增量的约束系统已经建好时，只生成矩阵（段都已缓存）
+fn bench_constraint_system_matrixs(bencher: &mut Bencher) {
+    let mut system = ConstraintSystem::from_game_board(&sample_game_board());
+    bencher.iter(|| system.matrixs());
+} // 1,770 ns/iter
+
+// 打开一格、生成矩阵、再撤销，即逐步分析时每一步的开销
+fn bench_constraint_system_step(bencher: &mut Bencher) {
+    let mut system = ConstraintSystem::from_game_board(&sample_game_board());
+    bencher.iter(|| {
+        let checkpoint = system.checkpoint();
+        system.reveal((5, 7), 2);
+        let m = system.matrixs();
+        system.undo_to(checkpoint);
+        m
+    });
+} // 4,843 ns/iter
+
+benchmark_group!(
+    refresh_matrix,
+    bench_sample_3BVs_exp,
+    bench_constraint_system_matrixs,
+    bench_constraint_system_step
+);
 benchmark_main!(refresh_matrix);
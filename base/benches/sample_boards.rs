@@ -8,6 +8,7 @@ use bencher::Bencher;
 fn bench_sample_3BVs_exp(bencher: &mut Bencher) {
     bencher.iter(|| sample_3BVs_exp(5, 5, 1000));
 } // 4,241,895 ns/iter
+// 本机上改用BitBoard计算3BV前 17,576,293 ns/iter，之后 7,139,367 ns/iter
 
 benchmark_group!(sample_boards, bench_sample_3BVs_exp);
 benchmark_main!(sample_boards);
@@ -1,23 +1,27 @@
 use crate::utils::{
-    cal_bbbv, cal_bbbv_exp, cal_table_minenum_enum, cal_table_minenum_recursion, chunk_matrixes,
-    combine, enuOneStep, enum_comb, find_a_border_cell, laymine, laymine_op, legalize_board,
-    refresh_board, refresh_matrix, refresh_matrixs, refresh_matrixses, unsolvable_structure,
-    BigNumber, C_query, C,
+    cal_bbbv, cal_table_minenum_dp, cal_table_minenum_enum,
+    cal_table_minenum_recursion_generic, chunk_matrixes,
+    combine, enuOneStep, enum_comb, find_a_border_cell, laymine_op, laymine_op_rng,
+    laymine_rng, legalize_board, random_seed, refresh_matrix, refresh_matrixs,
+    refresh_matrixses, unsolvable_structure, MineNumPoly, SeededRng,
 };
 
-#[cfg(feature = "js")]
-use crate::utils::js_shuffle;
+use crate::bitboard::BitBoard;
+use crate::constraint::ConstraintSystem;
+use crate::count::{LogNumber, MineCount};
+use crate::exec::{exec_find_first, exec_map, ExecContext, ExecOptions, ExecStatus};
+use crate::grid::{neighbours, Cell};
+use crate::validate::has_number_contradiction;
+use num_bigint::{BigInt, BigUint};
+use num_rational::BigRational;
+
+use crate::mbf::encode_mbf;
 
 #[cfg(any(feature = "py", feature = "rs"))]
 use crate::OBR::ImageBoard;
 
 use itertools::Itertools;
 
-#[cfg(any(feature = "py", feature = "rs"))]
-use rand::seq::SliceRandom;
-#[cfg(any(feature = "py", feature = "rs"))]
-use rand::thread_rng;
-
 #[cfg(any(feature = "py", feature = "rs"))]
 use rand::prelude::*;
 #[cfg(any(feature = "py", feature = "rs"))]
@@ -26,9 +30,7 @@ use rand::{rngs::StdRng, Rng, SeedableRng};
 use std::time;
 
 use std::cmp::{max, min};
-use std::sync::mpsc;
-use std::sync::{Arc, Mutex};
-use std::thread;
+use std::collections::HashMap;
 
 #[cfg(any(feature = "py", feature = "rs"))]
 use tract_ndarray::Array;
@@ -97,11 +99,11 @@ pub fn solve_minus(
         not_mine_rel.dedup();
         for i in &not_mine_rel {
             not_mine.push(xs[b][*i]);
-            board_of_game[xs[b][*i].0][xs[b][*i].1] = 12;
+            board_of_game[xs[b][*i].0][xs[b][*i].1] = Cell::Safe.to_i32();
         }
         for i in &is_mine_rel {
             is_mine.push(xs[b][*i]);
-            board_of_game[xs[b][*i].0][xs[b][*i].1] = 11;
+            board_of_game[xs[b][*i].0][xs[b][*i].1] = Cell::Flag.to_i32();
             for j in 0..As[b].len() {
                 bs[b][j] -= As[b][j][*i];
             }
@@ -155,7 +157,7 @@ pub fn solve_direct(
                 for k in (0..matrixColumn).rev() {
                     if As[b][i][k] >= 1 {
                         is_mine.push((xs[b][k].0, xs[b][k].1));
-                        board_of_game[xs[b][k].0][xs[b][k].1] = 11;
+                        board_of_game[xs[b][k].0][xs[b][k].1] = Cell::Flag.to_i32();
                         xs[b].remove(k);
                         for t in 0..matrixRow {
                             bs[b][t] -= As[b][t][k];
@@ -174,7 +176,7 @@ pub fn solve_direct(
                 for k in (0..matrixColumn).rev() {
                     if As[b][i][k] >= 1 {
                         not_mine.push(xs[b][k]);
-                        board_of_game[xs[b][k].0][xs[b][k].1] = 12;
+                        board_of_game[xs[b][k].0][xs[b][k].1] = Cell::Safe.to_i32();
                         xs[b].remove(k);
                         for t in 0..matrixRow {
                             As[b][t].remove(k);
@@ -200,204 +202,363 @@ pub fn solve_direct(
 /// 游戏局面概率计算引擎。  
 /// - 输入：局面、未被标出的雷数。未被标出的雷数大于等于1时，理解成实际数量；小于1时理解为比例。  
 /// - 注意：局面中可以标雷（11）和非类（12），但必须全部标对。  
-/// - 注意：若超出枚举长度（45），则该区块的部分返回平均概率，且返回所需的枚举长度。  
+/// - 注意：每一段都用动态规划精确计算，没有长度限制。最后一个返回值是最长的段的长度，以此体现局面的求解难度。  
 /// - 返回：所有边缘格子是雷的概率、内部未知格子是雷的概率、局面中总未知雷数（未知雷数 = 总雷数 - 已经标出的雷）的范围。  
-/// - 注意：若没有内部未知区域，返回NaN。
+/// - 注意：若没有内部未知区域，其概率返回0。  
+/// - 注意：局面自相矛盾、或与雷数矛盾时返回Err(1)，矛盾的细节用[validate_game_board](crate::validate_game_board)查看。
+/// - 注意：失败、高亮等标记（14~18）与原来一样被忽略，既不当作未打开的格子，也不当作矛盾。
 pub fn cal_possibility(
     board_of_game: &Vec<Vec<i32>>,
-    mut mine_num: f64,
+    mine_num: f64,
+) -> Result<(Vec<((usize, usize), f64)>, f64, [usize; 3], usize), usize> {
+    cal_possibility_budget(board_of_game, mine_num, None)
+}
+
+/// 带计算预算的概率计算引擎。  
+/// - 输入：局面、未被标出的雷数、段长的预算。  
+/// - 注意：预算为None时与[cal_possibility]相同；否则长度超出预算的段不参与计算，视为内部未知区域，返回平均概率，
+/// 用于对速度要求高、对精度要求不高的场合。  
+/// - 注意：情况数用对数表示（[LogNumber]），不会溢出，概率的相对误差在1e-12左右。需要精确值时用[cal_possibility_exact]。
+pub fn cal_possibility_budget(
+    board_of_game: &Vec<Vec<i32>>,
+    mine_num: f64,
+    enum_limit: Option<usize>,
+) -> Result<(Vec<((usize, usize), f64)>, f64, [usize; 3], usize), usize> {
+    cal_possibility_log(board_of_game, mine_num, enum_limit, None)
+}
+
+/// 可设执行选项的概率计算引擎，用于很大的局面。输入、返回的含义与[cal_possibility]相同。  
+/// - 注意：各段分给各线程用动态规划计算，进度的总工作量为段数。被取消或超时时返回Err(2)。
+pub fn cal_possibility_exec(
+    board_of_game: &Vec<Vec<i32>>,
+    mine_num: f64,
+    options: &ExecOptions,
+) -> Result<(Vec<((usize, usize), f64)>, f64, [usize; 3], usize), usize> {
+    cal_possibility_log(board_of_game, mine_num, None, Some(options))
+}
+
+// 用对数表示情况数的概率计算，cal_possibility_budget与cal_possibility_exec共用
+fn cal_possibility_log(
+    board_of_game: &Vec<Vec<i32>>,
+    mine_num: f64,
+    enum_limit: Option<usize>,
+    options: Option<&ExecOptions>,
 ) -> Result<(Vec<((usize, usize), f64)>, f64, [usize; 3], usize), usize> {
-    // 如果超出枚举长度限制，记录并返回这个长度，以此体现局面的求解难度。
+    let counts = cal_possibility_counts::<LogNumber>(board_of_game, mine_num, enum_limit, options)?;
+    let p = counts
+        .p
+        .iter()
+        .map(|(pos, c)| (*pos, c.ratio(&counts.total)))
+        .collect();
+    // 没有内部未知区域时，其概率记为0
+    let p_unknow = if counts.unknow_block == 0 {
+        0.0
+    } else {
+        counts.p_unknow.ratio(&counts.total)
+    };
+    Ok((p, p_unknow, counts.mine_num_range, counts.exceed_len))
+}
+
+/// 精确的概率计算引擎，用于验算。输入、返回的含义与[cal_possibility]相同，但概率是精确的分数。  
+/// - 注意：情况数用大整数表示，比[cal_possibility]慢得多。
+/// - 用python调用时的示例，概率为fractions.Fraction：
+/// ```python
+/// import ms_toollib as ms
+/// (p, p_unknow, mine_num_range, max_len) = ms.cal_possibility_exact(game_board, 99)
+/// ```
+pub fn cal_possibility_exact(
+    board_of_game: &Vec<Vec<i32>>,
+    mine_num: f64,
+) -> Result<(Vec<((usize, usize), BigRational)>, BigRational, [usize; 3], usize), usize> {
+    let counts = cal_possibility_counts::<BigUint>(board_of_game, mine_num, None, None)?;
+    let total = BigInt::from(counts.total);
+    let p = counts
+        .p
+        .into_iter()
+        .map(|(pos, c)| (pos, BigRational::new(BigInt::from(c), total.clone())))
+        .collect();
+    let p_unknow = if counts.unknow_block == 0 {
+        BigRational::from_integer(BigInt::from(0))
+    } else {
+        BigRational::new(BigInt::from(counts.p_unknow), total)
+    };
+    Ok((p, p_unknow, counts.mine_num_range, counts.exceed_len))
+}
+
+/// 概率计算引擎的中间结果：每格是雷的情况数，以及局面总情况数
+struct PossibilityCounts<T> {
+    p: Vec<((usize, usize), T)>,
+    /// 内部未知区域中某一格是雷的情况数
+    p_unknow: T,
+    total: T,
+    unknow_block: usize,
+    mine_num_range: [usize; 3],
+    exceed_len: usize,
+}
+
+/// 两个多项式的乘积中，雷数为m的项的系数
+fn coef_of_product<T: MineCount>(a: &MineNumPoly<T>, b: &MineNumPoly<T>, m: usize) -> T {
+    let mut c = T::zero();
+    for (i, x) in a.coef.iter().enumerate() {
+        if a.offset + i > m {
+            break;
+        }
+        let y = b.get(m - a.offset - i);
+        if !y.is_zero() {
+            c.accumulate(&x.times(&y));
+        }
+    }
+    c
+}
+
+fn cal_possibility_counts<T: MineCount + Send>(
+    board_of_game: &Vec<Vec<i32>>,
+    mine_num: f64,
+    enum_limit: Option<usize>,
+    options: Option<&ExecOptions>,
+) -> Result<PossibilityCounts<T>, usize> {
+    // 数字的矛盾在矩阵中体现不出来，先单独检查
+    if has_number_contradiction(board_of_game) {
+        return Err(1);
+    }
+    // 记录并返回最长的段的长度，以此体现局面的求解难度。
     let mut exceed_len = 0;
-    let mut p = vec![];
-    let mut table_cell_mine_num_s: Vec<Vec<Vec<usize>>> = vec![];
-    // 每段每格雷数表：记录了每段每格（或者地位等同的复合格）、每种总雷数下的是雷情况数
-    let mut comb_relp_s = vec![]; // 记录了方格的组合关系
-                                  // let mut enum_comb_table_s = vec![];
-    let mut table_mine_num_s: Vec<[Vec<usize>; 2]> = vec![];
-    // 每段雷数分布表：记录了每段（不包括内部段）每种总雷数下的是雷总情况数
-    // 例如：[[[17, 18, 19, 20, 21, 22, 23, 24], [48, 2144, 16872, 49568, 68975, 48960, 16608, 2046]]]
     let (mut matrix_a_s, mut matrix_x_s, mut matrix_b_s, mut unknow_block, is_mine_num) =
-        refresh_matrixs(&board_of_game);
+        refresh_matrixs(board_of_game);
     for i in (0..matrix_a_s.len()).rev() {
         let matrix_x_s_len = matrix_x_s[i].len();
         if matrix_x_s_len > exceed_len {
             exceed_len = matrix_x_s_len;
         }
-        if matrix_x_s_len > ENUM_LIMIT {
+        if matches!(enum_limit, Some(limit) if matrix_x_s_len > limit) {
             matrix_a_s.remove(i);
             matrix_x_s.remove(i);
             matrix_b_s.remove(i);
             unknow_block += matrix_x_s_len;
         }
     }
-
     let block_num = matrix_a_s.len(); // 整个局面被分成的段数
-                                      // let mut block_num_calable = 0;
 
-    let mut matrixA_squeeze_s: Vec<Vec<Vec<i32>>> = vec![];
-    let mut matrixx_squeeze_s: Vec<Vec<(usize, usize)>> = vec![];
-    // let mut min_max_mine_num = [0, 0];
-    for i in 0..block_num {
-        let (matrixA_squeeze, matrixx_squeeze, combination_relationship) =
+    // 第一步，用动态规划整理出每段雷数分布表（以雷数为自变量的多项式）、每段每格雷数情况表
+    let mut comb_relp_s = vec![];
+    let mut table_mine_num_s: Vec<MineNumPoly<T>> = vec![];
+    let mut table_cell_mine_num_s: Vec<(Vec<usize>, Vec<Vec<T>>)> = vec![];
+    // 各段互不相关，可以多线程计算
+    let single_thread = ExecOptions::single_thread();
+    let options = options.unwrap_or(&single_thread);
+    let (tables, status) = exec_map(options, block_num, block_num, |i, ctx| {
+        let (matrixA_squeeze, _, combination_relationship) =
             combine(&matrix_a_s[i], &matrix_x_s[i]);
-        comb_relp_s.push(combination_relationship);
-        matrixA_squeeze_s.push(matrixA_squeeze);
-        matrixx_squeeze_s.push(matrixx_squeeze);
+        let table =
+            cal_table_minenum_dp::<T>(&matrixA_squeeze, &matrix_b_s[i], &combination_relationship);
+        ctx.advance(1);
+        table.map(|t| (t, combination_relationship))
+    });
+    if status != ExecStatus::Finished {
+        return Err(2);
     }
-    // 分段枚举后，根据雷数限制，删除某些情况
-    for i in 0..block_num {
-        let table_mine_num_i;
-        let table_cell_mine_num_i;
-        match cal_table_minenum_recursion(
-            &matrixA_squeeze_s[i],
-            &matrixx_squeeze_s[i],
-            &matrix_b_s[i],
-            &comb_relp_s[i],
-        ) {
-            Ok((table_mine_num_i_, table_cell_mine_num_i_)) => {
-                table_mine_num_i = table_mine_num_i_;
-                table_cell_mine_num_i = table_cell_mine_num_i_;
-                // block_num_calable += 1;
-            }
-            Err(e) => {
-                if e == 1 {
-                    // table_mine_num_i=[vec![0],vec![0]];
-                    // table_cell_mine_num_i=[vec![0],vec![0]].to_vec();
-                    return Err(1); // 这是不合法、矛盾的的局面
-                } else {
-                    return Err(2); // 这是不可能的，枚举器不可能返回2这种错误，但是需要这样写通过rust的编译
-                }
-            }
-        };
-        // min_max_mine_num[0] += table_mine_num_i[0][0];
-        // min_max_mine_num[1] += table_mine_num_i[0][table_mine_num_i[0].len() - 1];
-
-        table_cell_mine_num_s.push(table_cell_mine_num_i);
-        table_mine_num_s.push(table_mine_num_i);
-    } // 第一步，整理出每段每格雷数情况表、每段雷数分布表、每段雷分布情况总数表
-    let mut min_mine_num = 0;
-    let mut max_mine_num = 0;
-
-    // let block_num = block_num_calable;
-
-    for i in 0..block_num {
-        min_mine_num += table_mine_num_s[i][0].iter().min().unwrap();
-        max_mine_num += table_mine_num_s[i][0].iter().max().unwrap();
+    for table in tables {
+        let ((mine_nums, table_mine_num_i, table_cell_mine_num_i), combination_relationship) =
+            table.unwrap()?;
+        let offset = mine_nums[0];
+        let mut coef = vec![T::zero(); mine_nums[mine_nums.len() - 1] - offset + 1];
+        for (m, c) in mine_nums.iter().zip(table_mine_num_i) {
+            coef[m - offset] = c;
+        }
+        table_mine_num_s.push(MineNumPoly { offset, coef });
+        table_cell_mine_num_s.push((mine_nums, table_cell_mine_num_i));
+        comb_relp_s.push(combination_relationship);
     }
+    let min_mine_num: usize = table_mine_num_s.iter().map(|t| t.offset).sum();
+    let mut max_mine_num: usize = table_mine_num_s
+        .iter()
+        .map(|t| t.offset + t.coef.len() - 1)
+        .sum();
     let mine_num = if mine_num <= 1.0 {
         let mn = ((board_of_game.len() * board_of_game[0].len()) as f64 * mine_num) as usize;
         min(
-            max(mn - is_mine_num, min_mine_num),
+            max(mn.saturating_sub(is_mine_num), min_mine_num),
             max_mine_num + unknow_block,
         )
     } else {
-        mine_num as usize - is_mine_num
+        (mine_num as usize).checked_sub(is_mine_num).ok_or(1usize)? // 标的雷比总雷数多
     };
-
     max_mine_num = min(max_mine_num, mine_num);
-    let unknow_mine_num: Vec<usize> =
-        (mine_num - max_mine_num..min(mine_num - min_mine_num, unknow_block) + 1).collect();
-    // 这里的写法存在极小的风险，例如边缘格雷数分布是0，1，3，而我们直接认为了可能有2
-    let mut unknow_mine_s_num = vec![];
-    for i in &unknow_mine_num {
-        unknow_mine_s_num.push(C(unknow_block, *i));
+
+    // 第二步，整理内部未知区域雷数分布表，与边缘的段地位等同
+    let mut table_unknow = MineNumPoly::zero();
+    let unknow_mine_num_min = mine_num.saturating_sub(max_mine_num);
+    let unknow_mine_num_max = min(mine_num.saturating_sub(min_mine_num), unknow_block);
+    if unknow_mine_num_min <= unknow_mine_num_max {
+        table_unknow.offset = unknow_mine_num_min;
+        table_unknow.coef = (unknow_mine_num_min..unknow_mine_num_max + 1)
+            .map(|k| T::binomial(unknow_block, k))
+            .collect();
     }
-    // 第二步，整理内部未知段雷数分布表，并筛选。这样内部未知雷段和边缘雷段的地位视为几乎等同，但数据结构不同
-    table_mine_num_s.push([unknow_mine_num.clone(), vec![]]);
-    // 这里暂时不知道怎么写，目前这样写浪费了几个字节的内存
-    // 未知区域的情况数随雷数的分布不能存在表table_mine_num中，因为格式不一样，后者是大数类型
-    let mut mine_in_each_block = (0..block_num + 1)
-        .map(|i| 0..table_mine_num_s[i][0].len())
-        .multi_cartesian_product()
-        .collect::<Vec<_>>();
-    for i in (0..mine_in_each_block.len()).rev() {
-        let mut total_num = 0;
-        for j in 0..block_num + 1 {
-            total_num += table_mine_num_s[j][0][mine_in_each_block[i][j]];
-        }
-        if total_num != mine_num {
-            mine_in_each_block.remove(i);
-        }
+
+    // 第三步，计算前缀积、后缀积（后缀积包括内部未知区域），用于计算除某段以外其他段的雷数分布
+    let mut prefix = vec![MineNumPoly::one()];
+    for i in 0..block_num {
+        let t = prefix[i].mul(&table_mine_num_s[i]);
+        prefix.push(t);
     }
-    // println!("mine_in_each_block: {:?}", mine_in_each_block);
-    // 第三步，枚举每段雷数情况索引表：行代表每种情况，列代表每段雷数的索引，最后一列代表未知区域雷数
-    let mut table_mine_num_other: Vec<Vec<BigNumber>> = vec![];
-    for i in 0..block_num + 1 {
-        table_mine_num_other.push(vec![
-            BigNumber { a: 0.0, b: 0 };
-            table_mine_num_s[i][0].len()
-        ]);
-    } // 初始化
-    for s in mine_in_each_block {
-        for i in 0..block_num {
-            let mut s_num = BigNumber { a: 1.0, b: 0 };
-            let mut s_mn = mine_num; // 未知区域中的雷数
-            for j in 0..block_num {
-                if i != j {
-                    s_num.mul_usize(table_mine_num_s[j][1][s[j]]);
-                }
-                s_mn -= table_mine_num_s[j][0][s[j]];
-            }
-            let ps = unknow_mine_num.iter().position(|x| *x == s_mn).unwrap();
-            s_num.mul_big_number(&unknow_mine_s_num[ps]);
-            table_mine_num_other[i][s[i]].add_big_number(&s_num);
-        }
-        let mut s_num = BigNumber { a: 1.0, b: 0 };
-        let mut s_mn = mine_num; // 未知区域中的雷数
-        for j in 0..block_num {
-            s_num.mul_usize(table_mine_num_s[j][1][s[j]]);
-            s_mn -= table_mine_num_s[j][0][s[j]];
-        }
-        let ps = unknow_mine_num.iter().position(|x| *x == s_mn).unwrap();
-        table_mine_num_other[block_num][ps].add_big_number(&s_num);
+    let mut suffix = vec![table_unknow.clone()];
+    for i in (0..block_num).rev() {
+        let t = table_mine_num_s[i].mul(&suffix[0]);
+        suffix.insert(0, t);
     }
-    // 第四步，计算每段其他雷数情况表
-    let mut T = BigNumber { a: 0.0, b: 0 };
-    for i in 0..unknow_mine_s_num.len() {
-        let mut t = table_mine_num_other[block_num][i].clone();
-        t.mul_big_number(&unknow_mine_s_num[i]);
-        T.add_big_number(&t);
+
+    // 第四步，计算局面总情况数
+    let total = suffix[0].get(mine_num);
+    if total.is_zero() {
+        return Err(1); // 雷数与局面矛盾
     }
-    // 第五步，计算局面总情况数
 
+    // 第五步，计算边缘每格是雷的情况数
+    let mut p = vec![];
     for i in 0..block_num {
+        let (mine_nums, table_cell_mine_num_i) = &table_cell_mine_num_s[i];
+        // 其他段在每种雷数下的情况数
+        let other: Vec<T> = mine_nums
+            .iter()
+            .map(|&m| {
+                if m > mine_num {
+                    T::zero()
+                } else {
+                    coef_of_product(&prefix[i], &suffix[i + 1], mine_num - m)
+                }
+            })
+            .collect();
         for cells_id in 0..comb_relp_s[i].len() {
-            let cells_len = comb_relp_s[i][cells_id].len();
-            for cell_id in 0..cells_len {
-                let mut s_cell = BigNumber { a: 0.0, b: 0 };
-                for s in 0..table_mine_num_other[i].len() {
-                    let mut o = table_mine_num_other[i][s].clone();
-                    o.mul_usize(table_cell_mine_num_s[i][s][cells_id]);
-                    s_cell.add_big_number(&o);
+            let mut s_cell = T::zero();
+            for s in 0..mine_nums.len() {
+                if !other[s].is_zero() {
+                    s_cell.accumulate(&table_cell_mine_num_i[s][cells_id].times(&other[s]));
                 }
-                let p_cell = s_cell.div_big_num(&T);
-                let id = comb_relp_s[i][cells_id][cell_id];
-                p.push((matrix_x_s[i][id], p_cell));
+            }
+            for id in &comb_relp_s[i][cells_id] {
+                p.push((matrix_x_s[i][*id], s_cell.clone()));
             }
         }
     }
-    // 第六步，计算边缘每格是雷的概率
-    let mut u_s = BigNumber { a: 0.0, b: 0 };
-    for i in 0..unknow_mine_num.len() {
-        let mut u = table_mine_num_other[block_num][i].clone();
-        u.mul_big_number(&unknow_mine_s_num[i]);
-        u.mul_usize(unknow_mine_num[i]);
-        u_s.add_big_number(&u);
+
+    // 第六步，计算内部未知区域中某一格是雷的情况数
+    let mut p_unknow = T::zero();
+    for k in max(unknow_mine_num_min, 1)..unknow_mine_num_max + 1 {
+        let t = prefix[block_num].get(mine_num - k);
+        if !t.is_zero() {
+            p_unknow.accumulate(&t.times(&T::binomial(unknow_block - 1, k - 1)));
+        }
     }
-    let p_unknow = u_s.div_big_num(&T) / unknow_block as f64;
-    // 第七步，计算内部未知区域是雷的概率
 
-    Ok((
+    Ok(PossibilityCounts {
         p,
         p_unknow,
-        [
+        total,
+        unknow_block,
+        mine_num_range: [
             min_mine_num + is_mine_num,
             mine_num + is_mine_num,
             max_mine_num + is_mine_num + unknow_block,
         ],
         exceed_len,
-    ))
+    })
+}
+
+/// 各段雷数分布表的缓存。对同一局面做多种假设（例如假设某格打开后是某个数字）反复计数时，
+/// 不受假设影响的段直接复用。
+#[derive(Default)]
+pub(crate) struct SegmentTableCache {
+    tables: HashMap<(Vec<(usize, usize)>, Vec<Vec<i32>>, Vec<i32>), MineNumPoly<LogNumber>>,
+}
+
+/// 局面的总情况数，即与局面、雷数相容的雷的分布的数量。局面与雷数矛盾时返回Err(1)。
+/// - 注意：mine_num是总雷数（含已标出的雷），不能是比例。
+pub(crate) fn cal_total_count(
+    board_of_game: &Vec<Vec<i32>>,
+    mine_num: usize,
+    cache: &mut SegmentTableCache,
+) -> Result<LogNumber, usize> {
+    // 周围没有未知格的数字不构成约束，但假设可能使其矛盾，需要单独检查
+    if has_number_contradiction(board_of_game) {
+        return Err(1);
+    }
+    let (matrix_a_s, matrix_x_s, matrix_b_s, unknow_block, is_mine_num) =
+        refresh_matrixs(board_of_game);
+    let mine_num = mine_num.checked_sub(is_mine_num).ok_or(1usize)?;
+    let mut table = MineNumPoly::one();
+    for i in 0..matrix_a_s.len() {
+        let key = (
+            matrix_x_s[i].clone(),
+            matrix_a_s[i].clone(),
+            matrix_b_s[i].clone(),
+        );
+        if !cache.tables.contains_key(&key) {
+            let (matrix_a_squeeze, _, combination_relationship) =
+                combine(&matrix_a_s[i], &matrix_x_s[i]);
+            let (mine_nums, table_mine_num_i, _) = cal_table_minenum_dp::<LogNumber>(
+                &matrix_a_squeeze,
+                &matrix_b_s[i],
+                &combination_relationship,
+            )?;
+            let offset = mine_nums[0];
+            let mut coef = vec![LogNumber::zero(); mine_nums[mine_nums.len() - 1] - offset + 1];
+            for (m, c) in mine_nums.iter().zip(table_mine_num_i) {
+                coef[m - offset] = c;
+            }
+            cache.tables.insert(key.clone(), MineNumPoly { offset, coef });
+        }
+        table = table.mul(&cache.tables[&key]);
+    }
+    let mut total = LogNumber::zero();
+    for k in mine_num.saturating_sub(unknow_block)..=mine_num {
+        let t = table.get(k);
+        if !t.is_zero() {
+            total.accumulate(&t.times(&LogNumber::binomial(unknow_block, mine_num - k)));
+        }
+    }
+    if total.is_zero() {
+        return Err(1);
+    }
+    Ok(total)
+}
+
+/// 假设未打开的格子cell打开后是各个数字，返回每种可能的数字、其情况数，以及假设后的局面。
+/// 打开后是0时，周围的格子标为非雷（12）。mine_num是总雷数。
+pub(crate) fn cal_number_counts(
+    board_of_game: &Vec<Vec<i32>>,
+    mine_num: usize,
+    cell: (usize, usize),
+    cache: &mut SegmentTableCache,
+) -> Vec<(i32, LogNumber, Vec<Vec<i32>>)> {
+    let row = board_of_game.len();
+    let column = board_of_game[0].len();
+    let mut flags = 0;
+    let mut covered = 0;
+    for (m, n) in neighbours(row, column, cell.0, cell.1) {
+        match Cell::from_i32(board_of_game[m][n]) {
+            Some(Cell::Covered) | Some(Cell::Safe) => covered += 1,
+            Some(Cell::Flag) => flags += 1,
+            _ => {}
+        }
+    }
+    let mut ans = vec![];
+    for number in flags..=flags + covered {
+        let mut b = board_of_game.clone();
+        b[cell.0][cell.1] = number;
+        if number == 0 {
+            // 0周围的格子不构成约束，直接标为非雷
+            for (m, n) in neighbours(row, column, cell.0, cell.1) {
+                if b[m][n] == Cell::Covered {
+                    b[m][n] = Cell::Safe.to_i32();
+                }
+            }
+        }
+        if let Ok(c) = cal_total_count(&b, mine_num, cache) {
+            ans.push((number, c, b));
+        }
+    }
+    ans
 }
 
 /// 计算局面中各位置是雷的概率，按照所在的位置返回。
@@ -457,11 +618,11 @@ pub fn cal_possibility_onboard(
     }
     for r in 0..board_of_game.len() {
         for c in 0..board_of_game[0].len() {
-            if board_of_game[r][c] == 11 {
+            if board_of_game[r][c] == Cell::Flag {
                 p[r][c] = 1.0;
-            } else if board_of_game[r][c] == 10 && p[r][c] < -0.5 {
+            } else if board_of_game[r][c] == Cell::Covered && p[r][c] < -0.5 {
                 p[r][c] = pp.1;
-            } else if board_of_game[r][c] == 12 {
+            } else if board_of_game[r][c] == Cell::Safe {
                 p[r][c] = 0.0;
             } else if p[r][c] < -0.5 {
                 p[r][c] = 0.0;
@@ -471,6 +632,87 @@ pub fn cal_possibility_onboard(
     Ok((p, pp.2))
 }
 
+/// 计算局面中各格打开后显示各个数字的概率，按照所在的位置返回。与[cal_possibility_onboard]配套，用于挑选猜雷的位置。
+/// - 输入：局面、雷数（与[cal_possibility]相同）。局面中可以标雷（11）和非雷（12），但必须全部标对。
+/// - 返回：每格9个概率，第n个是该格不是雷、且打开后显示n的概率，9个之和等于该格不是雷的概率。
+/// 已打开的格子、标出的雷全为0。
+/// - 注意：对每格的每种数字都要重新计数，但不受影响的段会复用雷数分布表；内部未知区域中周围都是内部格子的格子，
+/// 按周围未知格的数量只算一次。
+pub fn cal_number_distribution_onboard(
+    board_of_game: &Vec<Vec<i32>>,
+    mine_num: f64,
+) -> Result<Vec<Vec<[f64; 9]>>, usize> {
+    let (p, _, mine_num_range, _) = cal_possibility(board_of_game, mine_num)?;
+    // 雷的比例换算成雷数，使各种假设下的计数可以比较
+    let mine_num = mine_num_range[1];
+    let row = board_of_game.len();
+    let column = board_of_game[0].len();
+    let mut cache = SegmentTableCache::default();
+    let total = cal_total_count(board_of_game, mine_num, &mut cache)?;
+    let frontier: Vec<(usize, usize)> = p.iter().map(|x| x.0).collect();
+    let is_interior =
+        |x: usize, y: usize| board_of_game[x][y] == Cell::Covered && !frontier.contains(&(x, y));
+    // 周围都是内部格子的格子，按周围格子数缓存
+    let mut interior_cache: HashMap<usize, [f64; 9]> = HashMap::new();
+    let mut ans = vec![vec![[0.0; 9]; column]; row];
+    for i in 0..row {
+        for j in 0..column {
+            if board_of_game[i][j] != Cell::Covered && board_of_game[i][j] != Cell::Safe {
+                continue;
+            }
+            let key = if is_interior(i, j)
+                && neighbours(row, column, i, j).all(|(m, n)| is_interior(m, n))
+            {
+                Some(neighbours(row, column, i, j).count())
+            } else {
+                None
+            };
+            if let Some(t) = key.and_then(|k| interior_cache.get(&k)) {
+                ans[i][j] = *t;
+                continue;
+            }
+            for (number, c, _) in cal_number_counts(board_of_game, mine_num, (i, j), &mut cache) {
+                ans[i][j][number as usize] = c.ratio(&total);
+            }
+            if let Some(k) = key {
+                interior_cache.insert(k, ans[i][j]);
+            }
+        }
+    }
+    Ok(ans)
+}
+
+/// 计算局面中各格开空（不是雷、且打开后是0）的概率，按照所在的位置返回。输入与[cal_number_distribution_onboard]相同。
+pub fn cal_op_possibility_onboard(
+    board_of_game: &Vec<Vec<i32>>,
+    mine_num: f64,
+) -> Result<Vec<Vec<f64>>, usize> {
+    let d = cal_number_distribution_onboard(board_of_game, mine_num)?;
+    Ok(d.iter().map(|r| r.iter().map(|x| x[0]).collect()).collect())
+}
+
+/// 计算局面中各格的期望信息增益，按照所在的位置返回。输入与[cal_number_distribution_onboard]相同。
+/// - 返回：以该格不是雷为前提，打开后显示的数字的熵，单位为比特。已打开的格子、标出的雷为0。
+pub fn cal_information_gain_onboard(
+    board_of_game: &Vec<Vec<i32>>,
+    mine_num: f64,
+) -> Result<Vec<Vec<f64>>, usize> {
+    let d = cal_number_distribution_onboard(board_of_game, mine_num)?;
+    Ok(d.iter()
+        .map(|r| {
+            r.iter()
+                .map(|x| {
+                    let safe: f64 = x.iter().sum();
+                    x.iter()
+                        .filter(|&&q| q > 0.0)
+                        .map(|&q| -q / safe * (q / safe).log2())
+                        .sum()
+                })
+                .collect()
+        })
+        .collect())
+}
+
 /// 计算开空概率算法。  
 /// - 输入：局面、未被标出的雷数、坐标（可以同时输入多个）。  
 /// - 返回：坐标处开空的概率。  
@@ -501,12 +743,12 @@ pub fn cal_is_op_possibility_cells(
         let mut board_of_game_modified = board_of_game.clone();
         'outer: for m in max(1, cell[0]) - 1..min(row, cell[0] + 2) {
             for n in max(1, cell[1]) - 1..min(column, cell[1] + 2) {
-                if (board_of_game[m][n] < 10 && m == cell[0] && n == cell[1])
-                    || board_of_game[m][n] == 11
+                if (board_of_game[m][n] < Cell::Covered.to_i32() && m == cell[0] && n == cell[1])
+                    || board_of_game[m][n] == Cell::Flag
                 {
                     poss[cell_id] = 0.0;
                     break 'outer;
-                } else if board_of_game[m][n] == 12 || board_of_game[m][n] < 10 {
+                } else if board_of_game[m][n] == Cell::Safe || board_of_game[m][n] < Cell::Covered.to_i32() {
                     continue;
                 } else {
                     let p;
@@ -518,7 +760,7 @@ pub fn cal_is_op_possibility_cells(
                         }
                     };
                     poss[cell_id] *= 1.0 - p[m][n];
-                    board_of_game_modified[m][n] = 12;
+                    board_of_game_modified[m][n] = Cell::Safe.to_i32();
                 }
             }
         }
@@ -572,24 +814,24 @@ pub fn solve_enumerate(
         matrixx_squeeze_s.push(matrixx_squeeze);
     }
     for i in 0..block_num {
-        let (table_mine_num_i, table_cell_mine_num_i) = cal_table_minenum_recursion(
+        let (_, table_mine_num_i, table_cell_mine_num_i) = cal_table_minenum_recursion_generic::<BigUint>(
             &matrixA_squeeze_s[i],
             &matrixx_squeeze_s[i],
             &bs[i],
             &comb_relp_s[i],
         )
         .unwrap();
+        let total = table_mine_num_i.iter().sum::<BigUint>();
         for jj in 0..table_cell_mine_num_i[0].len() {
-            let mut s_num = 0; // 该合成格子的总情况数
+            let mut s_num = BigUint::zero(); // 该合成格子的总情况数
             for ii in 0..table_cell_mine_num_i.len() {
-                s_num += table_cell_mine_num_i[ii][jj];
+                s_num += &table_cell_mine_num_i[ii][jj];
             }
-            if s_num == 0 {
+            if s_num.is_zero() {
                 for kk in &comb_relp_s[i][jj] {
                     not_mine.push(xs[i][*kk]);
                 }
-            } else if s_num == table_mine_num_i[1].iter().sum::<usize>() * comb_relp_s[i][jj].len()
-            {
+            } else if s_num == &total * comb_relp_s[i][jj].len() {
                 for kk in &comb_relp_s[i][jj] {
                     is_mine.push(xs[i][*kk]);
                 }
@@ -599,66 +841,181 @@ pub fn solve_enumerate(
     (not_mine, is_mine)
 }
 
-// 判断当前是否获胜
-// 游戏局面中必须没有标错的雷
-// 这个函数不具备普遍意义
-fn isVictory(board_of_game: &Vec<Vec<i32>>, Board: &Vec<Vec<i32>>) -> bool {
-    let row = board_of_game.len();
-    let col = board_of_game[0].len();
-    for i in 0..row {
-        for j in 0..col {
-            if board_of_game[i][j] == 10 && Board[i][j] != -1 {
-                return false;
+/// 考虑剩余雷数的判雷引擎。比枚举法多用了一个条件：雷的总数。例如剩余的雷必须都在边缘上时，
+/// 内部的格子都不是雷。  
+/// - 输入：分段好的矩阵、局面、总雷数（含局面上已标出的雷）。  
+/// - 输出：是雷、不是雷的位置，包括内部的格子。能用枚举法判出的格子也在其中。  
+/// - 注意：不修改输入进来的局面。与[solve_enumerate](#solve_enumerate)一样，段的长度超出枚举极限时不判。
+pub fn solve_global(
+    As: &Vec<Vec<Vec<i32>>>,
+    xs: &Vec<Vec<(usize, usize)>>,
+    bs: &Vec<Vec<i32>>,
+    board_of_game: &Vec<Vec<i32>>,
+    mine_num: usize,
+) -> (Vec<(usize, usize)>, Vec<(usize, usize)>) {
+    let mut not_mine = vec![];
+    let mut is_mine = vec![];
+    let mut interior = vec![];
+    let mut flag_num = 0;
+    for i in 0..board_of_game.len() {
+        for j in 0..board_of_game[0].len() {
+            if board_of_game[i][j] == Cell::Flag {
+                flag_num += 1;
+            } else if board_of_game[i][j] == Cell::Covered && !xs.iter().any(|x| x.contains(&(i, j))) {
+                interior.push((i, j));
+            }
+        }
+    }
+    let Some(mine_left) = mine_num.checked_sub(flag_num) else {
+        return (not_mine, is_mine);
+    };
+    // 每段可能的雷数，及每种雷数下的情况数表
+    let mut tables = vec![];
+    for i in 0..xs.len() {
+        if xs[i].len() > ENUM_LIMIT {
+            return (not_mine, is_mine);
+        }
+        let (matrix_a_squeeze, matrix_x_squeeze, comb_relp) = combine(&As[i], &xs[i]);
+        match cal_table_minenum_recursion_generic::<BigUint>(
+            &matrix_a_squeeze,
+            &matrix_x_squeeze,
+            &bs[i],
+            &comb_relp,
+        ) {
+            Ok((mine_nums, table_mine_num, table_cell_mine_num)) => {
+                tables.push((comb_relp, mine_nums, table_mine_num, table_cell_mine_num))
+            }
+            Err(_) => return (not_mine, is_mine),
+        }
+    }
+    // 给定的若干段的雷数之和能取到哪些值
+    let reachable_sums = |ids: &mut dyn Iterator<Item = usize>| {
+        let mut sums = vec![true];
+        for i in ids {
+            let mut t = vec![false; sums.len() + tables[i].1.last().unwrap()];
+            for (s, _) in sums.iter().enumerate().filter(|x| *x.1) {
+                for (k, n) in tables[i].1.iter().enumerate() {
+                    if !tables[i].2[k].is_zero() {
+                        t[s + n] = true;
+                    }
+                }
+            }
+            sums = t;
+        }
+        sums
+    };
+    let is_feasible = |sums: &[bool], n: usize| {
+        sums.iter()
+            .enumerate()
+            .any(|(s, &r)| r && s + n <= mine_left && mine_left - s - n <= interior.len())
+    };
+    for i in 0..tables.len() {
+        let sums = reachable_sums(&mut (0..tables.len()).filter(|&j| j != i));
+        let (comb_relp, mine_nums, table_mine_num, table_cell_mine_num) = &tables[i];
+        let feasible: Vec<usize> = (0..mine_nums.len())
+            .filter(|&k| !table_mine_num[k].is_zero() && is_feasible(&sums, mine_nums[k]))
+            .collect();
+        if feasible.is_empty() {
+            // 局面与雷数矛盾
+            return (vec![], vec![]);
+        }
+        for jj in 0..comb_relp.len() {
+            if feasible.iter().all(|&k| table_cell_mine_num[k][jj].is_zero()) {
+                not_mine.extend(comb_relp[jj].iter().map(|kk| xs[i][*kk]));
+            } else if feasible
+                .iter()
+                .all(|&k| table_cell_mine_num[k][jj] == &table_mine_num[k] * comb_relp[jj].len())
+            {
+                is_mine.extend(comb_relp[jj].iter().map(|kk| xs[i][*kk]));
             }
         }
     }
-    return true;
+    // 内部的雷数 = 剩余雷数 - 边缘的雷数
+    let sums = reachable_sums(&mut (0..tables.len()));
+    let interior_mine_nums: Vec<usize> = (0..sums.len())
+        .filter(|&s| sums[s] && s <= mine_left && mine_left - s <= interior.len())
+        .map(|s| mine_left - s)
+        .collect();
+    if interior_mine_nums.is_empty() {
+        return (vec![], vec![]);
+    }
+    if interior_mine_nums.iter().all(|&n| n == 0) {
+        not_mine.append(&mut interior);
+    } else if interior_mine_nums.iter().all(|&n| n == interior.len()) {
+        is_mine.append(&mut interior);
+    }
+    (not_mine, is_mine)
 }
 
 /// <span id="is_solvable">从指定位置开始扫，判断局面是否无猜。  
 /// - 注意：周围一圈都是雷，那么若中间是雷不算猜，若中间不是雷算有猜。  
-/// - 注意：不考虑剩余雷数。
+/// - 注意：不考虑剩余雷数。考虑剩余雷数的版本见[is_solvable_global](#is_solvable_global)。
 pub fn is_solvable(Board: &Vec<Vec<i32>>, x0: usize, y0: usize) -> bool {
     if unsolvable_structure(&Board) {
         //若包含不可判雷结构，则不是无猜
         return false;
     }
+    is_solvable_by(Board, x0, y0, false)
+}
+
+/// <span id="is_solvable_global">从指定位置开始扫，判断局面是否无猜，考虑剩余雷数。  
+/// - 注意：局部判不出时，再用[solve_global](#solve_global)结合雷的总数判雷，与人在残局中能做的推理一致。
+/// 因此比[is_solvable](#is_solvable)宽松，后者判为无猜的局面这里也判为无猜。
+pub fn is_solvable_global(Board: &Vec<Vec<i32>>, x0: usize, y0: usize) -> bool {
+    is_solvable_by(Board, x0, y0, true)
+}
+
+fn is_solvable_by(Board: &Vec<Vec<i32>>, x0: usize, y0: usize, use_mine_num: bool) -> bool {
     let row = Board.len();
     let column = Board[0].len();
-    let mut board_of_game = vec![vec![10; column]; row];
-    // 10是未打开，11是标雷
-    // 局面大小必须超过6*6
-    refresh_board(&Board, &mut board_of_game, vec![(x0, y0)]);
-    if isVictory(&board_of_game, &Board) {
-        return true; // 暂且认为点一下就扫开也是可以的
-    }
+    let mine_num = Board.iter().flatten().filter(|&&x| x == Cell::Mine).count();
+    // 用增量的约束系统，每轮只更新新打开的格子
+    let mut system = ConstraintSystem::new(row, column);
+    open_cells(&mut system, Board, vec![(x0, y0)]);
     loop {
-        let (mut As, mut xs, mut bs, _, _) = refresh_matrixs(&board_of_game);
-        let ans = solve_direct(&mut As, &mut xs, &mut bs, &mut board_of_game);
-        let mut not_mine;
+        if system.get_unrevealed_num() == mine_num {
+            return true; // 扫完。点一下就扫开也算无猜
+        }
+        let (not_mine, _) = system.solve_direct();
+        if !not_mine.is_empty() {
+            open_cells(&mut system, Board, not_mine);
+            continue;
+        }
+        let (mut As, mut xs, mut bs, _, _) = system.matrixs();
+        let mut board_of_game = system.game_board();
+        let mut ans = solve_minus(&mut As, &mut xs, &mut bs, &mut board_of_game);
         if ans.0.is_empty() && ans.1.is_empty() {
-            let ans = solve_minus(&mut As, &mut xs, &mut bs, &mut board_of_game);
+            ans = solve_enumerate(&As, &xs, &bs);
+            if ans.0.is_empty() && ans.1.is_empty() && use_mine_num {
+                ans = solve_global(&As, &xs, &bs, &board_of_game, mine_num);
+            }
             if ans.0.is_empty() && ans.1.is_empty() {
-                let ans = solve_enumerate(&As, &xs, &bs);
-                if ans.0.is_empty() && ans.1.is_empty() {
-                    return false;
-                } else {
-                    not_mine = ans.0
-                }
-                if !ans.1.is_empty() {
-                    for (o, p) in ans.1 {
-                        board_of_game[o][p] = 11;
-                    }
-                }
-            } else {
-                not_mine = ans.0
+                return false;
             }
-        } else {
-            not_mine = ans.0
         }
-        refresh_board(&Board, &mut board_of_game, not_mine);
-        if isVictory(&board_of_game, &Board) {
-            return true;
+        for cell in ans.1 {
+            system.flag(cell);
+        }
+        open_cells(&mut system, Board, ans.0);
+    }
+}
+
+/// 在约束系统上打开一些非雷，0会连锁打开
+pub(crate) fn open_cells(
+    system: &mut ConstraintSystem,
+    board: &[Vec<i32>],
+    mut cells: Vec<(usize, usize)>,
+) {
+    let row = board.len();
+    let column = board[0].len();
+    while let Some((x, y)) = cells.pop() {
+        let v = system.get((x, y));
+        if v != Cell::Covered && v != Cell::Safe {
+            continue;
+        }
+        system.reveal((x, y), board[x][y]);
+        if board[x][y] == 0 {
+            cells.extend(neighbours(row, column, x, y));
         }
     }
 }
@@ -680,59 +1037,151 @@ pub fn laymine_solvable_thread(
     mine_num: usize,
     x0: usize,
     y0: usize,
-    mut max_times: usize,
+    max_times: usize,
 ) -> (Vec<Vec<i32>>, bool) {
-    let mut game_board = vec![vec![0; column]; row];
-    let mut handles = vec![];
-    let flag_exit = Arc::new(Mutex::new(0));
-    let (tx, rx) = mpsc::channel(); // mpsc 是多个发送者，一个接收者
-    for ii in (1..9).rev() {
-        let tx_ = mpsc::Sender::clone(&tx);
-        let max_time = max_times / ii;
-        max_times -= max_time;
-        let flag_exit = Arc::clone(&flag_exit);
-        let handle = thread::spawn(move || {
-            let mut Num3BV;
-            let mut counter = 0;
-            let mut Board = vec![vec![0; column]; row];
-            // let mut para = [0, 0, 0];
-            while counter < max_time {
-                {
-                    let f = flag_exit.lock().unwrap();
-                    if *f == 1 {
-                        break;
-                    }
-                } // 这段用花括号控制生命周期
-                let Board_ = laymine_op(row, column, mine_num, x0, y0);
-                counter += 1;
-                if is_solvable(&Board_, x0, y0) {
-                    for i in 0..row {
-                        for j in 0..column {
-                            Board[i][j] = Board_[i][j];
-                        }
-                    }
-                    let mut f = flag_exit.lock().unwrap();
-                    *f = 1;
-                    tx_.send((Board, true)).unwrap();
-                    break;
-                }
+    laymine_solvable_thread_seed(row, column, mine_num, x0, y0, max_times, random_seed())
+}
+
+/// 批量生成满足条件的局面，直接编码为mbf文件的二进制数据，用于制作UPK练习的局面集。  
+/// - 输入：高、宽、雷数、起手行数、起手列数、局面数、3BV的范围（闭区间）、是否要求无猜。  
+/// - 返回：每个局面的mbf二进制数据。  
+/// - 注意：每次调用生成的局面集都不同，要复现同一个局面集请用[laymine_mbf_boards_seed]。
+/// - 用python调用时的示例：
+/// ```python
+/// import ms_toollib as ms
+/// boards = ms.laymine_mbf_boards(16, 30, 99, 0, 0, 100, (130, 150), True)
+/// ```
+#[allow(clippy::too_many_arguments)]
+pub fn laymine_mbf_boards(
+    row: usize,
+    column: usize,
+    mine_num: usize,
+    x0: usize,
+    y0: usize,
+    n: usize,
+    bbbv_range: (usize, usize),
+    solvable: bool,
+) -> Vec<Vec<u8>> {
+    laymine_mbf_boards_seed(
+        row,
+        column,
+        mine_num,
+        x0,
+        y0,
+        n,
+        bbbv_range,
+        solvable,
+        random_seed(),
+    )
+}
+
+/// 可设种子的批量生成mbf局面。相同的种子与参数，在各平台生成完全相同的局面集。  
+/// - 输入：高、宽、雷数、起手行数、起手列数、局面数、3BV的范围（闭区间）、是否要求无猜、种子。  
+/// - 返回：每个局面的mbf二进制数据。  
+/// - 注意：要求无猜时，起手位置必为空（op）。每个局面最多尝试100000次，条件过于苛刻时返回的局面数可能少于要求的数目。
+/// - 用python调用时的示例：
+/// ```python
+/// import ms_toollib as ms
+/// boards = ms.laymine_mbf_boards(16, 30, 99, 0, 0, 100, (130, 150), True, 2022)
+/// for (i, raw) in enumerate(boards):
+///     with open(f"upk_{i}.mbf", "wb") as f:
+///         f.write(bytes(raw))
+/// ```
+#[allow(clippy::too_many_arguments)]
+pub fn laymine_mbf_boards_seed(
+    row: usize,
+    column: usize,
+    mine_num: usize,
+    x0: usize,
+    y0: usize,
+    n: usize,
+    bbbv_range: (usize, usize),
+    solvable: bool,
+    seed: u64,
+) -> Vec<Vec<u8>> {
+    let mut rng = SeededRng::new(seed);
+    let mut boards = vec![];
+    'outer: for _ in 0..n {
+        for _ in 0..100000 {
+            let board = if solvable {
+                laymine_op_rng(row, column, mine_num, x0, y0, &mut rng)
+            } else {
+                laymine_rng(row, column, mine_num, x0, y0, &mut rng)
+            };
+            let bbbv = cal_bbbv(&board);
+            if bbbv < bbbv_range.0 || bbbv > bbbv_range.1 {
+                continue;
             }
-            let Board_ = laymine_op(row, column, mine_num, x0, y0);
-            Num3BV = cal_bbbv(&Board_);
-            tx_.send((Board_, false)).unwrap();
-        });
-        handles.push(handle);
-    }
-    for handle in handles {
-        handle.join().unwrap();
-    }
-    let received = rx.recv().unwrap(); // 尝试次数仅仅为单个线程的次数，并不准
-    for i in 0..row {
-        for j in 0..column {
-            game_board[i][j] = received.0[i][j];
+            if solvable && !is_solvable(&board, x0, y0) {
+                continue;
+            }
+            match encode_mbf(&board) {
+                Ok(raw_data) => boards.push(raw_data),
+                Err(_) => break 'outer,
+            }
+            continue 'outer;
         }
+        break;
+    }
+    boards
+}
+
+/// <span id="laymine_solvable_thread_seed">可设种子的删选法多（8）线程无猜埋雷。  
+/// - 输入：高、宽、雷数、起手行数、起手列数、最大尝试次数、种子。  
+/// - 返回：局面、是否成功。  
+/// - 注意：各线程分摊尝试的编号，最终取成功的尝试中编号最小的一个，因此结果与线程的调度无关，与[laymine_solvable_seed](#laymine_solvable_seed)完全相同。
+#[cfg(any(feature = "py", feature = "rs"))]
+pub fn laymine_solvable_thread_seed(
+    row: usize,
+    column: usize,
+    mine_num: usize,
+    x0: usize,
+    y0: usize,
+    max_times: usize,
+    seed: u64,
+) -> (Vec<Vec<i32>>, bool) {
+    let options = ExecOptions {
+        thread_num: 8,
+        ..ExecOptions::default()
+    };
+    let (board, flag, _) =
+        laymine_solvable_exec(row, column, mine_num, x0, y0, max_times, seed, &options);
+    (board, flag)
+}
+
+/// 可设种子、可设执行选项的删选法无猜埋雷。  
+/// - 输入：高、宽、雷数、起手行数、起手列数、最大尝试次数、种子、执行选项。  
+/// - 返回：局面、是否成功、结束的状态。进度的总工作量为最大尝试次数。  
+/// - 注意：没有被取消或超时时，结果与线程数无关，与[laymine_solvable_seed](#laymine_solvable_seed)完全相同。
+/// 被取消或超时时，若已找到无猜的局面则仍然返回成功，但不保证与laymine_solvable_seed相同。
+#[allow(clippy::too_many_arguments)]
+pub fn laymine_solvable_exec(
+    row: usize,
+    column: usize,
+    mine_num: usize,
+    x0: usize,
+    y0: usize,
+    max_times: usize,
+    seed: u64,
+    options: &ExecOptions,
+) -> (Vec<Vec<i32>>, bool, ExecStatus) {
+    let attempt = |times: usize| {
+        laymine_op_rng(
+            row,
+            column,
+            mine_num,
+            x0,
+            y0,
+            &mut SeededRng::new_stream(seed, times as u64),
+        )
+    };
+    let (times, status) =
+        exec_find_first(options, max_times, |times| is_solvable(&attempt(times), x0, y0));
+    // 编号比成功编号小的尝试都已经失败，重新生成一次成功的局面即可
+    match times {
+        Some(times) => (attempt(times), true, status),
+        None => (attempt(max_times), false, status),
     }
-    (game_board, received.1)
 }
 
 /// 删选法单线程无猜埋雷。不可以生成任意雷密度的无猜局面。但雷满足均匀分布。  
@@ -765,6 +1214,48 @@ pub fn laymine_solvable(
     (Board, false)
 }
 
+/// 可设种子的删选法单线程无猜埋雷。相同的种子与参数，在各平台埋出完全相同的局面。  
+/// - 输入：高、宽、雷数、起手行数、起手列数、最大尝试次数、种子。  
+/// - 返回：局面、是否成功。  
+/// - 注意：第i次尝试使用种子的第i个流，因此结果与[laymine_solvable_thread_seed](#laymine_solvable_thread_seed)完全相同。
+/// - 用python调用时的示例：
+/// ```python
+/// import ms_toollib as ms
+/// (board, flag) = ms.laymine_solvable_seed(16, 30, 99, 3, 20, 100000, 20230101)
+/// ```
+pub fn laymine_solvable_seed(
+    row: usize,
+    column: usize,
+    mine_num: usize,
+    x0: usize,
+    y0: usize,
+    max_times: usize,
+    seed: u64,
+) -> (Vec<Vec<i32>>, bool) {
+    for times in 0..max_times {
+        let board = laymine_op_rng(
+            row,
+            column,
+            mine_num,
+            x0,
+            y0,
+            &mut SeededRng::new_stream(seed, times as u64),
+        );
+        if is_solvable(&board, x0, y0) {
+            return (board, true);
+        }
+    }
+    let board = laymine_op_rng(
+        row,
+        column,
+        mine_num,
+        x0,
+        y0,
+        &mut SeededRng::new_stream(seed, max_times as u64),
+    );
+    (board, false)
+}
+
 /// 调整法无猜埋雷。可以生成任意雷密度的无猜局面。但雷不满足均匀分布。  
 /// - 输入：高、宽、雷数、起手行数、起手列数  
 /// - 返回局面、是否成功  
@@ -781,8 +1272,23 @@ pub fn laymine_solvable_adjust(
     mine_num: usize,
     x0: usize,
     y0: usize,
+) -> (Vec<Vec<i32>>, bool) {
+    laymine_solvable_adjust_seed(row, column, mine_num, x0, y0, random_seed())
+}
+
+/// 可设种子的调整法无猜埋雷。相同的种子与参数，在各平台埋出完全相同的局面。  
+/// - 输入：高、宽、雷数、起手行数、起手列数、种子  
+/// - 返回局面、是否成功  
+pub fn laymine_solvable_adjust_seed(
+    row: usize,
+    column: usize,
+    mine_num: usize,
+    x0: usize,
+    y0: usize,
+    seed: u64,
 ) -> (Vec<Vec<i32>>, bool) {
     // 利用局面调整算法，无猜埋雷
+    let mut rng = SeededRng::new(seed);
     let mut board;
     let mut area_op = 9;
     if x0 == 0 || y0 == 0 || x0 == row - 1 || y0 == column - 1 {
@@ -798,7 +1304,7 @@ pub fn laymine_solvable_adjust(
     }
     if row * column - area_op < mine_num {
         // 雷数太多以致起手无法开空，此时放弃无猜，返回任意一种局面
-        let t = laymine(row, column, mine_num, x0, y0);
+        let t = laymine_rng(row, column, mine_num, x0, y0, &mut rng);
         if row * column - mine_num == 1 {
             return (t, true);
         } else {
@@ -806,11 +1312,11 @@ pub fn laymine_solvable_adjust(
         }
     }
     if row * column == area_op + mine_num {
-        return (laymine_op(row, column, mine_num, x0, y0), true);
+        return (laymine_op_rng(row, column, mine_num, x0, y0, &mut rng), true);
     }
 
     board = vec![vec![-10; column]; row];
-    let mut board_of_game = vec![vec![10; column]; row];
+    let mut board_of_game = vec![vec![Cell::Covered.to_i32(); column]; row];
     board_of_game[x0][y0] = 0;
     let remain_mine_num = mine_num;
     let remain_not_mine_num = row * column - area_op - mine_num;
@@ -831,6 +1337,7 @@ pub fn laymine_solvable_adjust(
         &cells_plan_to_click,
         remain_mine_num,
         remain_not_mine_num,
+        &mut rng,
     );
     // println!("+++++");
     // b.iter().for_each(|i| {
@@ -839,7 +1346,7 @@ pub fn laymine_solvable_adjust(
     //     println!("")
     // });
     if !flag || b.is_empty() {
-        return (laymine_op(row, column, mine_num, x0, y0), false);
+        return (laymine_op_rng(row, column, mine_num, x0, y0, &mut rng), false);
     }
     for i in 0..row {
         for j in 0..column {
@@ -851,7 +1358,7 @@ pub fn laymine_solvable_adjust(
     // 最后，算数字
     for i in 0..row {
         for j in 0..column {
-            if b[i][j] == -1 {
+            if b[i][j] == Cell::Mine {
                 for m in max(1, i) - 1..min(row, i + 2) {
                     for n in max(1, j) - 1..min(column, j + 2) {
                         if b[m][n] >= 0 {
@@ -879,6 +1386,7 @@ fn adjust_step(
     plan_click: &Vec<(usize, usize)>, // 当前计划点开的格子，递归部分要保证点开后，局面是有解开的可能的
     remain_mine_num: usize,           // 当前还要埋的雷数
     remain_not_mine_num: usize,       // 当前还要埋的非雷数
+    rng: &mut SeededRng,
 ) -> (Vec<Vec<i32>>, bool) {
     let mut b = board.clone(); // 克隆一个board的备份
     let mut bg = board_of_game.clone(); // 克隆一个board_of_game的备份
@@ -961,7 +1469,7 @@ fn adjust_step(
         }
         // 对每种雷数，重复尝试5次。
         for u in 0..3 {
-            adjust_the_area_on_board(&mut b, &front_xs_0, mine_num);
+            adjust_the_area_on_board(&mut b, &front_xs_0, mine_num, rng);
             // 以下的循环用来修正b向量
             for bb in 0..bs_0.len() {
                 for ss in 0..bs_0[bb].len() {
@@ -970,8 +1478,8 @@ fn adjust_step(
                     for aa in 0..As_0[bb][0].len() {
                         // aa是第几个格子
                         if As_0[bb][ss][aa] == 1 {
-                            if b[xs_0[bb][aa].0][xs_0[bb][aa].1] == -1
-                                && bg[xs_0[bb][aa].0][xs_0[bb][aa].1] != 11
+                            if b[xs_0[bb][aa].0][xs_0[bb][aa].1] == Cell::Mine
+                                && bg[xs_0[bb][aa].0][xs_0[bb][aa].1] != Cell::Flag
                             {
                                 bs_0[bb][ss] += 1;
                             }
@@ -985,11 +1493,11 @@ fn adjust_step(
                 &mut bs_0.clone(),
                 &mut bg,
             );
-            i.iter().for_each(|x| bg[x.0][x.1] = 10); // get_all_not_and_is_mine_on_board是修改局面的，修回来
+            i.iter().for_each(|x| bg[x.0][x.1] = Cell::Covered.to_i32()); // get_all_not_and_is_mine_on_board是修改局面的，修回来
 
             if n.len() > 0 {
                 n.iter().for_each(|x| bg[x.0][x.1] = 1);
-                i.iter().for_each(|x| bg[x.0][x.1] = 11);
+                i.iter().for_each(|x| bg[x.0][x.1] = Cell::Flag.to_i32());
                 // println!("当前步骤成功！");
                 // success_flag = true; // 当前步骤成功
                 if rn <= n.len() {
@@ -998,7 +1506,7 @@ fn adjust_step(
                 // pc.append(&mut n);
                 r -= mine_num;
                 rn -= xs_cell_num - mine_num;
-                let a = adjust_step(&b, &bg, &n, r, rn);
+                let a = adjust_step(&b, &bg, &n, r, rn, rng);
                 if a.1 {
                     if !a.0.is_empty() {
                         return (a.0, true);
@@ -1022,21 +1530,14 @@ fn adjust_the_area_on_board(
     board: &mut Vec<Vec<i32>>,
     area_current_adjust: &Vec<Vec<(usize, usize)>>,
     mine_num: usize,
+    rng: &mut SeededRng,
 ) {
     // let row = board.len();
     // let column = board[0].len();
     let cell_num = area_current_adjust.iter().fold(0, |acc, x| acc + x.len());
     let mut b = vec![0; cell_num - mine_num];
     b.append(&mut vec![-1; mine_num]);
-
-    #[cfg(any(feature = "py", feature = "rs"))]
-    let mut rng = thread_rng();
-    // let mut rng = StdRng::seed_from_u64(532);
-    #[cfg(any(feature = "py", feature = "rs"))]
-    b.shuffle(&mut rng);
-
-    #[cfg(feature = "js")]
-    b.shuffle_();
+    rng.shuffle(&mut b);
 
     let mut id = 0;
     for i in area_current_adjust {
@@ -1050,36 +1551,73 @@ fn adjust_the_area_on_board(
 /// 埋雷并计算高级局面3BV的引擎，用于研究高级3BV的分布。16线程。传入局数，例如1000 000。试一下你的电脑算的有多块吧。  
 #[cfg(any(feature = "py", feature = "rs"))]
 pub fn sample_3BVs_exp(x0: usize, y0: usize, n: usize) -> [usize; 382] {
+    sample_3BVs_exp_seed(x0, y0, n, random_seed())
+}
+
+/// 可设种子的埋雷并计算高级局面3BV的引擎。16线程，每个线程使用种子的一个流，因此结果可复现。  
+#[cfg(any(feature = "py", feature = "rs"))]
+pub fn sample_3BVs_exp_seed(x0: usize, y0: usize, n: usize, seed: u64) -> [usize; 382] {
+    let options = ExecOptions {
+        thread_num: 16,
+        ..ExecOptions::default()
+    };
+    sample_3BVs_exp_exec(x0, y0, n, seed, &options).0
+}
+
+/// 可设执行选项的埋雷并计算高级局面3BV的引擎。  
+/// - 输入：起手行数、起手列数、局数、种子、执行选项。  
+/// - 返回：各3BV的局数、结束的状态。进度的总工作量为局数。  
+/// - 注意：局数分成16份，每份使用种子的一个流，因此没有被取消或超时时，结果与线程数无关，与[sample_3BVs_exp_seed]相同。
+/// 被取消或超时时，返回已经抽样的部分，各3BV的局数之和小于局数。
+#[cfg(any(feature = "py", feature = "rs"))]
+pub fn sample_3BVs_exp_exec(
+    x0: usize,
+    y0: usize,
+    n: usize,
+    seed: u64,
+    options: &ExecOptions,
+) -> ([usize; 382], ExecStatus) {
     // 从标准高级中采样计算3BV
-    // 16线程计算
     let n0 = n / 16;
-    let mut threads = vec![];
-    for i in 0..16 {
-        let join_item = thread::spawn(move || -> [usize; 382] { laymine_study_exp(x0, y0, n0) });
-        threads.push(join_item);
-    }
+    let (results, status) = exec_map(options, 16, n0 * 16, |i, ctx| {
+        let mut rng = SeededRng::new_stream(seed, i as u64);
+        laymine_study_exp(x0, y0, n0, &mut rng, ctx)
+    });
     let mut aa = [0; 382];
-    for i in threads.into_iter().map(|c| c.join().unwrap()) {
+    for i in results.into_iter().flatten() {
         for ii in 0..382 {
             aa[ii] += i[ii];
         }
     }
-    aa
+    (aa, status)
 }
 
 #[cfg(any(feature = "py", feature = "rs"))]
-fn laymine_study_exp(x0: usize, y0: usize, n: usize) -> [usize; 382] {
-    let mut rng = thread_rng();
+fn laymine_study_exp(
+    x0: usize,
+    y0: usize,
+    n: usize,
+    rng: &mut SeededRng,
+    ctx: &ExecContext,
+) -> [usize; 382] {
     // let area: usize = 16 * 30 - 1;
     let pointer = x0 + y0 * 16;
     let mut bv_record = [0; 382];
+    let mut bit_board = BitBoard::new(16, 30);
     for id in 0..n {
+        // 每1024局报告一次进度，检查一次是否应该停下
+        if id % 1024 == 0 && id > 0 {
+            ctx.advance(1024);
+            if ctx.is_stopped() {
+                return bv_record;
+            }
+        }
         let mut Board1Dim = [0; 479];
         for i in 380..479 {
             Board1Dim[i] = -1;
         }
 
-        Board1Dim.shuffle(&mut rng);
+        rng.shuffle(&mut Board1Dim);
         let mut Board1Dim_2 = [0; 480];
         // Board1Dim_2.reserve(area + 1);
 
@@ -1090,23 +1628,15 @@ fn laymine_study_exp(x0: usize, y0: usize, n: usize) -> [usize; 382] {
         for i in pointer..479 {
             Board1Dim_2[i + 1] = Board1Dim[i];
         }
-        let mut Board: Vec<Vec<i32>> = vec![vec![0; 30]; 16];
+        bit_board.clear();
         for i in 0..480 {
             if Board1Dim_2[i] < 0 {
-                let x = i % 16;
-                let y = i / 16;
-                Board[x][y] = -1;
-                for j in max(1, x) - 1..min(16, x + 2) {
-                    for k in max(1, y) - 1..min(30, y + 2) {
-                        if Board[j][k] >= 0 {
-                            Board[j][k] += 1;
-                        }
-                    }
-                }
+                bit_board.set_mine((i % 16, i / 16));
             }
         }
-        bv_record[cal_bbbv_exp(&Board)] += 1;
+        bv_record[bit_board.cal_bbbv()] += 1;
     }
+    ctx.advance(n - n.saturating_sub(1) / 1024 * 1024);
     bv_record
 }
 
@@ -1145,8 +1675,8 @@ fn OBR_cell(
         7 => Ok(6),
         8 => Ok(7),
         9 => Ok(8),
-        10 => Ok(10),
-        _ => Ok(11),
+        10 => Ok(Cell::Covered.to_i32()),
+        _ => Ok(Cell::Flag.to_i32()),
     }
 }
 
@@ -1259,6 +1789,26 @@ pub fn mark_board(board_of_game: &mut Vec<Vec<i32>>) {
     solve_minus(&mut As, &mut xs, &mut bs, board_of_game);
 }
 
+/// 在[mark_board](#mark_board)的基础上，再结合雷的总数标雷、标非雷，直到标不出新的格子。  
+/// - 输入：游戏局面、总雷数（含已标出的雷）。局面中的标雷必须正确。  
+/// - 注意：与mark_board不同，这里也会用枚举法，因此较慢。内部的格子判出后同样标成11或12。
+pub fn mark_board_global(board_of_game: &mut Vec<Vec<i32>>, mine_num: usize) {
+    loop {
+        mark_board(board_of_game);
+        let (As, xs, bs, _, _) = refresh_matrixs(&board_of_game);
+        let (not_mine, is_mine) = solve_global(&As, &xs, &bs, board_of_game, mine_num);
+        if not_mine.is_empty() && is_mine.is_empty() {
+            break;
+        }
+        for (x, y) in not_mine {
+            board_of_game[x][y] = Cell::Safe.to_i32();
+        }
+        for (x, y) in is_mine {
+            board_of_game[x][y] = Cell::Flag.to_i32();
+        }
+    }
+}
+
 /// 求出游戏局面中所有非雷、是雷的位置。  
 /// - 注意：局面中可以有标雷，但不能有错误！
 pub fn get_all_not_and_is_mine_on_board(
@@ -1292,12 +1842,12 @@ pub fn get_all_not_and_is_mine_on_board(
 pub fn is_guess_while_needless(board_of_game: &mut Vec<Vec<i32>>, xy: &(usize, usize)) -> i32 {
     board_of_game.iter_mut().for_each(|x| {
         x.iter_mut().for_each(|xx| {
-            if *xx > 10 {
-                *xx = 10
+            if *xx > Cell::Covered.to_i32() {
+                *xx = Cell::Covered.to_i32()
             }
         })
     });
-    if board_of_game[xy.0][xy.1] < 10 {
+    if board_of_game[xy.0][xy.1] < Cell::Covered.to_i32() {
         return 5;
     }
     let mut flag_need = true;
@@ -1315,18 +1865,18 @@ pub fn is_guess_while_needless(board_of_game: &mut Vec<Vec<i32>>, xy: &(usize, u
             return 3;
         }
         flag_need = n.is_empty();
-        match board_of_game[xy.0][xy.1] {
-            12 => return 1,
-            11 => return 4,
+        match Cell::from_i32(board_of_game[xy.0][xy.1]) {
+            Some(Cell::Safe) => return 1,
+            Some(Cell::Flag) => return 4,
             _ => {
                 let (n, _) = solve_minus(As, xs, bs, board_of_game);
                 if !flag_border && !n.is_empty() {
                     return 3;
                 }
                 flag_need = flag_need && n.is_empty();
-                match board_of_game[xy.0][xy.1] {
-                    12 => return 1,
-                    11 => return 4,
+                match Cell::from_i32(board_of_game[xy.0][xy.1]) {
+                    Some(Cell::Safe) => return 1,
+                    Some(Cell::Flag) => return 4,
                     _ => {
                         let (n, i) = solve_enumerate(As, xs, bs);
                         if !flag_border && !n.is_empty() {
@@ -1358,18 +1908,18 @@ pub fn is_guess_while_needless(board_of_game: &mut Vec<Vec<i32>>, xy: &(usize, u
 pub fn is_able_to_solve(board_of_game: &mut Vec<Vec<i32>>, xy: &(usize, usize)) -> bool {
     board_of_game.iter_mut().for_each(|x| {
         x.iter_mut().for_each(|xx| {
-            if *xx > 10 {
-                *xx = 10
+            if *xx > Cell::Covered.to_i32() {
+                *xx = Cell::Covered.to_i32()
             }
         })
     });
     let (mut As, mut xs, mut bs, _, _) = refresh_matrixs(&board_of_game);
     solve_direct(&mut As, &mut xs, &mut bs, board_of_game);
-    if board_of_game[xy.0][xy.1] == 11 || board_of_game[xy.0][xy.1] == 12 {
+    if board_of_game[xy.0][xy.1] == Cell::Flag || board_of_game[xy.0][xy.1] == Cell::Safe {
         return true;
     }
     solve_minus(&mut As, &mut xs, &mut bs, board_of_game);
-    if board_of_game[xy.0][xy.1] == 11 || board_of_game[xy.0][xy.1] == 12 {
+    if board_of_game[xy.0][xy.1] == Cell::Flag || board_of_game[xy.0][xy.1] == Cell::Safe {
         return true;
     }
     let (n, i) = solve_enumerate(&As, &xs, &bs);
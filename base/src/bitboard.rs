@@ -0,0 +1,269 @@
+// 按位压缩的局面：每行的雷用连续的u64按位存放，数字用位运算的加法器整行地算，
+// 空、岛用按行分段的并查集计数。用于大量抽样局面时快速计算3BV、op、isl等指标
+
+/// 按位压缩的局面，只存雷的位置。
+/// - 用途：抽样大量局面、统计指标时，代替Vec<Vec<i32>>和cal_bbbv、cal_op、cal_isl、cal_cell_nums，
+///   没有逐格的分配与递归。结果与这些函数完全相同。
+/// - 用法示例：
+/// ```rust
+/// use ms_toollib::{BitBoard, cal_bbbv, laymine_seed};
+/// let board = laymine_seed(16, 30, 99, 0, 0, 7);
+/// let bit_board = BitBoard::from_board(&board);
+/// assert_eq!(bit_board.cal_bbbv(), cal_bbbv(&board));
+/// assert_eq!(bit_board.to_board(), board);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BitBoard {
+    row: usize,
+    column: usize,
+    /// 每行占用的u64的个数
+    words: usize,
+    /// 第i行第j列是雷，即mines[i * words + j / 64]的第j % 64位为1
+    mines: Vec<u64>,
+}
+
+impl BitBoard {
+    /// 没有雷的局面
+    pub fn new(row: usize, column: usize) -> BitBoard {
+        let words = column.div_ceil(64);
+        BitBoard {
+            row,
+            column,
+            words,
+            mines: vec![0; row * words],
+        }
+    }
+    /// 从真实局面构造，只看雷（-1）的位置
+    pub fn from_board(board: &[Vec<i32>]) -> BitBoard {
+        let mut bit_board = BitBoard::new(board.len(), board[0].len());
+        for (i, r) in board.iter().enumerate() {
+            for (j, &v) in r.iter().enumerate() {
+                if v < 0 {
+                    bit_board.set_mine((i, j));
+                }
+            }
+        }
+        bit_board
+    }
+    pub fn get_row(&self) -> usize {
+        self.row
+    }
+    pub fn get_column(&self) -> usize {
+        self.column
+    }
+    pub fn set_mine(&mut self, pos: (usize, usize)) {
+        self.mines[pos.0 * self.words + pos.1 / 64] |= 1 << (pos.1 % 64);
+    }
+    pub fn remove_mine(&mut self, pos: (usize, usize)) {
+        self.mines[pos.0 * self.words + pos.1 / 64] &= !(1 << (pos.1 % 64));
+    }
+    pub fn is_mine(&self, pos: (usize, usize)) -> bool {
+        self.mines[pos.0 * self.words + pos.1 / 64] >> (pos.1 % 64) & 1 == 1
+    }
+    /// 清空所有的雷，以便重复使用内存
+    pub fn clear(&mut self) {
+        self.mines.iter_mut().for_each(|w| *w = 0);
+    }
+    pub fn get_mine_num(&self) -> usize {
+        self.mines.iter().map(|w| w.count_ones() as usize).sum()
+    }
+    /// 转回真实局面，-1是雷，0到8是数字
+    pub fn to_board(&self) -> Vec<Vec<i32>> {
+        let mut board = vec![vec![0; self.column]; self.row];
+        for (i, r) in board.iter_mut().enumerate() {
+            for k in 0..self.words {
+                let planes = self.cal_number_planes(i, k);
+                for (j, cell) in r.iter_mut().enumerate().skip(k * 64).take(64) {
+                    let b = j % 64;
+                    *cell = if self.is_mine((i, j)) {
+                        -1
+                    } else {
+                        (0..4).map(|p| ((planes[p] >> b & 1) << p) as i32).sum()
+                    };
+                }
+            }
+        }
+        board
+    }
+    /// 计算局面的3BV，与[cal_bbbv](crate::cal_bbbv)相同
+    pub fn cal_bbbv(&self) -> usize {
+        let zero = self.cal_zero_mask();
+        let island = self.cal_island_mask(&zero);
+        self.count_components(&zero)
+            + island
+                .iter()
+                .map(|w| w.count_ones() as usize)
+                .sum::<usize>()
+    }
+    /// 计算空，即0的8连通域数，与[cal_op](crate::cal_op)相同
+    pub fn cal_op(&self) -> usize {
+        self.count_components(&self.cal_zero_mask())
+    }
+    /// 计算岛，与[cal_isl](crate::cal_isl)相同
+    pub fn cal_isl(&self) -> usize {
+        let zero = self.cal_zero_mask();
+        self.count_components(&self.cal_island_mask(&zero))
+    }
+    /// 计算每个数字出现的次数，与[cal_cell_nums](crate::cal_cell_nums)相同
+    pub fn cal_cell_nums(&self) -> [usize; 9] {
+        let mut ans = [0; 9];
+        for r in 0..self.row {
+            for k in 0..self.words {
+                let [p0, p1, p2, p3] = self.cal_number_planes(r, k);
+                let safe = !self.mines[r * self.words + k] & self.valid_mask(k);
+                // 周围雷数不超过8，p3为1时只能是8
+                let low = safe & !p3;
+                ans[8] += (safe & p3).count_ones() as usize;
+                ans[0] += (low & !p2 & !p1 & !p0).count_ones() as usize;
+                ans[1] += (low & !p2 & !p1 & p0).count_ones() as usize;
+                ans[2] += (low & !p2 & p1 & !p0).count_ones() as usize;
+                ans[3] += (low & !p2 & p1 & p0).count_ones() as usize;
+                ans[4] += (low & p2 & !p1 & !p0).count_ones() as usize;
+                ans[5] += (low & p2 & !p1 & p0).count_ones() as usize;
+                ans[6] += (low & p2 & p1 & !p0).count_ones() as usize;
+                ans[7] += (low & p2 & p1 & p0).count_ones() as usize;
+            }
+        }
+        ans
+    }
+    /// 每行第k个u64中属于局面的位。只有每行的最后一个u64可能不满
+    fn valid_mask(&self, k: usize) -> u64 {
+        let rest = self.column - k * 64;
+        if rest >= 64 {
+            u64::MAX
+        } else {
+            (1 << rest) - 1
+        }
+    }
+    /// 一行的第k个u64向列号大的方向移一位
+    fn shift_up(line: &[u64], k: usize) -> u64 {
+        let carry = if k > 0 { line[k - 1] >> 63 } else { 0 };
+        line[k] << 1 | carry
+    }
+    /// 一行的第k个u64向列号小的方向移一位
+    fn shift_down(line: &[u64], k: usize) -> u64 {
+        let carry = if k + 1 < line.len() {
+            line[k + 1] << 63
+        } else {
+            0
+        };
+        line[k] >> 1 | carry
+    }
+    /// 第r行第k个u64上周围雷数的4个二进制位。用逐位的加法器把周围8个方向的雷加起来
+    fn cal_number_planes(&self, r: usize, k: usize) -> [u64; 4] {
+        let w = self.words;
+        let mut planes = [0u64; 4];
+        let mut add = |mut carry: u64| {
+            for plane in planes.iter_mut() {
+                let t = *plane;
+                *plane = t ^ carry;
+                carry &= t;
+            }
+        };
+        for s in r.saturating_sub(1)..(r + 2).min(self.row) {
+            let line = &self.mines[s * w..(s + 1) * w];
+            add(Self::shift_up(line, k));
+            add(Self::shift_down(line, k));
+            if s != r {
+                add(line[k]);
+            }
+        }
+        planes
+    }
+    /// 0所在的位置
+    fn cal_zero_mask(&self) -> Vec<u64> {
+        let mut zero = Vec::with_capacity(self.mines.len());
+        for r in 0..self.row {
+            for k in 0..self.words {
+                let [p0, p1, p2, p3] = self.cal_number_planes(r, k);
+                zero.push(
+                    !self.mines[r * self.words + k] & !(p0 | p1 | p2 | p3) & self.valid_mask(k),
+                );
+            }
+        }
+        zero
+    }
+    /// 岛上的数字，即周围没有0的数字所在的位置
+    fn cal_island_mask(&self, zero: &[u64]) -> Vec<u64> {
+        let w = self.words;
+        let mut island = Vec::with_capacity(self.mines.len());
+        for r in 0..self.row {
+            for k in 0..w {
+                let mut near_zero = 0;
+                for s in r.saturating_sub(1)..(r + 2).min(self.row) {
+                    let line = &zero[s * w..(s + 1) * w];
+                    near_zero |= line[k] | Self::shift_up(line, k) | Self::shift_down(line, k);
+                }
+                island.push(!self.mines[r * w + k] & !near_zero & self.valid_mask(k));
+            }
+        }
+        island
+    }
+    /// 一行中从from列开始的下一段连续的1，返回[起始列, 结束列)
+    fn next_run(&self, line: &[u64], from: usize) -> Option<(usize, usize)> {
+        let find = |from: usize, invert: bool| -> usize {
+            let mut k = from / 64;
+            if k >= self.words {
+                return self.column;
+            }
+            let mut word = if invert { !line[k] } else { line[k] } & (u64::MAX << (from % 64));
+            while word == 0 {
+                k += 1;
+                if k >= self.words {
+                    return self.column;
+                }
+                word = if invert { !line[k] } else { line[k] };
+            }
+            (k * 64 + word.trailing_zeros() as usize).min(self.column)
+        };
+        let start = find(from, false);
+        if start >= self.column {
+            return None;
+        }
+        Some((start, find(start, true)))
+    }
+    /// 位图中1的8连通域数。逐行找出连续的段，用并查集合并上下相接（列区间扩大1后相交）的段
+    fn count_components(&self, mask: &[u64]) -> usize {
+        let w = self.words;
+        let mut parent: Vec<usize> = Vec::with_capacity(self.row * 8);
+        let mut prev: Vec<(usize, usize, usize)> = Vec::with_capacity(16);
+        let mut cur: Vec<(usize, usize, usize)> = Vec::with_capacity(16);
+        let mut components = 0;
+        fn find(parent: &mut [usize], mut x: usize) -> usize {
+            while parent[x] != x {
+                parent[x] = parent[parent[x]];
+                x = parent[x];
+            }
+            x
+        }
+        for r in 0..self.row {
+            let line = &mask[r * w..(r + 1) * w];
+            cur.clear();
+            let mut from = 0;
+            let mut t = 0;
+            while let Some((start, end)) = self.next_run(line, from) {
+                let id = parent.len();
+                parent.push(id);
+                components += 1;
+                // 上一行的段按列有序，跳过已经完全在左边的段
+                while t < prev.len() && prev[t].1 < start {
+                    t += 1;
+                }
+                let mut u = t;
+                while u < prev.len() && prev[u].0 <= end {
+                    let a = find(&mut parent, prev[u].2);
+                    let b = find(&mut parent, id);
+                    if a != b {
+                        parent[a] = b;
+                        components -= 1;
+                    }
+                    u += 1;
+                }
+                cur.push((start, end, id));
+                from = end;
+            }
+            std::mem::swap(&mut prev, &mut cur);
+        }
+        components
+    }
+}
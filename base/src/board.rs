@@ -1,11 +1,18 @@
 ﻿// 局面相关的类，录像在video
 
 use crate::algorithms::{
-    cal_possibility_onboard, solve_direct, solve_enumerate, solve_minus,
+    cal_possibility_onboard, solve_direct, solve_enumerate, solve_global, solve_minus,
 };
 use crate::utils::{
-    cal3BVonIsland, cal_cell_nums, cal_isl, cal_op, refresh_matrixs,
+    cal3BVonIsland, cal_board_numbers, cal_cell_nums, cal_isl, cal_op, refresh_matrixs,
 };
+use crate::board_code::{decode_board_code, encode_board_code, ErrBoardCode};
+use crate::mbf::{decode_mbf, encode_mbf, read_mbf_file, save_to_mbf_file, ErrMbf};
+use crate::constraint::Matrixs;
+use crate::difficulty::{cal_difficulty, Difficulty};
+use crate::grid::{Cell, Grid};
+use std::fmt;
+use std::str::FromStr;
 
 
 /// 静态游戏局面的包装类。  
@@ -14,7 +21,8 @@ use crate::utils::{
 pub struct GameBoard {
     /// 游戏局面，来自玩家，上面标的雷可能是错的。
     pub game_board: Vec<Vec<i32>>,
-    game_board_marked: Vec<Vec<i32>>,
+    // 标雷、确定非雷等都视为未打开，由算法重新标记的局面
+    game_board_marked: Grid<Cell>,
     poss: Vec<Vec<f64>>,
     mine_num: usize,
     is_marked: bool, // game_board_marked是否被完全标记过
@@ -23,6 +31,10 @@ pub struct GameBoard {
     basic_is_mine: Vec<(usize, usize)>,
     enum_not_mine: Vec<(usize, usize)>,
     enum_is_mine: Vec<(usize, usize)>,
+    matrixs: Option<Matrixs>, // 预先算好的game_board_marked的矩阵，有的话mark时不再重建
+    has_global: bool, // 是否已经结合雷数判过雷
+    global_not_mine: Vec<(usize, usize)>,
+    global_is_mine: Vec<(usize, usize)>,
 }
 
 // impl Default for GameBoard {
@@ -46,7 +58,7 @@ impl GameBoard {
     pub fn new(mine_num: usize) -> GameBoard {
         GameBoard {
             game_board: vec![],
-            game_board_marked: vec![],
+            game_board_marked: Grid::new(0, 0, Cell::Covered),
             poss: vec![],
             mine_num: mine_num,
             is_marked: false,
@@ -55,19 +67,27 @@ impl GameBoard {
             basic_not_mine: vec![],
             enum_is_mine: vec![],
             enum_not_mine: vec![],
+            matrixs: None,
+            has_global: false,
+            global_not_mine: vec![],
+            global_is_mine: vec![],
         }
     }
-    pub fn set_game_board(&mut self, board: &Vec<Vec<i32>>) {
-        let mut game_board_marked = board.clone();
-        for i in 0..game_board_marked.len() {
-            for j in 0..game_board_marked[0].len() {
-                if game_board_marked[i][j] > 10 {
-                    game_board_marked[i][j] = 10;
-                }
-            }
-        }
-        self.game_board = board.clone();
-        self.game_board_marked = game_board_marked;
+    pub fn set_game_board(&mut self, board: &[Vec<i32>]) {
+        // 标雷、确定非雷等都视为未打开，交给算法重新判断
+        self.game_board_marked = Grid::from_vec(board)
+            .map(|&v| Cell::from_i32(v).filter(|c| !c.is_covered()).unwrap_or(Cell::Covered));
+        self.game_board = board.to_vec();
+        self.matrixs = None;
+    }
+    /// 网格表示的游戏局面，不认识的值视为未打开
+    pub fn grid(&self) -> Grid<Cell> {
+        Grid::from_vec(&self.game_board).map(|&v| Cell::from_i32(v).unwrap_or(Cell::Covered))
+    }
+    /// 设置预先算好的矩阵，必须与set_game_board的局面（标雷、非雷视为未打开）一致。
+    /// 录像分析时由增量的约束系统提供，以免每个局面都用refresh_matrixs重建。
+    pub(crate) fn set_matrixs(&mut self, matrixs: Matrixs) {
+        self.matrixs = Some(matrixs);
     }
     fn mark(&mut self) {
         // 一旦被标记，那么就会用3大判雷引擎都分析一遍
@@ -75,29 +95,31 @@ impl GameBoard {
         if self.is_marked {
             return;
         }
-        let (mut a_s, mut x_s, mut b_s, _, _) = refresh_matrixs(&self.game_board_marked);
-        let mut ans = solve_direct(&mut a_s, &mut x_s, &mut b_s, &mut self.game_board_marked).0;
+        let mut board_of_game = self.game_board_marked.to_i32_board();
+        let (mut a_s, mut x_s, mut b_s, _, _) = match self.matrixs.take() {
+            Some(m) => m,
+            None => refresh_matrixs(&board_of_game),
+        };
+        let mut ans = solve_direct(&mut a_s, &mut x_s, &mut b_s, &mut board_of_game).0;
         self.basic_not_mine.append(&mut ans);
 
-        let mut ans = solve_minus(&mut a_s, &mut x_s, &mut b_s, &mut self.game_board_marked).0;
+        let mut ans = solve_minus(&mut a_s, &mut x_s, &mut b_s, &mut board_of_game).0;
         self.basic_not_mine.append(&mut ans);
         for i in &self.basic_not_mine {
-            self.game_board_marked[i.0][i.1] = 12;
+            board_of_game[i.0][i.1] = Cell::Safe.to_i32();
         }
-        for i in 0..self.game_board_marked.len() {
-            for j in 0..self.game_board_marked[0].len() {
-                if self.game_board_marked[i][j] == 11 {
-                    self.basic_is_mine.push((i, j));
-                }
+        // 判雷引擎只会标上雷和非雷
+        self.game_board_marked = Grid::from_i32_board(&board_of_game).unwrap();
+        for (pos, &c) in self.game_board_marked.iter() {
+            if c == Cell::Flag {
+                self.basic_is_mine.push(pos);
             }
         }
         self.enum_not_mine = solve_enumerate(&a_s, &x_s, &b_s).0;
         // println!("yyyyyyyyyyyyyyyyy");
-        for i in 0..self.game_board_marked.len() {
-            for j in 0..self.game_board_marked[0].len() {
-                if self.game_board_marked[i][j] == 11 && !self.basic_is_mine.contains(&(i, j)) {
-                    self.enum_is_mine.push((i, j));
-                }
+        for (pos, &c) in self.game_board_marked.iter() {
+            if c == Cell::Flag && !self.basic_is_mine.contains(&pos) {
+                self.enum_is_mine.push(pos);
             }
         }
         self.is_marked = true;
@@ -106,7 +128,7 @@ impl GameBoard {
         if !self.has_poss {
             self.mark();
             // println!("{:?}, {:?}", self.game_board_marked, self.mine_num);
-            self.poss = cal_possibility_onboard(&self.game_board_marked, self.mine_num as f64)
+            self.poss = cal_possibility_onboard(&self.game_board_marked.to_i32_board(), self.mine_num as f64)
                 .unwrap()
                 .0;
             self.has_poss = true;
@@ -141,6 +163,36 @@ impl GameBoard {
         }
         &self.enum_is_mine
     }
+    fn mark_global(&mut self) {
+        // 在3大判雷引擎的基础上，结合雷数判雷。只记录前面判不出的格子
+        if self.has_global {
+            return;
+        }
+        self.mark();
+        let mut board = self.game_board_marked.to_i32_board();
+        for &(x, y) in &self.enum_not_mine {
+            board[x][y] = Cell::Safe.to_i32();
+        }
+        let (a_s, x_s, b_s, _, _) = refresh_matrixs(&board);
+        for (x, y) in solve_enumerate(&a_s, &x_s, &b_s).1 {
+            board[x][y] = Cell::Flag.to_i32();
+        }
+        let (a_s, x_s, b_s, _, _) = refresh_matrixs(&board);
+        let (not_mine, is_mine) = solve_global(&a_s, &x_s, &b_s, &board, self.mine_num);
+        self.global_not_mine = not_mine;
+        self.global_is_mine = is_mine;
+        self.has_global = true;
+    }
+    /// 只有结合雷的总数才能判出的非雷。可选的一层，与get_enum_not_mine等不重叠
+    pub fn get_global_not_mine(&mut self) -> &Vec<(usize, usize)> {
+        self.mark_global();
+        &self.global_not_mine
+    }
+    /// 只有结合雷的总数才能判出的雷
+    pub fn get_global_is_mine(&mut self) -> &Vec<(usize, usize)> {
+        self.mark_global();
+        &self.global_is_mine
+    }
 }
 
 /// 静态局面的包装类。  
@@ -201,6 +253,31 @@ impl Board {
             has_cal_cells: false,
         }
     }
+    /// 从局面码构造局面，同时返回局面码中可选的起手位置
+    pub fn from_code(code: &str) -> Result<(Board, Option<(usize, usize)>), ErrBoardCode> {
+        let (board, start) = decode_board_code(code)?;
+        Ok((Board::new(board), start))
+    }
+    /// 把局面的雷的分布编码为局面码，可以附带起手位置
+    pub fn to_code(&self, start: Option<(usize, usize)>) -> Result<String, ErrBoardCode> {
+        encode_board_code(&self.board, start)
+    }
+    /// 从mbf文件的二进制数据构造局面
+    pub fn from_mbf(raw_data: &[u8]) -> Result<Board, ErrMbf> {
+        Ok(Board::new(decode_mbf(raw_data)?))
+    }
+    /// 读取mbf文件构造局面
+    pub fn from_mbf_file(file_name: &str) -> Result<Board, ErrMbf> {
+        Ok(Board::new(read_mbf_file(file_name)?))
+    }
+    /// 编码为mbf文件的二进制数据
+    pub fn get_mbf_raw_data(&self) -> Result<Vec<u8>, ErrMbf> {
+        encode_mbf(&self.board)
+    }
+    /// 保存为mbf文件，文件名不用带后缀，返回实际保存的文件名
+    pub fn save_to_mbf_file(&self, file_name: &str) -> Result<String, ErrMbf> {
+        save_to_mbf_file(&self.board, file_name)
+    }
     pub fn get_bbbv(&mut self) -> usize {
         if self.has_cal_bbbv {
             return self.bbbv;
@@ -303,4 +380,180 @@ impl Board {
         self.cal_cell_nums();
         return self.cell8;
     }
+    /// 评估从(x0, y0)起手时局面的难度，见[cal_difficulty](crate::cal_difficulty)
+    pub fn get_difficulty(&self, x0: usize, y0: usize) -> Difficulty {
+        cal_difficulty(&self.board, x0, y0)
+    }
+}
+
+/// 解析局面的文本表示失败的原因
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ErrParseBoard {
+    /// 没有任何格子
+    EmptyBoard,
+    /// 各行的长度不一致
+    InconsistentRowLength,
+    /// 不认识的字符
+    InvalidCharacter(char),
+    /// 局面上写的数字与雷的分布不符，坐标为(行, 列)
+    InvalidNumber(usize, usize),
+    /// 雷数的声明无法解析
+    InvalidMineNum,
+}
+
+/// 局面的文本表示中的每一行，忽略空行与行内的空格
+fn parse_notation_rows(
+    s: &str,
+    f: impl Fn(char) -> Option<i32>,
+) -> Result<Vec<Vec<i32>>, ErrParseBoard> {
+    let mut board: Vec<Vec<i32>> = vec![];
+    for line in s.lines() {
+        let mut row = vec![];
+        for c in line.chars().filter(|c| !c.is_whitespace()) {
+            match f(c) {
+                Some(v) => row.push(v),
+                None => return Err(ErrParseBoard::InvalidCharacter(c)),
+            }
+        }
+        if row.is_empty() {
+            continue;
+        }
+        if !board.is_empty() && row.len() != board[0].len() {
+            return Err(ErrParseBoard::InconsistentRowLength);
+        }
+        board.push(row);
+    }
+    if board.is_empty() {
+        return Err(ErrParseBoard::EmptyBoard);
+    }
+    Ok(board)
+}
+
+/// 局面的文本表示：`*`为雷，`0`~`8`为数字，`.`为不关心数字的非雷。数字总是由雷重新计算。
+/// ```
+/// use ms_toollib::Board;
+/// let board: Board = "*1.\n11.\n...".parse().unwrap();
+/// assert_eq!(board.board, vec![vec![-1, 1, 0], vec![1, 1, 0], vec![0, 0, 0]]);
+/// assert_eq!(board.to_string(), "*10\n110\n000");
+/// ```
+impl FromStr for Board {
+    type Err = ErrParseBoard;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let notation = parse_notation_rows(s, |c| match c {
+            '*' => Some(-1),
+            '.' => Some(-2),
+            '0'..='8' => Some(c as i32 - '0' as i32),
+            _ => None,
+        })?;
+        let mut board: Vec<Vec<i32>> = notation
+            .iter()
+            .map(|row| row.iter().map(|&v| if v == -1 { -1 } else { 0 }).collect())
+            .collect();
+        cal_board_numbers(&mut board);
+        for i in 0..board.len() {
+            for j in 0..board[0].len() {
+                if notation[i][j] >= 0 && notation[i][j] != board[i][j] {
+                    return Err(ErrParseBoard::InvalidNumber(i, j));
+                }
+            }
+        }
+        Ok(Board::new(board))
+    }
+}
+
+impl fmt::Display for Board {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let rows: Vec<String> = self
+            .board
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|&v| match v {
+                        -1 => '*',
+                        0..=8 => (b'0' + v as u8) as char,
+                        _ => '?',
+                    })
+                    .collect()
+            })
+            .collect();
+        write!(f, "{}", rows.join("\n"))
+    }
+}
+
+/// 游戏局面的文本表示：`0`~`8`为数字，`.`为未打开（10），`F`为标雷（11），`S`为已知非雷（12），
+/// `X`为标错的雷（14），`!`为踩到的雷（15），`*`为失败后显示的雷（16），`o`为双击时按下的格子（18）。  
+/// 可以用单独一行`mines: 99`声明雷的总数，省略时雷数视为局面上`F`、`!`、`*`的数目。
+/// ```
+/// use ms_toollib::GameBoard;
+/// let game_board: GameBoard = "mines: 3\n.F1\n..1\n111".parse().unwrap();
+/// assert_eq!(game_board.game_board[0], vec![10, 11, 1]);
+/// assert_eq!(game_board.to_string(), "mines: 3\n.F1\n..1\n111");
+/// ```
+impl FromStr for GameBoard {
+    type Err = ErrParseBoard;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut mine_num = None;
+        let mut rows = String::new();
+        for line in s.lines() {
+            match line.trim().strip_prefix("mines:") {
+                Some(n) => match n.trim().parse::<usize>() {
+                    Ok(n) => mine_num = Some(n),
+                    Err(_) => return Err(ErrParseBoard::InvalidMineNum),
+                },
+                None => {
+                    rows.push_str(line);
+                    rows.push('\n');
+                }
+            }
+        }
+        let board = parse_notation_rows(&rows, |c| {
+            let cell = match c {
+                '0'..='8' => Cell::Number(c as u8 - b'0'),
+                '.' => Cell::Covered,
+                'F' => Cell::Flag,
+                'S' => Cell::Safe,
+                'X' => Cell::WrongFlag,
+                '!' => Cell::ExplodedMine,
+                '*' => Cell::RevealedMine,
+                'o' => Cell::Pressed,
+                _ => return None,
+            };
+            Some(cell.to_i32())
+        })?;
+        let mine_num = mine_num.unwrap_or_else(|| {
+            board
+                .iter()
+                .flatten()
+                .filter(|&&v| v == Cell::Flag || v == Cell::ExplodedMine || v == Cell::RevealedMine)
+                .count()
+        });
+        let mut game_board = GameBoard::new(mine_num);
+        game_board.set_game_board(&board);
+        Ok(game_board)
+    }
+}
+
+impl fmt::Display for GameBoard {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let rows: Vec<String> = self
+            .game_board
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|&v| match Cell::from_i32(v) {
+                        Some(Cell::Number(n)) => (b'0' + n) as char,
+                        Some(Cell::Covered) => '.',
+                        Some(Cell::Flag) => 'F',
+                        Some(Cell::Safe) => 'S',
+                        Some(Cell::WrongFlag) => 'X',
+                        Some(Cell::ExplodedMine) => '!',
+                        Some(Cell::RevealedMine) => '*',
+                        Some(Cell::Pressed) => 'o',
+                        _ => '?',
+                    })
+                    .collect()
+            })
+            .collect();
+        write!(f, "mines: {}\n{}", self.mine_num, rows.join("\n"))
+    }
 }
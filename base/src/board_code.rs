@@ -0,0 +1,183 @@
+// 局面码：把局面的雷的分布编码成短字符串，方便在聊天、网址中分享局面
+
+use crate::utils::cal_board_numbers;
+
+/// 当前的局面码版本
+const BOARD_CODE_VERSION: u8 = 1;
+
+const BASE64URL_TABLE: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+/// 解析局面码失败的原因
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ErrBoardCode {
+    /// 局面的高或宽为0或超过255，无法编码
+    InvalidBoardSize,
+    /// 含有base64url以外的字符
+    InvalidCharacter,
+    CodeIsTooShort,
+    /// 不认识的版本，可能是更新的工具生成的
+    UnsupportedVersion,
+    ChecksumMismatch,
+    /// 起手位置越界
+    InvalidStartPosition,
+}
+
+/// 将局面编码为局面码。
+/// - 输入：局面（-1为雷）、可选的起手位置(行, 列)。
+/// - 返回：base64url字符串，不含填充。高、宽都不能超过255。
+/// - 格式：版本（1字节）、标志（1字节，最低位表示是否含起手位置）、高、宽、[起手行、起手列]、
+///   按行优先、高位在前排列的雷的位图（与evf录像相同）、Fletcher-16校验和（2字节）。
+/// - 用python调用时的示例：
+/// ```python
+/// import ms_toollib as ms
+/// board = ms.laymine(16, 30, 99, 0, 0)
+/// code = ms.encode_board_code(board, (0, 0))
+/// (board_2, start) = ms.decode_board_code(code)
+/// ```
+pub fn encode_board_code(
+    board: &[Vec<i32>],
+    start: Option<(usize, usize)>,
+) -> Result<String, ErrBoardCode> {
+    let height = board.len();
+    if height == 0 || height > 255 {
+        return Err(ErrBoardCode::InvalidBoardSize);
+    }
+    let width = board[0].len();
+    if width == 0 || width > 255 {
+        return Err(ErrBoardCode::InvalidBoardSize);
+    }
+    let mut raw_data = vec![BOARD_CODE_VERSION, 0, height as u8, width as u8];
+    if let Some((x0, y0)) = start {
+        if x0 >= height || y0 >= width {
+            return Err(ErrBoardCode::InvalidStartPosition);
+        }
+        raw_data[1] |= 0b0000_0001;
+        raw_data.push(x0 as u8);
+        raw_data.push(y0 as u8);
+    }
+    let mut byte = 0;
+    let mut ptr = 0;
+    for row in board {
+        for &v in &row[..width] {
+            byte <<= 1;
+            if v == -1 {
+                byte |= 1;
+            }
+            ptr += 1;
+            if ptr == 8 {
+                raw_data.push(byte);
+                ptr = 0;
+                byte = 0;
+            }
+        }
+    }
+    if ptr > 0 {
+        byte <<= 8 - ptr;
+        raw_data.push(byte);
+    }
+    let checksum = fletcher16(&raw_data);
+    raw_data.push((checksum >> 8) as u8);
+    raw_data.push((checksum % 256) as u8);
+    Ok(base64url_encode(&raw_data))
+}
+
+/// 将局面码解码为局面。
+/// - 输入：局面码。
+/// - 返回：局面（数字已重新计算）、可选的起手位置(行, 列)。
+#[allow(clippy::type_complexity)]
+pub fn decode_board_code(
+    code: &str,
+) -> Result<(Vec<Vec<i32>>, Option<(usize, usize)>), ErrBoardCode> {
+    let raw_data = base64url_decode(code.trim())?;
+    if raw_data.len() < 6 {
+        return Err(ErrBoardCode::CodeIsTooShort);
+    }
+    let (payload, checksum) = raw_data.split_at(raw_data.len() - 2);
+    if fletcher16(payload) != ((checksum[0] as u16) << 8 | checksum[1] as u16) {
+        return Err(ErrBoardCode::ChecksumMismatch);
+    }
+    if payload[0] != BOARD_CODE_VERSION {
+        return Err(ErrBoardCode::UnsupportedVersion);
+    }
+    let height = payload[2] as usize;
+    let width = payload[3] as usize;
+    if height == 0 || width == 0 {
+        return Err(ErrBoardCode::InvalidBoardSize);
+    }
+    let mut ptr = 4;
+    let mut start = None;
+    if payload[1] & 0b0000_0001 != 0 {
+        if payload.len() < 6 {
+            return Err(ErrBoardCode::CodeIsTooShort);
+        }
+        let (x0, y0) = (payload[4] as usize, payload[5] as usize);
+        if x0 >= height || y0 >= width {
+            return Err(ErrBoardCode::InvalidStartPosition);
+        }
+        start = Some((x0, y0));
+        ptr = 6;
+    }
+    if payload.len() - ptr < (height * width).div_ceil(8) {
+        return Err(ErrBoardCode::CodeIsTooShort);
+    }
+    let mut board = vec![vec![0; width]; height];
+    for (i, row) in board.iter_mut().enumerate() {
+        for (j, v) in row.iter_mut().enumerate() {
+            let id = i * width + j;
+            if payload[ptr + id / 8] & (0b1000_0000 >> (id % 8)) != 0 {
+                *v = -1;
+            }
+        }
+    }
+    cal_board_numbers(&mut board);
+    Ok((board, start))
+}
+
+fn fletcher16(data: &[u8]) -> u16 {
+    let mut sum1: u16 = 0;
+    let mut sum2: u16 = 0;
+    for &byte in data {
+        sum1 = (sum1 + byte as u16) % 255;
+        sum2 = (sum2 + sum1) % 255;
+    }
+    (sum2 << 8) | sum1
+}
+
+fn base64url_encode(data: &[u8]) -> String {
+    let mut code = String::with_capacity((data.len() * 4).div_ceil(3));
+    for chunk in data.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |acc, (i, &b)| acc | (b as u32) << (16 - 8 * i));
+        for i in 0..chunk.len() + 1 {
+            code.push(BASE64URL_TABLE[(n >> (18 - 6 * i) & 0b11_1111) as usize] as char);
+        }
+    }
+    code
+}
+
+fn base64url_decode(code: &str) -> Result<Vec<u8>, ErrBoardCode> {
+    let mut values = vec![];
+    for c in code.bytes() {
+        match BASE64URL_TABLE.iter().position(|&t| t == c) {
+            Some(v) => values.push(v as u32),
+            None => return Err(ErrBoardCode::InvalidCharacter),
+        }
+    }
+    if values.len() % 4 == 1 {
+        return Err(ErrBoardCode::InvalidCharacter);
+    }
+    let mut data = vec![];
+    for chunk in values.chunks(4) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |acc, (i, &v)| acc | v << (18 - 6 * i));
+        for i in 0..chunk.len() - 1 {
+            data.push((n >> (16 - 8 * i)) as u8);
+        }
+    }
+    Ok(data)
+}
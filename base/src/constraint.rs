@@ -0,0 +1,426 @@
+// 增量的约束系统：局面每次只有少量格子变化时，只更新受影响的约束，不必用refresh_matrixs从头重建
+
+use crate::grid::{neighbours, Cell, Grid};
+
+/// 分段的矩阵，格式与[refresh_matrixs](crate::refresh_matrixs)的返回值相同：As、xs、bs、内部未知格子数、标出的雷数
+pub type Matrixs = (
+    Vec<Vec<Vec<i32>>>,
+    Vec<Vec<(usize, usize)>>,
+    Vec<Vec<i32>>,
+    usize,
+    usize,
+);
+
+fn neighbour_ids(row: usize, column: usize, id: usize) -> impl Iterator<Item = usize> {
+    neighbours(row, column, id / column, id % column).map(move |(m, n)| m * column + n)
+}
+
+/// 缓存的一段矩阵，及其包含的数字格
+#[derive(Debug, Clone)]
+struct Block {
+    numbers: Vec<usize>,
+    a: Vec<Vec<i32>>,
+    x: Vec<(usize, usize)>,
+    b: Vec<i32>,
+}
+
+/// 对约束系统的一次修改，记录下来用于撤销
+#[derive(Debug, Clone, Copy)]
+enum Change {
+    /// 打开一格，prior是打开前的状态（未打开或确定非雷）
+    Reveal {
+        cell: usize,
+        prior: Cell,
+    },
+    Flag {
+        cell: usize,
+    },
+    Safe {
+        cell: usize,
+    },
+}
+
+/// 增量的约束系统。
+/// - 用途：逐步扫开局面时（无猜判定、录像分析），每次只用新打开的格子、新标的雷更新约束，
+///   单集合判雷也只检查受影响的数字，生成分段的矩阵时也只重建受影响的段。所有修改都可以撤销。
+/// - 格子的状态与游戏局面相同：0到8是数字，10是未打开，11是标雷，12是确定非雷。标雷、非雷必须正确。
+/// - 用法示例：
+/// ```rust
+/// use ms_toollib::ConstraintSystem;
+/// let mut system = ConstraintSystem::new(2, 2);
+/// system.reveal((1, 0), 1);
+/// system.reveal((1, 1), 1);
+/// let checkpoint = system.checkpoint();
+/// system.flag((0, 0));
+/// let (not_mine, _) = system.solve_direct();
+/// assert_eq!(not_mine, vec![(0, 1)]);
+/// system.undo_to(checkpoint);
+/// assert_eq!(system.game_board(), vec![vec![10, 10], vec![1, 1]]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct ConstraintSystem {
+    row: usize,
+    column: usize,
+    state: Grid<Cell>,
+    /// 数字格周围还没标出的雷数
+    need: Vec<i32>,
+    /// 数字格周围未打开（10）的格子
+    unknown: Vec<Vec<usize>>,
+    /// 每格周围打开了的格子数，为0的未打开格子属于内部未知区域
+    revealed_neighbours: Vec<u8>,
+    /// 没有打开的格子数（10、11、12）
+    unrevealed_num: usize,
+    /// 内部未知区域的格子数，即周围没有打开的格子的10
+    inner_num: usize,
+    flag_num: usize,
+    /// 待做单集合判雷的数字格
+    dirty: Vec<usize>,
+    /// 缓存的段，失效的段为None
+    blocks: Vec<Option<Block>>,
+    /// 每个数字格所在的段，不在任何段中时为usize::MAX
+    block_of: Vec<usize>,
+    /// 约束变化了、所在的段需要重建的数字格
+    stale: Vec<usize>,
+    log: Vec<Change>,
+}
+
+impl ConstraintSystem {
+    /// 全部未打开的局面
+    pub fn new(row: usize, column: usize) -> ConstraintSystem {
+        ConstraintSystem {
+            row,
+            column,
+            state: Grid::new(row, column, Cell::Covered),
+            need: vec![0; row * column],
+            unknown: vec![vec![]; row * column],
+            revealed_neighbours: vec![0; row * column],
+            unrevealed_num: row * column,
+            inner_num: row * column,
+            flag_num: 0,
+            dirty: vec![],
+            blocks: vec![],
+            block_of: vec![usize::MAX; row * column],
+            stale: vec![],
+            log: vec![],
+        }
+    }
+    /// 从游戏局面构造。构造的过程不能撤销
+    pub fn from_game_board(board_of_game: &[Vec<i32>]) -> ConstraintSystem {
+        let mut system = ConstraintSystem::new(board_of_game.len(), board_of_game[0].len());
+        for (i, r) in board_of_game.iter().enumerate() {
+            for (j, &v) in r.iter().enumerate() {
+                if let Some(Cell::Number(n)) = Cell::from_i32(v) {
+                    system.reveal((i, j), n as i32);
+                }
+            }
+        }
+        for (i, r) in board_of_game.iter().enumerate() {
+            for (j, &v) in r.iter().enumerate() {
+                match Cell::from_i32(v) {
+                    Some(Cell::Flag) => system.flag((i, j)),
+                    Some(Cell::Safe) => system.mark_safe((i, j)),
+                    _ => {}
+                }
+            }
+        }
+        system.log.clear();
+        system
+    }
+    fn id(&self, cell: (usize, usize)) -> usize {
+        cell.0 * self.column + cell.1
+    }
+    fn pos(&self, id: usize) -> (usize, usize) {
+        (id / self.column, id % self.column)
+    }
+    fn is_number(&self, id: usize) -> bool {
+        self.cell(id).number().is_some()
+    }
+    /// 1到8的数字，即会给出约束的数字
+    fn is_positive_number(&self, id: usize) -> bool {
+        matches!(self.cell(id), Cell::Number(1..=8))
+    }
+    fn cell(&self, id: usize) -> Cell {
+        self.state[self.pos(id)]
+    }
+    fn set_cell(&mut self, id: usize, cell: Cell) {
+        let pos = self.pos(id);
+        self.state[pos] = cell;
+    }
+    fn is_inner(&self, id: usize) -> bool {
+        self.cell(id) == Cell::Covered && self.revealed_neighbours[id] == 0
+    }
+    pub fn get(&self, cell: (usize, usize)) -> i32 {
+        self.state[cell].to_i32()
+    }
+    /// 没有打开的格子数（含标雷和非雷）。等于雷数时即扫完
+    pub fn get_unrevealed_num(&self) -> usize {
+        self.unrevealed_num
+    }
+    pub fn get_flag_num(&self) -> usize {
+        self.flag_num
+    }
+    /// 当前的游戏局面
+    pub fn game_board(&self) -> Vec<Vec<i32>> {
+        self.state.to_i32_board()
+    }
+    /// 周围数字格的约束中去掉（或加回）一个未打开的格子
+    fn detach(&mut self, id: usize, is_mine: bool) {
+        for k in neighbour_ids(self.row, self.column, id) {
+            if self.is_number(k) {
+                if let Some(t) = self.unknown[k].iter().position(|&c| c == id) {
+                    self.unknown[k].swap_remove(t);
+                }
+                if is_mine {
+                    self.need[k] -= 1;
+                }
+                self.dirty.push(k);
+                self.stale.push(k);
+            }
+        }
+    }
+    fn attach(&mut self, id: usize, is_mine: bool) {
+        for k in neighbour_ids(self.row, self.column, id) {
+            if self.is_number(k) {
+                self.unknown[k].push(id);
+                if is_mine {
+                    self.need[k] += 1;
+                }
+                self.dirty.push(k);
+                self.stale.push(k);
+            }
+        }
+    }
+    /// 打开一格，number是其上的数字。只能打开10或12
+    pub fn reveal(&mut self, cell: (usize, usize), number: i32) {
+        let id = self.id(cell);
+        let prior = self.cell(id);
+        if prior != Cell::Covered && prior != Cell::Safe {
+            return;
+        }
+        if prior == Cell::Covered {
+            self.detach(id, false);
+        }
+        if self.is_inner(id) {
+            self.inner_num -= 1;
+        }
+        self.set_cell(id, Cell::Number(number as u8));
+        self.unrevealed_num -= 1;
+        let mut unknown = vec![];
+        let mut flags = 0;
+        for k in neighbour_ids(self.row, self.column, id) {
+            if self.is_inner(k) {
+                self.inner_num -= 1;
+            }
+            self.revealed_neighbours[k] += 1;
+            match self.cell(k) {
+                Cell::Covered => unknown.push(k),
+                Cell::Flag => flags += 1,
+                _ => {}
+            }
+        }
+        self.unknown[id] = unknown;
+        self.need[id] = number - flags;
+        self.dirty.push(id);
+        self.stale.push(id);
+        self.log.push(Change::Reveal { cell: id, prior });
+    }
+    /// 标雷。只能标在10上
+    pub fn flag(&mut self, cell: (usize, usize)) {
+        let id = self.id(cell);
+        if self.cell(id) != Cell::Covered {
+            return;
+        }
+        self.detach(id, true);
+        if self.is_inner(id) {
+            self.inner_num -= 1;
+        }
+        self.set_cell(id, Cell::Flag);
+        self.flag_num += 1;
+        self.log.push(Change::Flag { cell: id });
+    }
+    /// 标非雷（12）但不打开。只能标在10上
+    pub fn mark_safe(&mut self, cell: (usize, usize)) {
+        let id = self.id(cell);
+        if self.cell(id) != Cell::Covered {
+            return;
+        }
+        self.detach(id, false);
+        if self.is_inner(id) {
+            self.inner_num -= 1;
+        }
+        self.set_cell(id, Cell::Safe);
+        self.log.push(Change::Safe { cell: id });
+    }
+    /// 撤销点，即目前为止的修改次数
+    pub fn checkpoint(&self) -> usize {
+        self.log.len()
+    }
+    /// 撤销最近的一次修改。没有可撤销的修改时返回false
+    pub fn undo(&mut self) -> bool {
+        let Some(change) = self.log.pop() else {
+            return false;
+        };
+        match change {
+            Change::Reveal { cell, prior } => {
+                for k in neighbour_ids(self.row, self.column, cell) {
+                    self.revealed_neighbours[k] -= 1;
+                    if self.is_inner(k) {
+                        self.inner_num += 1;
+                    }
+                }
+                self.unknown[cell].clear();
+                self.need[cell] = 0;
+                self.stale.push(cell);
+                self.set_cell(cell, prior);
+                if self.is_inner(cell) {
+                    self.inner_num += 1;
+                }
+                self.unrevealed_num += 1;
+                if prior == Cell::Covered {
+                    self.attach(cell, false);
+                }
+            }
+            Change::Flag { cell } => {
+                self.set_cell(cell, Cell::Covered);
+                if self.is_inner(cell) {
+                    self.inner_num += 1;
+                }
+                self.flag_num -= 1;
+                self.attach(cell, true);
+            }
+            Change::Safe { cell } => {
+                self.set_cell(cell, Cell::Covered);
+                if self.is_inner(cell) {
+                    self.inner_num += 1;
+                }
+                self.attach(cell, false);
+            }
+        }
+        true
+    }
+    /// 撤销到撤销点
+    pub fn undo_to(&mut self, checkpoint: usize) {
+        while self.log.len() > checkpoint {
+            self.undo();
+        }
+    }
+    /// 单集合判雷，只检查上次以来受影响的数字，直到判不出新的格子。判出的雷标成11，非雷标成12。
+    /// - 返回：非雷、是雷的位置。
+    #[allow(clippy::type_complexity)]
+    pub fn solve_direct(&mut self) -> (Vec<(usize, usize)>, Vec<(usize, usize)>) {
+        let mut not_mine = vec![];
+        let mut is_mine = vec![];
+        while let Some(k) = self.dirty.pop() {
+            if !self.is_number(k) || self.unknown[k].is_empty() {
+                continue;
+            }
+            if self.need[k] == 0 {
+                for c in self.unknown[k].clone() {
+                    not_mine.push(self.pos(c));
+                    self.mark_safe(self.pos(c));
+                }
+            } else if self.need[k] == self.unknown[k].len() as i32 {
+                for c in self.unknown[k].clone() {
+                    is_mine.push(self.pos(c));
+                    self.flag(self.pos(c));
+                }
+            }
+        }
+        (not_mine, is_mine)
+    }
+    /// 由当前的约束生成分段的矩阵，与对当前局面调用refresh_matrixs的结果等价（段、行、列的顺序可能不同）。
+    /// 段缓存在系统中，只重建上次以来约束变化了的数字所在的段，其余的段直接复用。
+    pub fn matrixs(&mut self) -> Matrixs {
+        // 让受影响的段失效，其中的数字都要重新分段
+        let mut seeds = std::mem::take(&mut self.stale);
+        let mut t = 0;
+        while t < seeds.len() {
+            let b = self.block_of[seeds[t]];
+            if b != usize::MAX {
+                if let Some(block) = self.blocks[b].take() {
+                    for &k in &block.numbers {
+                        self.block_of[k] = usize::MAX;
+                    }
+                    seeds.extend(block.numbers);
+                }
+            }
+            t += 1;
+        }
+        for id in seeds {
+            if self.block_of[id] == usize::MAX
+                && self.is_positive_number(id)
+                && !self.unknown[id].is_empty()
+            {
+                self.build_block(id);
+            }
+        }
+        let mut matrix_as = vec![];
+        let mut matrix_xs = vec![];
+        let mut matrix_bs = vec![];
+        for block in self.blocks.iter().flatten() {
+            matrix_as.push(block.a.clone());
+            matrix_xs.push(block.x.clone());
+            matrix_bs.push(block.b.clone());
+        }
+        (
+            matrix_as,
+            matrix_xs,
+            matrix_bs,
+            self.inner_num,
+            self.flag_num,
+        )
+    }
+    /// 从数字格id开始广度优先搜索，找出与它共享未知格的所有数字，建成一段。
+    /// 搜到仍然有效的段时，说明两段连在了一起，使其失效并入新的段
+    fn build_block(&mut self, id: usize) {
+        let slot = match self.blocks.iter().position(|b| b.is_none()) {
+            Some(slot) => slot,
+            None => {
+                self.blocks.push(None);
+                self.blocks.len() - 1
+            }
+        };
+        let mut numbers = vec![id];
+        let mut cells: Vec<usize> = vec![];
+        let mut cell_ids = std::collections::HashSet::new();
+        self.block_of[id] = slot;
+        let mut t = 0;
+        while t < numbers.len() {
+            for i in 0..self.unknown[numbers[t]].len() {
+                let c = self.unknown[numbers[t]][i];
+                if !cell_ids.insert(c) {
+                    continue;
+                }
+                cells.push(c);
+                for k in neighbour_ids(self.row, self.column, c) {
+                    if self.block_of[k] == slot || !self.is_positive_number(k) {
+                        continue;
+                    }
+                    let b = self.block_of[k];
+                    if b != usize::MAX {
+                        if let Some(block) = self.blocks[b].take() {
+                            for &m in &block.numbers {
+                                self.block_of[m] = usize::MAX;
+                            }
+                        }
+                    }
+                    self.block_of[k] = slot;
+                    numbers.push(k);
+                }
+            }
+            t += 1;
+        }
+        let a = numbers
+            .iter()
+            .map(|&k| {
+                cells
+                    .iter()
+                    .map(|c| self.unknown[k].contains(c) as i32)
+                    .collect()
+            })
+            .collect();
+        let x = cells.iter().map(|&c| self.pos(c)).collect();
+        let b = numbers.iter().map(|&k| self.need[k]).collect();
+        self.blocks[slot] = Some(Block { numbers, a, x, b });
+    }
+}
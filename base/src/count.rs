@@ -0,0 +1,151 @@
+// 情况数的表示。雷的分布情况数可以远远超过f64的范围，计算概率时有两种表示：
+// 精确的大整数BigUint，以及取对数的LogNumber（速度快，相对误差在1e-12左右）。
+
+use num_bigint::BigUint;
+use num_traits::{One, Zero};
+
+/// 情况数，即雷的分布的数量。概率计算引擎对其泛型，以便同时支持精确计算与快速计算。
+pub trait MineCount: Clone + std::fmt::Debug {
+    fn zero() -> Self;
+    fn from_usize(k: usize) -> Self;
+    fn is_zero(&self) -> bool;
+    /// self += other
+    fn accumulate(&mut self, other: &Self);
+    /// self * other
+    fn times(&self, other: &Self) -> Self;
+    /// self / k，用于结果必然能整除的场合
+    fn div_usize(&self, k: usize) -> Self;
+    fn one() -> Self {
+        Self::from_usize(1)
+    }
+    fn mul_usize(&self, k: usize) -> Self {
+        self.times(&Self::from_usize(k))
+    }
+    /// 组合数C(n, k)，k > n时为0
+    fn binomial(n: usize, k: usize) -> Self {
+        if k > n {
+            return Self::zero();
+        }
+        let k = k.min(n - k);
+        let mut c = Self::one();
+        for i in 0..k {
+            c = c.mul_usize(n - i).div_usize(i + 1);
+        }
+        c
+    }
+}
+
+impl MineCount for BigUint {
+    fn zero() -> Self {
+        Zero::zero()
+    }
+    fn from_usize(k: usize) -> Self {
+        BigUint::from(k)
+    }
+    fn is_zero(&self) -> bool {
+        Zero::is_zero(self)
+    }
+    fn accumulate(&mut self, other: &Self) {
+        *self += other;
+    }
+    fn times(&self, other: &Self) -> Self {
+        self * other
+    }
+    fn div_usize(&self, k: usize) -> Self {
+        self / k
+    }
+    fn one() -> Self {
+        One::one()
+    }
+    fn mul_usize(&self, k: usize) -> Self {
+        self * k
+    }
+}
+
+/// 直接用f64计数。情况数超过2^1023时会溢出，只用于格子数较少的段。
+impl MineCount for f64 {
+    fn zero() -> Self {
+        0.0
+    }
+    fn from_usize(k: usize) -> Self {
+        k as f64
+    }
+    fn is_zero(&self) -> bool {
+        *self == 0.0
+    }
+    fn accumulate(&mut self, other: &Self) {
+        *self += other;
+    }
+    fn times(&self, other: &Self) -> Self {
+        self * other
+    }
+    fn div_usize(&self, k: usize) -> Self {
+        self / k as f64
+    }
+}
+
+/// 原来的科学计数法表示的大数字，已被LogNumber代替，保留一个版本
+#[deprecated(note = "用LogNumber代替，或用BigUint精确计算")]
+pub type BigNumber = LogNumber;
+
+/// 以自然对数表示的非负数，0表示为负无穷。乘法变为加法，不会溢出。
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LogNumber(pub f64);
+
+impl LogNumber {
+    pub fn from_f64(v: f64) -> LogNumber {
+        LogNumber(v.ln())
+    }
+    /// 自然对数
+    pub fn ln(&self) -> f64 {
+        self.0
+    }
+    /// self / other，other不能为0
+    pub fn ratio(&self, other: &LogNumber) -> f64 {
+        (self.0 - other.0).exp()
+    }
+}
+
+impl MineCount for LogNumber {
+    fn zero() -> Self {
+        LogNumber(f64::NEG_INFINITY)
+    }
+    fn from_usize(k: usize) -> Self {
+        LogNumber((k as f64).ln())
+    }
+    fn is_zero(&self) -> bool {
+        self.0 == f64::NEG_INFINITY
+    }
+    fn accumulate(&mut self, other: &Self) {
+        if other.is_zero() {
+            return;
+        }
+        if self.is_zero() {
+            *self = *other;
+            return;
+        }
+        let (big, small) = if self.0 > other.0 {
+            (self.0, other.0)
+        } else {
+            (other.0, self.0)
+        };
+        self.0 = big + (small - big).exp().ln_1p();
+    }
+    fn times(&self, other: &Self) -> Self {
+        LogNumber(self.0 + other.0)
+    }
+    fn div_usize(&self, k: usize) -> Self {
+        LogNumber(self.0 - (k as f64).ln())
+    }
+    fn binomial(n: usize, k: usize) -> Self {
+        if k > n {
+            return Self::zero();
+        }
+        let k = k.min(n - k);
+        LogNumber(
+            (0..k)
+                .map(|i| ((n - i) as f64).ln() - ((i + 1) as f64).ln())
+                .sum(),
+        )
+    }
+}
@@ -0,0 +1,165 @@
+// 局面难度评估：模拟求解器从起手位置扫开局面，统计每一轮推理用到的方法、枚举的长度、被迫的猜雷与双击的机会
+
+use crate::algorithms::{cal_possibility, open_cells, solve_enumerate, solve_minus};
+use crate::constraint::ConstraintSystem;
+use crate::grid::neighbours;
+
+/// 局面的难度评估，由求解器的推理过程统计。
+/// - 推理按轮进行，每一轮用尽量简单的方法判出至少一格：单集合判雷（solve_direct）、减法公式（solve_minus）、
+///   枚举（solve_enumerate），都判不出时被迫猜雷。
+#[derive(Debug, Clone, PartialEq)]
+pub struct Difficulty {
+    /// 只用单集合判雷的轮数
+    pub direct_steps: usize,
+    /// 需要减法公式的轮数
+    pub minus_steps: usize,
+    /// 需要枚举的轮数
+    pub enumerate_steps: usize,
+    /// 需要枚举的轮中，最长的段的长度，与[cal_possibility](crate::cal_possibility)返回的最后一项相同
+    pub max_enum_len: usize,
+    /// 被迫猜雷的次数
+    pub guesses: usize,
+    /// 每次猜雷都猜对的概率
+    pub guess_survival: f64,
+    /// 双击的机会，即能用一次双击打开至少2格的数字的个数
+    pub chording_chances: usize,
+    /// 综合的难度分数，越大越难，无猜且只用单集合判雷的局面为0
+    pub score: f64,
+}
+
+/// 评估局面的难度。
+/// - 输入：局面、起手行数、起手列数。起手位置必须不是雷。
+/// - 返回：难度评估。分数 = 减法公式的轮数 + Σ每轮枚举(2 + 段长 / 4) + 猜雷次数 * 5 - log2(猜对的概率) * 10 - 双击的机会 * 0.1，
+///   最小为0。
+/// - 注意：被迫猜雷时，选真实局面中不是雷的格子里是雷的概率最小的一个，即假设玩家猜对，但猜对的概率计入评估。
+///   不考虑剩余雷数，与[is_solvable](crate::is_solvable)一致。
+/// - 用python调用时的示例：
+/// ```python
+/// import ms_toollib as ms
+/// board = ms.Board(ms.laymine(16, 30, 99, 3, 20))
+/// d = board.get_difficulty(3, 20)
+/// print(d["score"], d["guesses"])
+/// ```
+pub fn cal_difficulty(board: &Vec<Vec<i32>>, x0: usize, y0: usize) -> Difficulty {
+    let row = board.len();
+    let column = board[0].len();
+    let mine_num = board.iter().flatten().filter(|&&x| x == -1).count();
+    let mut d = Difficulty {
+        direct_steps: 0,
+        minus_steps: 0,
+        enumerate_steps: 0,
+        max_enum_len: 0,
+        guesses: 0,
+        guess_survival: 1.0,
+        chording_chances: 0,
+        score: 0.0,
+    };
+    let mut enumerate_cost = 0.0;
+    let mut chorded = vec![vec![false; column]; row];
+    let mut system = ConstraintSystem::new(row, column);
+    open_cells(&mut system, board, vec![(x0, y0)]);
+    while system.get_unrevealed_num() > mine_num {
+        let (not_mine, is_mine) = system.solve_direct();
+        if !not_mine.is_empty() || !is_mine.is_empty() {
+            d.direct_steps += 1;
+            d.chording_chances += count_chording_chances(&system, &mut chorded);
+            open_cells(&mut system, board, not_mine);
+            continue;
+        }
+        let (mut matrix_a_s, mut matrix_x_s, mut matrix_b_s, _, _) = system.matrixs();
+        let mut board_of_game = system.game_board();
+        let mut ans = solve_minus(
+            &mut matrix_a_s,
+            &mut matrix_x_s,
+            &mut matrix_b_s,
+            &mut board_of_game,
+        );
+        if !ans.0.is_empty() || !ans.1.is_empty() {
+            d.minus_steps += 1;
+        } else {
+            ans = solve_enumerate(&matrix_a_s, &matrix_x_s, &matrix_b_s);
+            if !ans.0.is_empty() || !ans.1.is_empty() {
+                let len = matrix_x_s.iter().map(|x| x.len()).max().unwrap_or(0);
+                d.enumerate_steps += 1;
+                d.max_enum_len = d.max_enum_len.max(len);
+                enumerate_cost += 2.0 + len as f64 / 4.0;
+            } else {
+                // 被迫猜雷
+                match cal_guess(&system, board, mine_num) {
+                    Some((cell, p)) => {
+                        d.guesses += 1;
+                        d.guess_survival *= 1.0 - p;
+                        ans = (vec![cell], vec![]);
+                    }
+                    None => break,
+                }
+            }
+        }
+        for cell in ans.1 {
+            if system.get(cell) == 10 {
+                system.flag(cell);
+            }
+        }
+        d.chording_chances += count_chording_chances(&system, &mut chorded);
+        open_cells(&mut system, board, ans.0);
+    }
+    let score = d.minus_steps as f64 + enumerate_cost + d.guesses as f64 * 5.0
+        - d.guess_survival.max(f64::MIN_POSITIVE).log2() * 10.0
+        - d.chording_chances as f64 * 0.1;
+    d.score = score.max(0.0);
+    d
+}
+
+/// 新出现的双击机会：周围的雷都已判出、还有至少2格未打开的数字。每个数字只算一次
+fn count_chording_chances(system: &ConstraintSystem, chorded: &mut [Vec<bool>]) -> usize {
+    let board_of_game = system.game_board();
+    let row = board_of_game.len();
+    let column = board_of_game[0].len();
+    let mut chances = 0;
+    for i in 0..row {
+        for j in 0..column {
+            let v = board_of_game[i][j];
+            if chorded[i][j] || !(1..=8).contains(&v) {
+                continue;
+            }
+            let (mut flags, mut covered) = (0, 0);
+            for (m, n) in neighbours(row, column, i, j) {
+                match board_of_game[m][n] {
+                    11 => flags += 1,
+                    10 | 12 => covered += 1,
+                    _ => {}
+                }
+            }
+            if flags == v && covered >= 2 {
+                chorded[i][j] = true;
+                chances += 1;
+            }
+        }
+    }
+    chances
+}
+
+/// 被迫猜雷时猜的格子及其是雷的概率。在真实局面中不是雷的未打开格子里，选是雷的概率最小的
+// is_none_or要求rust 1.82，这里用map_or兼容更老的编译器
+#[allow(clippy::unnecessary_map_or)]
+fn cal_guess(
+    system: &ConstraintSystem,
+    board: &[Vec<i32>],
+    mine_num: usize,
+) -> Option<((usize, usize), f64)> {
+    let board_of_game = system.game_board();
+    let (p, p_unknow, _, _) = cal_possibility(&board_of_game, mine_num as f64).ok()?;
+    let mut poss = vec![vec![p_unknow; board[0].len()]; board.len()];
+    for ((x, y), q) in p {
+        poss[x][y] = q;
+    }
+    let mut best: Option<((usize, usize), f64)> = None;
+    for (i, r) in board_of_game.iter().enumerate() {
+        for (j, &v) in r.iter().enumerate() {
+            if v == 10 && board[i][j] >= 0 && best.map_or(true, |b| poss[i][j] < b.1) {
+                best = Some(((i, j), poss[i][j]));
+            }
+        }
+    }
+    best
+}
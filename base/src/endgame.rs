@@ -0,0 +1,350 @@
+// 残局求解：剩余格子较少时，枚举所有与局面相容的雷的分布，精确计算每种点法在最优策略下的胜率
+
+use crate::grid::neighbours;
+use std::collections::HashMap;
+
+/// 残局求解失败的原因
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ErrEndgame {
+    /// 未打开的格子超过64个
+    TooManyCells,
+    /// 与局面相容的雷的分布的数量超过预算
+    TooManyConfigurations,
+    /// 局面与雷数矛盾
+    Contradictory,
+}
+
+/// 残局中的一种点法：先点开cell，之后都按最优策略走
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct EndgameMove {
+    pub cell: (usize, usize),
+    /// 能赢的雷的分布的数量
+    pub wins: usize,
+    /// 胜率，即wins / 分布的总数
+    pub win_probability: f64,
+}
+
+/// 残局求解器，计算每种点法在之后最优策略下的精确胜率。
+/// - 输入：游戏局面、总雷数（含已标出的雷）、预算（与局面相容的雷的分布的最大数量）。局面中可以标雷（11），
+///   但必须全部标对；非雷（12）视为未打开。
+/// - 返回：与局面相容的雷的分布的总数，以及所有不是必然是雷的格子的点法，按胜率从高到低排列。
+///   每种分布等可能，胜率是精确的分数wins / total。
+/// - 注意：点开0时会像游戏中一样连锁打开。必然不是雷的格子总是先点，它们的胜率都等于最优胜率。
+///   局面的状态按相容的分布的集合记忆化。
+/// - 用python调用时的示例：
+/// ```python
+/// import ms_toollib as ms
+/// (total, moves) = ms.solve_endgame(game_board, 99, 10000)
+/// print(moves[0]) # ((x, y), wins, win_probability)
+/// ```
+pub fn solve_endgame(
+    board_of_game: &[Vec<i32>],
+    mine_num: usize,
+    budget: usize,
+) -> Result<(usize, Vec<EndgameMove>), ErrEndgame> {
+    let endgame = Endgame::new(board_of_game, mine_num, budget)?;
+    let all: Vec<u32> = (0..endgame.configs.len() as u32).collect();
+    let mut memo = HashMap::new();
+    let always_mine = endgame
+        .configs
+        .iter()
+        .fold(endgame.full_mask(), |a, c| a & c);
+    let mut moves = vec![];
+    for c in 0..endgame.cells.len() {
+        if always_mine >> c & 1 == 1 {
+            continue;
+        }
+        let wins = endgame.cal_click_wins(&all, 0, c, &mut memo);
+        moves.push(EndgameMove {
+            cell: endgame.cells[c],
+            wins,
+            win_probability: wins as f64 / all.len() as f64,
+        });
+    }
+    moves.sort_by_key(|m| std::cmp::Reverse(m.wins));
+    Ok((all.len(), moves))
+}
+
+/// 点开后看到相同数字的一组分布：打开的格子，以及分布的编号
+type RevealGroup = (u64, Vec<u32>);
+
+struct Endgame {
+    /// 未打开的格子
+    cells: Vec<(usize, usize)>,
+    /// 每个未打开的格子周围的未打开格子，按位表示
+    neighbour_masks: Vec<u64>,
+    /// 每个未打开的格子周围的标雷数
+    flag_nums: Vec<usize>,
+    /// 所有相容的雷的分布，按位表示
+    configs: Vec<u64>,
+}
+
+impl Endgame {
+    fn new(
+        board_of_game: &[Vec<i32>],
+        mine_num: usize,
+        budget: usize,
+    ) -> Result<Endgame, ErrEndgame> {
+        let row = board_of_game.len();
+        let column = board_of_game[0].len();
+        let mut cells = vec![];
+        let mut flag_num = 0;
+        for (i, r) in board_of_game.iter().enumerate() {
+            for (j, &v) in r.iter().enumerate() {
+                match v {
+                    10 | 12 => cells.push((i, j)),
+                    11 => flag_num += 1,
+                    _ => {}
+                }
+            }
+        }
+        if cells.len() > 64 {
+            return Err(ErrEndgame::TooManyCells);
+        }
+        let id_of = |pos: (usize, usize)| cells.iter().position(|&c| c == pos);
+        let mut neighbour_masks = vec![];
+        let mut flag_nums = vec![];
+        for &(i, j) in &cells {
+            let mut mask = 0u64;
+            let mut flags = 0;
+            for (m, n) in neighbours(row, column, i, j) {
+                if let Some(k) = id_of((m, n)) {
+                    mask |= 1 << k;
+                } else if board_of_game[m][n] == 11 {
+                    flags += 1;
+                }
+            }
+            neighbour_masks.push(mask);
+            flag_nums.push(flags);
+        }
+        // 数字给出的约束：(周围未打开的格子, 其中的雷数)
+        let mut constraints = vec![];
+        for i in 0..row {
+            for j in 0..column {
+                if !(0..=8).contains(&board_of_game[i][j]) {
+                    continue;
+                }
+                let mut ids = vec![];
+                let mut flags = 0;
+                for (m, n) in neighbours(row, column, i, j) {
+                    if let Some(k) = id_of((m, n)) {
+                        ids.push(k);
+                    } else if board_of_game[m][n] == 11 {
+                        flags += 1;
+                    }
+                }
+                let need = board_of_game[i][j] - flags;
+                if need < 0 || need as usize > ids.len() {
+                    return Err(ErrEndgame::Contradictory);
+                }
+                constraints.push((ids, need as usize));
+            }
+        }
+        let mine_left = mine_num
+            .checked_sub(flag_num)
+            .ok_or(ErrEndgame::Contradictory)?;
+        let mut endgame = Endgame {
+            cells,
+            neighbour_masks,
+            flag_nums,
+            configs: vec![],
+        };
+        endgame.enumerate(&constraints, mine_left, budget)?;
+        if endgame.configs.is_empty() {
+            return Err(ErrEndgame::Contradictory);
+        }
+        Ok(endgame)
+    }
+    fn full_mask(&self) -> u64 {
+        if self.cells.len() == 64 {
+            u64::MAX
+        } else {
+            (1u64 << self.cells.len()) - 1
+        }
+    }
+    /// 回溯枚举所有相容的雷的分布。有约束的格子排在前面，以便尽早剪枝
+    fn enumerate(
+        &mut self,
+        constraints: &[(Vec<usize>, usize)],
+        mine_left: usize,
+        budget: usize,
+    ) -> Result<(), ErrEndgame> {
+        let n = self.cells.len();
+        let mut order: Vec<usize> = (0..n)
+            .filter(|&k| constraints.iter().any(|c| c.0.contains(&k)))
+            .collect();
+        let interior: Vec<usize> = (0..n).filter(|k| !order.contains(k)).collect();
+        order.extend(interior);
+        let cell_to_constraint: Vec<Vec<usize>> = (0..n)
+            .map(|k| {
+                (0..constraints.len())
+                    .filter(|&e| constraints[e].0.contains(&k))
+                    .collect()
+            })
+            .collect();
+        let mut need: Vec<i32> = constraints.iter().map(|c| c.1 as i32).collect();
+        let mut left: Vec<i32> = constraints.iter().map(|c| c.0.len() as i32).collect();
+        self.search(
+            0,
+            &order,
+            &cell_to_constraint,
+            &mut need,
+            &mut left,
+            mine_left,
+            0,
+            budget,
+        )
+    }
+    #[allow(clippy::too_many_arguments)]
+    fn search(
+        &mut self,
+        k: usize,
+        order: &[usize],
+        cell_to_constraint: &[Vec<usize>],
+        need: &mut [i32],
+        left: &mut [i32],
+        mine_left: usize,
+        mask: u64,
+        budget: usize,
+    ) -> Result<(), ErrEndgame> {
+        if mine_left > order.len() - k {
+            return Ok(());
+        }
+        if k == order.len() {
+            if self.configs.len() >= budget {
+                return Err(ErrEndgame::TooManyConfigurations);
+            }
+            self.configs.push(mask);
+            return Ok(());
+        }
+        let cell = order[k];
+        for mine in [false, true] {
+            if mine && mine_left == 0 {
+                continue;
+            }
+            let mut ok = true;
+            for &e in &cell_to_constraint[cell] {
+                left[e] -= 1;
+                if mine {
+                    need[e] -= 1;
+                }
+                if need[e] < 0 || need[e] > left[e] {
+                    ok = false;
+                }
+            }
+            let ans = if ok {
+                let (m, new_mask) = if mine {
+                    (mine_left - 1, mask | 1 << cell)
+                } else {
+                    (mine_left, mask)
+                };
+                self.search(
+                    k + 1,
+                    order,
+                    cell_to_constraint,
+                    need,
+                    left,
+                    m,
+                    new_mask,
+                    budget,
+                )
+            } else {
+                Ok(())
+            };
+            for &e in &cell_to_constraint[cell] {
+                left[e] += 1;
+                if mine {
+                    need[e] += 1;
+                }
+            }
+            ans?;
+        }
+        Ok(())
+    }
+    /// 在分布config下点开cell（不是雷），返回打开的格子，以及打开的格子上的数字。0会连锁打开
+    fn reveal(&self, config: u64, opened: u64, cell: usize) -> (u64, Vec<(usize, usize)>) {
+        let mut revealed = 0u64;
+        let mut numbers = vec![];
+        let mut stack = vec![cell];
+        while let Some(c) = stack.pop() {
+            if (opened | revealed) >> c & 1 == 1 {
+                continue;
+            }
+            revealed |= 1 << c;
+            let number =
+                self.flag_nums[c] + (config & self.neighbour_masks[c]).count_ones() as usize;
+            numbers.push((c, number));
+            if number == 0 {
+                let mut nb = self.neighbour_masks[c] & !(opened | revealed);
+                while nb != 0 {
+                    stack.push(nb.trailing_zeros() as usize);
+                    nb &= nb - 1;
+                }
+            }
+        }
+        numbers.sort_unstable();
+        (revealed, numbers)
+    }
+    /// 在相容的分布集合configs下点开cell，之后按最优策略走，能赢的分布数
+    fn cal_click_wins(
+        &self,
+        configs: &[u32],
+        opened: u64,
+        cell: usize,
+        memo: &mut HashMap<Vec<u32>, usize>,
+    ) -> usize {
+        // 按打开后看到的数字把分布分组
+        let mut groups: HashMap<Vec<(usize, usize)>, RevealGroup> = HashMap::new();
+        for &id in configs {
+            let config = self.configs[id as usize];
+            if config >> cell & 1 == 1 {
+                continue;
+            }
+            let (revealed, numbers) = self.reveal(config, opened, cell);
+            groups
+                .entry(numbers)
+                .or_insert((revealed, vec![]))
+                .1
+                .push(id);
+        }
+        groups
+            .values()
+            .map(|(revealed, g)| self.cal_wins(g, opened | revealed, memo))
+            .sum()
+    }
+    /// 在相容的分布集合configs下，按最优策略走能赢的分布数
+    fn cal_wins(&self, configs: &[u32], opened: u64, memo: &mut HashMap<Vec<u32>, usize>) -> usize {
+        if configs.len() == 1 {
+            return 1;
+        }
+        if let Some(&w) = memo.get(configs) {
+            return w;
+        }
+        let mut union = 0u64;
+        let mut intersection = self.full_mask();
+        for &id in configs {
+            union |= self.configs[id as usize];
+            intersection &= self.configs[id as usize];
+        }
+        let unopened = self.full_mask() & !opened;
+        let safe = unopened & !union;
+        let wins = if safe != 0 {
+            // 必然不是雷的格子先点，不会降低胜率
+            self.cal_click_wins(configs, opened, safe.trailing_zeros() as usize, memo)
+        } else {
+            let mut best = 0;
+            let mut candidates = unopened & !intersection;
+            while candidates != 0 {
+                let c = candidates.trailing_zeros() as usize;
+                candidates &= candidates - 1;
+                best = best.max(self.cal_click_wins(configs, opened, c, memo));
+                if best == configs.len() {
+                    break;
+                }
+            }
+            best
+        };
+        memo.insert(configs.to_vec(), wins);
+        wins
+    }
+}
@@ -0,0 +1,230 @@
+// 长时间计算的执行层：线程数、取消、限时、进度回调。埋雷、抽样、批量分析录像、大局面的概率计算共用
+
+use std::fmt;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Instant;
+
+/// 取消令牌。克隆出的令牌共享同一个状态，在任意线程中取消后，所有使用它的计算尽快停下。
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancelToken {
+    pub fn new() -> CancelToken {
+        CancelToken::default()
+    }
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+}
+
+/// 进度回调，参数为已完成的工作量、总工作量。在计算线程中被调用，应尽快返回。
+pub type ProgressCallback = Arc<dyn Fn(usize, usize) + Send + Sync>;
+
+/// 计算结束的状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExecStatus {
+    /// 全部算完
+    Finished,
+    /// 被取消令牌取消，结果只有一部分
+    Cancelled,
+    /// 超过了截止时间，结果只有一部分
+    TimedOut,
+}
+
+/// 长时间计算的执行选项。默认用所有的CPU核、不可取消、不限时、不报告进度。
+/// - 用法示例：
+/// ```rust
+/// use ms_toollib::{laymine_solvable_exec, CancelToken, ExecOptions};
+/// use std::sync::Arc;
+/// use std::time::{Duration, Instant};
+/// let cancel = CancelToken::new();
+/// let options = ExecOptions {
+///     thread_num: 2,
+///     cancel: Some(cancel.clone()),
+///     deadline: Some(Instant::now() + Duration::from_secs(10)),
+///     progress: Some(Arc::new(|done, total| println!("{}/{}", done, total))),
+/// };
+/// let (board, flag, status) = laymine_solvable_exec(16, 30, 99, 3, 20, 100000, 2022, &options);
+/// ```
+#[derive(Clone)]
+pub struct ExecOptions {
+    /// 线程数。为0或1时在调用者的线程中计算，不创建新线程
+    pub thread_num: usize,
+    pub cancel: Option<CancelToken>,
+    /// 截止时间，超过后尽快停下
+    pub deadline: Option<Instant>,
+    /// 进度回调。工作量每完成约1%调用一次
+    pub progress: Option<ProgressCallback>,
+}
+
+impl Default for ExecOptions {
+    fn default() -> Self {
+        ExecOptions {
+            thread_num: thread::available_parallelism().map_or(1, |n| n.get()),
+            cancel: None,
+            deadline: None,
+            progress: None,
+        }
+    }
+}
+
+impl fmt::Debug for ExecOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ExecOptions")
+            .field("thread_num", &self.thread_num)
+            .field("cancel", &self.cancel)
+            .field("deadline", &self.deadline)
+            .field("progress", &self.progress.is_some())
+            .finish()
+    }
+}
+
+impl ExecOptions {
+    /// 在调用者的线程中计算，不可取消、不限时
+    pub fn single_thread() -> ExecOptions {
+        ExecOptions {
+            thread_num: 1,
+            cancel: None,
+            deadline: None,
+            progress: None,
+        }
+    }
+    /// 应该停下时返回停下的原因，否则返回None
+    pub fn stop_reason(&self) -> Option<ExecStatus> {
+        if matches!(&self.cancel, Some(c) if c.is_cancelled()) {
+            return Some(ExecStatus::Cancelled);
+        }
+        if matches!(self.deadline, Some(d) if Instant::now() >= d) {
+            return Some(ExecStatus::TimedOut);
+        }
+        None
+    }
+}
+
+/// 一次计算的上下文，多个线程共用：检查是否应该停下、报告进度
+pub(crate) struct ExecContext<'a> {
+    options: &'a ExecOptions,
+    total: usize,
+    done: AtomicUsize,
+    /// 上次报告进度时完成的百分比
+    reported: AtomicUsize,
+    stopped: Mutex<Option<ExecStatus>>,
+}
+
+impl<'a> ExecContext<'a> {
+    fn new(options: &'a ExecOptions, total: usize) -> ExecContext<'a> {
+        ExecContext {
+            options,
+            total,
+            done: AtomicUsize::new(0),
+            reported: AtomicUsize::new(0),
+            stopped: Mutex::new(None),
+        }
+    }
+    /// 是否应该停下。一旦停下，之后总是返回true
+    pub(crate) fn is_stopped(&self) -> bool {
+        let mut stopped = self.stopped.lock().unwrap();
+        if stopped.is_none() {
+            *stopped = self.options.stop_reason();
+        }
+        stopped.is_some()
+    }
+    /// 完成了k份工作量
+    pub(crate) fn advance(&self, k: usize) {
+        let done = (self.done.fetch_add(k, Ordering::SeqCst) + k).min(self.total);
+        if let Some(f) = &self.options.progress {
+            let percent = (done * 100).checked_div(self.total).unwrap_or(100);
+            if self.reported.fetch_max(percent, Ordering::SeqCst) < percent || done == self.total {
+                f(done, self.total);
+            }
+        }
+    }
+    fn status(&self) -> ExecStatus {
+        self.stopped.lock().unwrap().unwrap_or(ExecStatus::Finished)
+    }
+}
+
+/// 把编号为0..task_num的任务分给各线程，按编号从小到大领取。
+/// - 任务函数的参数为编号与上下文，应在适当的时候检查上下文是否应该停下，并报告完成的工作量（总工作量为total）。
+/// - 返回：每个任务的结果（因停下而没有开始的任务为None）、结束的状态。
+pub(crate) fn exec_map<R, F>(
+    options: &ExecOptions,
+    task_num: usize,
+    total: usize,
+    f: F,
+) -> (Vec<Option<R>>, ExecStatus)
+where
+    R: Send,
+    F: Fn(usize, &ExecContext) -> R + Sync,
+{
+    let ctx = ExecContext::new(options, total);
+    let results: Vec<Mutex<Option<R>>> = (0..task_num).map(|_| Mutex::new(None)).collect();
+    let next = AtomicUsize::new(0);
+    let work = || loop {
+        let id = next.fetch_add(1, Ordering::SeqCst);
+        if id >= task_num || ctx.is_stopped() {
+            break;
+        }
+        let r = f(id, &ctx);
+        *results[id].lock().unwrap() = Some(r);
+    };
+    let thread_num = options.thread_num.min(task_num);
+    if thread_num <= 1 {
+        work();
+    } else {
+        thread::scope(|s| {
+            for _ in 0..thread_num {
+                s.spawn(work);
+            }
+        });
+    }
+    let results = results
+        .into_iter()
+        .map(|r| r.into_inner().unwrap())
+        .collect();
+    (results, ctx.status())
+}
+
+/// 找出0..n中使f为真的最小的编号。编号按从小到大被各线程领取，找到后不再领取更大的编号，
+/// 因此没有停下时，结果与线程数无关。总工作量为n，每试一个编号完成1份。
+/// - 注意：停下时返回已经找到的最小的编号，比它小的编号可能还没有试过。
+pub(crate) fn exec_find_first<F>(
+    options: &ExecOptions,
+    n: usize,
+    f: F,
+) -> (Option<usize>, ExecStatus)
+where
+    F: Fn(usize) -> bool + Sync,
+{
+    let ctx = ExecContext::new(options, n);
+    let best = AtomicUsize::new(n);
+    let next = AtomicUsize::new(0);
+    let work = || loop {
+        let id = next.fetch_add(1, Ordering::SeqCst);
+        if id >= best.load(Ordering::SeqCst) || ctx.is_stopped() {
+            break;
+        }
+        if f(id) {
+            best.fetch_min(id, Ordering::SeqCst);
+        }
+        ctx.advance(1);
+    };
+    if options.thread_num <= 1 {
+        work();
+    } else {
+        thread::scope(|s| {
+            for _ in 0..options.thread_num {
+                s.spawn(work);
+            }
+        });
+    }
+    let best = best.load(Ordering::SeqCst);
+    ((best < n).then_some(best), ctx.status())
+}
@@ -0,0 +1,324 @@
+// 带解释的判雷引擎：对每个判出的格子，给出证明它的约束，便于教学时在界面上高亮
+
+use crate::algorithms::solve_enumerate;
+use crate::grid::neighbours;
+use crate::utils::refresh_matrixs;
+
+/// 一个数字给出的约束：cells中恰有mines个雷。cells是该数字周围还没有确定的格子，
+/// mines是数字减去周围已确定的雷。
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Constraint {
+    /// 数字的位置
+    pub number: (usize, usize),
+    pub cells: Vec<(usize, usize)>,
+    pub mines: usize,
+}
+
+/// 双集合判雷中两个约束的未知格之间的关系
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Relation {
+    /// a的未知格包含b的未知格
+    Contains,
+    /// a的未知格包含于b的未知格
+    ContainedIn,
+    /// 相交但互不包含
+    Overlap,
+}
+
+/// 判出某格的证明
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Proof {
+    /// 单集合：约束中的雷数等于格子数（全是雷），或者等于0（全不是雷）
+    Direct(Constraint),
+    /// 双集合：a比b多的雷数恰好等于a独有的格子数，于是a独有的格子全是雷，b独有的格子全不是雷
+    Minus {
+        a: Constraint,
+        b: Constraint,
+        relation: Relation,
+    },
+    /// 枚举：假设该格的状态与结论相反，这些约束就无解。约束的集合是极小的，去掉任何一个都不再矛盾
+    Enumerate(Vec<Constraint>),
+}
+
+/// 一条推理：格子、是否是雷、证明，以及第几轮得出的。
+/// 后一轮的推理以前面各轮的结论为前提。
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Deduction {
+    pub cell: (usize, usize),
+    pub is_mine: bool,
+    pub proof: Proof,
+    pub round: usize,
+}
+
+/// 带解释的判雷引擎。
+/// - 输入：游戏局面。局面中可以有标雷（11）和非雷（12），但必须全部标对。
+/// - 返回：按推理顺序排列的所有能判出的格子及其证明。每一轮都只用最简单的方法：
+///   单集合能判出时不用双集合，双集合能判出时不用枚举。
+/// - 注意：只标雷、不打开格子，因此判出的只是当前局面边缘的格子。
+/// - 用python调用时的示例：
+/// ```python
+/// import ms_toollib as ms
+/// for d in ms.solve_explain(game_board):
+///     print(d["cell"], d["is_mine"], d["method"], d["constraints"])
+/// ```
+pub fn solve_explain(board_of_game: &[Vec<i32>]) -> Vec<Deduction> {
+    let mut board_of_game = board_of_game.to_vec();
+    let mut deductions = vec![];
+    let mut round = 0;
+    loop {
+        let mut found = explain_round(&board_of_game, round);
+        if found.is_empty() {
+            break;
+        }
+        for d in &found {
+            board_of_game[d.cell.0][d.cell.1] = if d.is_mine { 11 } else { 12 };
+        }
+        deductions.append(&mut found);
+        round += 1;
+    }
+    deductions
+}
+
+/// 一轮推理：用能判出格子的最简单的方法，判出当前局面上能判出的所有格子
+pub(crate) fn explain_round(board_of_game: &Vec<Vec<i32>>, round: usize) -> Vec<Deduction> {
+    let constraints = cal_constraints(board_of_game);
+    let mut found = explain_direct(&constraints, round);
+    if found.is_empty() {
+        found = explain_minus(&constraints, round);
+    }
+    if found.is_empty() {
+        found = explain_enumerate(board_of_game, &constraints, round);
+    }
+    found
+}
+
+/// 局面上所有周围还有未确定格子的数字给出的约束
+fn cal_constraints(board_of_game: &[Vec<i32>]) -> Vec<Constraint> {
+    let row = board_of_game.len();
+    let column = board_of_game[0].len();
+    let mut constraints = vec![];
+    for i in 0..row {
+        for j in 0..column {
+            if board_of_game[i][j] < 0 || board_of_game[i][j] > 8 {
+                continue;
+            }
+            let mut cells = vec![];
+            let mut flags = 0;
+            for (m, n) in neighbours(row, column, i, j) {
+                if board_of_game[m][n] == 10 {
+                    cells.push((m, n));
+                } else if board_of_game[m][n] == 11 {
+                    flags += 1;
+                }
+            }
+            if !cells.is_empty() && board_of_game[i][j] >= flags {
+                constraints.push(Constraint {
+                    number: (i, j),
+                    cells,
+                    mines: (board_of_game[i][j] - flags) as usize,
+                });
+            }
+        }
+    }
+    constraints
+}
+
+fn push_deduction(
+    found: &mut Vec<Deduction>,
+    cell: (usize, usize),
+    is_mine: bool,
+    proof: &Proof,
+    round: usize,
+) {
+    if found.iter().all(|d| d.cell != cell) {
+        found.push(Deduction {
+            cell,
+            is_mine,
+            proof: proof.clone(),
+            round,
+        });
+    }
+}
+
+fn explain_direct(constraints: &[Constraint], round: usize) -> Vec<Deduction> {
+    let mut found = vec![];
+    for c in constraints {
+        if c.mines == 0 || c.mines == c.cells.len() {
+            let proof = Proof::Direct(c.clone());
+            for &cell in &c.cells {
+                push_deduction(&mut found, cell, c.mines > 0, &proof, round);
+            }
+        }
+    }
+    found
+}
+
+fn explain_minus(constraints: &[Constraint], round: usize) -> Vec<Deduction> {
+    let mut found = vec![];
+    for i in 0..constraints.len() {
+        for j in 0..constraints.len() {
+            let (a, b) = (&constraints[i], &constraints[j]);
+            if i == j || a.mines < b.mines || !a.cells.iter().any(|x| b.cells.contains(x)) {
+                continue;
+            }
+            let only_a: Vec<_> = a.cells.iter().filter(|x| !b.cells.contains(x)).collect();
+            let only_b: Vec<_> = b.cells.iter().filter(|x| !a.cells.contains(x)).collect();
+            if only_a.len() != a.mines - b.mines || only_a.len() + only_b.len() == 0 {
+                continue;
+            }
+            let relation = if only_b.is_empty() {
+                Relation::Contains
+            } else if only_a.is_empty() {
+                Relation::ContainedIn
+            } else {
+                Relation::Overlap
+            };
+            let proof = Proof::Minus {
+                a: a.clone(),
+                b: b.clone(),
+                relation,
+            };
+            for &&cell in &only_a {
+                push_deduction(&mut found, cell, true, &proof, round);
+            }
+            for &&cell in &only_b {
+                push_deduction(&mut found, cell, false, &proof, round);
+            }
+        }
+    }
+    found
+}
+
+fn explain_enumerate(
+    board_of_game: &Vec<Vec<i32>>,
+    constraints: &[Constraint],
+    round: usize,
+) -> Vec<Deduction> {
+    let (matrix_as, matrix_xs, matrix_bs, _, _) = refresh_matrixs(board_of_game);
+    let (not_mine, is_mine) = solve_enumerate(&matrix_as, &matrix_xs, &matrix_bs);
+    let mut found = vec![];
+    for (cells, mine) in [(not_mine, false), (is_mine, true)] {
+        for cell in cells {
+            let proof = Proof::Enumerate(cal_minimal_contradiction(constraints, cell, mine));
+            push_deduction(&mut found, cell, mine, &proof, round);
+        }
+    }
+    found
+}
+
+/// 假设cell的状态与is_mine相反，找出极小的矛盾约束集：从涉及该格的约束开始向外扩张，直到矛盾，
+/// 再逐个尝试去掉约束
+fn cal_minimal_contradiction(
+    constraints: &[Constraint],
+    cell: (usize, usize),
+    is_mine: bool,
+) -> Vec<Constraint> {
+    let mut ids: Vec<usize> = (0..constraints.len())
+        .filter(|&i| constraints[i].cells.contains(&cell))
+        .collect();
+    while is_satisfiable(constraints, &ids, cell, !is_mine) {
+        let mut new_ids = ids.clone();
+        for i in 0..constraints.len() {
+            if !new_ids.contains(&i)
+                && ids.iter().any(|&j| {
+                    constraints[i]
+                        .cells
+                        .iter()
+                        .any(|x| constraints[j].cells.contains(x))
+                })
+            {
+                new_ids.push(i);
+            }
+        }
+        if new_ids.len() == ids.len() {
+            break;
+        }
+        ids = new_ids;
+    }
+    let mut k = 0;
+    while k < ids.len() {
+        let mut t = ids.clone();
+        t.remove(k);
+        if !is_satisfiable(constraints, &t, cell, !is_mine) {
+            ids = t;
+        } else {
+            k += 1;
+        }
+    }
+    ids.sort_unstable();
+    ids.iter().map(|&i| constraints[i].clone()).collect()
+}
+
+/// 约束集ids在cell的状态固定为is_mine时是否有解
+fn is_satisfiable(
+    constraints: &[Constraint],
+    ids: &[usize],
+    cell: (usize, usize),
+    is_mine: bool,
+) -> bool {
+    let mut cells: Vec<(usize, usize)> = ids
+        .iter()
+        .flat_map(|&i| constraints[i].cells.iter().cloned())
+        .collect();
+    cells.push(cell);
+    cells.sort_unstable();
+    cells.dedup();
+    let cell_to_constraint: Vec<Vec<usize>> = cells
+        .iter()
+        .map(|x| {
+            (0..ids.len())
+                .filter(|&k| constraints[ids[k]].cells.contains(x))
+                .collect()
+        })
+        .collect();
+    let fixed: Vec<Option<bool>> = cells
+        .iter()
+        .map(|x| if *x == cell { Some(is_mine) } else { None })
+        .collect();
+    let mut need: Vec<i32> = ids.iter().map(|&i| constraints[i].mines as i32).collect();
+    let mut left: Vec<i32> = ids
+        .iter()
+        .map(|&i| constraints[i].cells.len() as i32)
+        .collect();
+    search_assignment(0, &cell_to_constraint, &fixed, &mut need, &mut left)
+}
+
+fn search_assignment(
+    k: usize,
+    cell_to_constraint: &[Vec<usize>],
+    fixed: &[Option<bool>],
+    need: &mut [i32],
+    left: &mut [i32],
+) -> bool {
+    if k == cell_to_constraint.len() {
+        return true;
+    }
+    let choices: &[bool] = match fixed[k] {
+        Some(true) => &[true],
+        Some(false) => &[false],
+        None => &[false, true],
+    };
+    for &mine in choices {
+        let mut ok = true;
+        for &e in &cell_to_constraint[k] {
+            left[e] -= 1;
+            if mine {
+                need[e] -= 1;
+            }
+            if need[e] < 0 || need[e] > left[e] {
+                ok = false;
+            }
+        }
+        let ans = ok && search_assignment(k + 1, cell_to_constraint, fixed, need, left);
+        for &e in &cell_to_constraint[k] {
+            left[e] += 1;
+            if mine {
+                need[e] += 1;
+            }
+        }
+        if ans {
+            return true;
+        }
+    }
+    false
+}
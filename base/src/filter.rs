@@ -0,0 +1,335 @@
+// 按条件埋雷：按3BV、空、岛、各数字的数量、是否无猜、难度的范围删选局面，
+// 罕见的条件可以在删选前局部移动雷，向条件靠近
+
+use crate::algorithms::is_solvable;
+use crate::bitboard::BitBoard;
+use crate::difficulty::cal_difficulty;
+use crate::exec::{exec_map, ExecOptions, ExecStatus};
+#[cfg(any(feature = "py", feature = "rs"))]
+use crate::utils::random_seed;
+use crate::utils::SeededRng;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+#[cfg(any(feature = "py", feature = "rs"))]
+use std::time::Duration;
+use std::time::Instant;
+
+/// 多线程按条件埋雷的线程数
+#[cfg(any(feature = "py", feature = "rs"))]
+const FILTER_THREAD_NUM: usize = 8;
+
+/// 按条件埋雷的条件。各范围都是闭区间，默认不限制。
+/// - 用法示例：
+/// ```rust
+/// use ms_toollib::LaymineFilter;
+/// let filter = LaymineFilter {
+///     bbbv: (120, 140),
+///     op: (5, usize::MAX),
+///     ..LaymineFilter::default()
+/// };
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct LaymineFilter {
+    pub bbbv: (usize, usize),
+    pub op: (usize, usize),
+    pub isl: (usize, usize),
+    /// 0到8各数字的数量的范围
+    pub cell_nums: [(usize, usize); 9],
+    /// 是否要求无猜。要求无猜时，起手位置必为空（op）
+    pub solvable: bool,
+    /// 难度分数的范围，见[cal_difficulty](crate::cal_difficulty)
+    pub difficulty: (f64, f64),
+    /// 每次随机埋雷后，最多局部移动雷的次数。为0时是纯删选法，满足条件的局面服从条件下的均匀分布；
+    /// 大于0时每次随机选一个雷移到别处，离条件不变远就接受，罕见的条件能快得多，但局面不再服从均匀分布
+    pub local_moves: usize,
+}
+
+impl Default for LaymineFilter {
+    fn default() -> Self {
+        LaymineFilter {
+            bbbv: (0, usize::MAX),
+            op: (0, usize::MAX),
+            isl: (0, usize::MAX),
+            cell_nums: [(0, usize::MAX); 9],
+            solvable: false,
+            difficulty: (0.0, f64::INFINITY),
+            local_moves: 0,
+        }
+    }
+}
+
+/// 按条件埋雷的统计
+#[derive(Debug, Clone, PartialEq)]
+pub struct LaymineFilterStats {
+    /// 随机埋雷的次数
+    pub attempts: usize,
+    /// 满足条件的局面数
+    pub accepted: usize,
+    /// 用时，单位为毫秒
+    pub time_ms: u64,
+    /// 是否因为用完了时间或尝试次数而没有埋够局面
+    pub exhausted: bool,
+}
+
+impl LaymineFilterStats {
+    /// 接受率，即满足条件的局面数除以随机埋雷的次数
+    pub fn acceptance_rate(&self) -> f64 {
+        if self.attempts == 0 {
+            0.0
+        } else {
+            self.accepted as f64 / self.attempts as f64
+        }
+    }
+}
+
+/// 多线程按条件埋雷，直到埋够局面或用完时间。
+/// - 输入：高、宽、雷数、起手行数、起手列数、条件、局面数、限时（毫秒）。
+/// - 返回：满足条件的局面（不多于局面数）、统计。
+/// - 原理：各线程不停地随机埋雷，按从快到慢的顺序（数字的数量、空、3BV、岛、无猜、难度）检查条件，一项不满足就放弃。
+///   条件设了local_moves时，先局部移动雷向条件靠近，再检查。
+/// - 注意：限时在每次埋雷之间检查，实际用时可能略多于限时。统计中的接受率可以用来估计条件有多罕见。
+/// - 用python调用时的示例：
+/// ```python
+/// import ms_toollib as ms
+/// (boards, stats) = ms.laymine_filtered(16, 30, 99, 3, 20, 10, bbbv=(120, 140), op=(5, 1000), time_limit_ms=2000)
+/// print(len(boards), stats["acceptance_rate"])
+/// ```
+#[cfg(any(feature = "py", feature = "rs"))]
+#[allow(clippy::too_many_arguments)]
+pub fn laymine_filtered(
+    row: usize,
+    column: usize,
+    mine_num: usize,
+    x0: usize,
+    y0: usize,
+    filter: &LaymineFilter,
+    n: usize,
+    time_limit_ms: u64,
+) -> (Vec<Vec<Vec<i32>>>, LaymineFilterStats) {
+    let options = ExecOptions {
+        thread_num: FILTER_THREAD_NUM,
+        deadline: Some(Instant::now() + Duration::from_millis(time_limit_ms)),
+        ..ExecOptions::default()
+    };
+    let (boards, stats, _) = laymine_filtered_exec(
+        row,
+        column,
+        mine_num,
+        x0,
+        y0,
+        filter,
+        n,
+        random_seed(),
+        &options,
+    );
+    (boards, stats)
+}
+
+/// 可设执行选项的按条件埋雷，直到埋够局面、被取消或超时。
+/// - 输入：高、宽、雷数、起手行数、起手列数、条件、局面数、种子、执行选项。
+/// - 返回：满足条件的局面（不多于局面数）、统计、结束的状态。进度的总工作量为局面数。
+/// - 注意：每个线程使用种子的一个流，但各线程埋够局面的先后与调度有关，因此多线程时结果不可复现，
+///   需要可复现时用[laymine_filtered_seed]。
+#[allow(clippy::too_many_arguments)]
+pub fn laymine_filtered_exec(
+    row: usize,
+    column: usize,
+    mine_num: usize,
+    x0: usize,
+    y0: usize,
+    filter: &LaymineFilter,
+    n: usize,
+    seed: u64,
+    options: &ExecOptions,
+) -> (Vec<Vec<Vec<i32>>>, LaymineFilterStats, ExecStatus) {
+    let start_time = Instant::now();
+    let boards = Mutex::new(vec![]);
+    let attempts = AtomicUsize::new(0);
+    let task_num = options.thread_num.max(1);
+    let (_, status) = exec_map(options, task_num, n, |t, ctx| {
+        let mut sampler = match FilterSampler::new(row, column, mine_num, x0, y0, filter.clone()) {
+            Some(s) => s,
+            None => return,
+        };
+        let mut rng = SeededRng::new_stream(seed, t as u64);
+        while boards.lock().unwrap().len() < n && !ctx.is_stopped() {
+            attempts.fetch_add(1, Ordering::SeqCst);
+            if let Some(board) = sampler.sample(&mut rng) {
+                let mut b = boards.lock().unwrap();
+                if b.len() < n {
+                    b.push(board);
+                    ctx.advance(1);
+                }
+            }
+        }
+    });
+    let boards = boards.into_inner().unwrap();
+    let stats = LaymineFilterStats {
+        attempts: attempts.into_inner(),
+        accepted: boards.len(),
+        time_ms: start_time.elapsed().as_millis() as u64,
+        exhausted: boards.len() < n,
+    };
+    (boards, stats, status)
+}
+
+/// 可设种子的单线程按条件埋雷。相同的种子与参数，在各平台埋出完全相同的局面。
+/// - 输入：高、宽、雷数、起手行数、起手列数、条件、局面数、最大尝试次数、种子。
+/// - 返回：满足条件的局面（不多于局面数）、统计。统计中的用时恒为0。
+/// - 注意：用最大尝试次数而不是限时控制用时，以保证结果可复现。
+#[allow(clippy::too_many_arguments)]
+pub fn laymine_filtered_seed(
+    row: usize,
+    column: usize,
+    mine_num: usize,
+    x0: usize,
+    y0: usize,
+    filter: &LaymineFilter,
+    n: usize,
+    max_times: usize,
+    seed: u64,
+) -> (Vec<Vec<Vec<i32>>>, LaymineFilterStats) {
+    let mut boards = vec![];
+    let mut attempts = 0;
+    if let Some(mut sampler) = FilterSampler::new(row, column, mine_num, x0, y0, filter.clone()) {
+        let mut rng = SeededRng::new(seed);
+        while boards.len() < n && attempts < max_times {
+            attempts += 1;
+            if let Some(board) = sampler.sample(&mut rng) {
+                boards.push(board);
+            }
+        }
+    }
+    let stats = LaymineFilterStats {
+        attempts,
+        accepted: boards.len(),
+        time_ms: 0,
+        exhausted: boards.len() < n,
+    };
+    (boards, stats)
+}
+
+/// 单个线程的埋雷状态，重复使用内存
+struct FilterSampler {
+    x0: usize,
+    y0: usize,
+    mine_num: usize,
+    filter: LaymineFilter,
+    /// 可以埋雷的格子。每次埋雷后，前mine_num个是雷
+    cells: Vec<(usize, usize)>,
+    bit_board: BitBoard,
+}
+
+impl FilterSampler {
+    /// 可以埋雷的格子不够时返回None
+    fn new(
+        row: usize,
+        column: usize,
+        mine_num: usize,
+        x0: usize,
+        y0: usize,
+        filter: LaymineFilter,
+    ) -> Option<FilterSampler> {
+        let mut cells = vec![];
+        for i in 0..row {
+            for j in 0..column {
+                let forbidden = if filter.solvable {
+                    i.abs_diff(x0) <= 1 && j.abs_diff(y0) <= 1
+                } else {
+                    (i, j) == (x0, y0)
+                };
+                if !forbidden {
+                    cells.push((i, j));
+                }
+            }
+        }
+        if cells.len() < mine_num {
+            return None;
+        }
+        Some(FilterSampler {
+            x0,
+            y0,
+            mine_num,
+            filter,
+            cells,
+            bit_board: BitBoard::new(row, column),
+        })
+    }
+    /// 随机埋一次雷，满足条件时返回局面
+    fn sample(&mut self, rng: &mut SeededRng) -> Option<Vec<Vec<i32>>> {
+        // 部分的Fisher-Yates洗牌，只洗出前mine_num个
+        self.bit_board.clear();
+        for i in 0..self.mine_num {
+            let id = i + rng.gen_range(self.cells.len() - i);
+            self.cells.swap(i, id);
+            self.bit_board.set_mine(self.cells[i]);
+        }
+        let mut miss = self.cal_miss(self.filter.local_moves == 0);
+        if miss > 0 && self.mine_num > 0 && self.mine_num < self.cells.len() {
+            for _ in 0..self.filter.local_moves {
+                let a = rng.gen_range(self.mine_num);
+                let b = self.mine_num + rng.gen_range(self.cells.len() - self.mine_num);
+                self.bit_board.remove_mine(self.cells[a]);
+                self.bit_board.set_mine(self.cells[b]);
+                let new_miss = self.cal_miss(false);
+                if new_miss <= miss {
+                    self.cells.swap(a, b);
+                    miss = new_miss;
+                    if miss == 0 {
+                        break;
+                    }
+                } else {
+                    self.bit_board.remove_mine(self.cells[b]);
+                    self.bit_board.set_mine(self.cells[a]);
+                }
+            }
+        }
+        if miss > 0 {
+            return None;
+        }
+        let board = self.bit_board.to_board();
+        if self.filter.solvable && !is_solvable(&board, self.x0, self.y0) {
+            return None;
+        }
+        if self.filter.difficulty != (0.0, f64::INFINITY) {
+            let score = cal_difficulty(&board, self.x0, self.y0).score;
+            if score < self.filter.difficulty.0 || score > self.filter.difficulty.1 {
+                return None;
+            }
+        }
+        Some(board)
+    }
+    /// 各项指标离条件的距离之和，为0说明满足条件（无猜除外）。early_exit时，一项不满足就返回
+    fn cal_miss(&self, early_exit: bool) -> usize {
+        fn out_of(v: usize, range: (usize, usize)) -> usize {
+            range.0.saturating_sub(v) + v.saturating_sub(range.1)
+        }
+        let f = &self.filter;
+        let mut miss = 0;
+        if f.cell_nums != [(0, usize::MAX); 9] {
+            let cell_nums = self.bit_board.cal_cell_nums();
+            for (&c, &r) in cell_nums.iter().zip(f.cell_nums.iter()) {
+                miss += out_of(c, r);
+            }
+            if early_exit && miss > 0 {
+                return miss;
+            }
+        }
+        if f.op != (0, usize::MAX) {
+            miss += out_of(self.bit_board.cal_op(), f.op);
+            if early_exit && miss > 0 {
+                return miss;
+            }
+        }
+        if f.bbbv != (0, usize::MAX) {
+            miss += out_of(self.bit_board.cal_bbbv(), f.bbbv);
+            if early_exit && miss > 0 {
+                return miss;
+            }
+        }
+        if f.isl != (0, usize::MAX) {
+            miss += out_of(self.bit_board.cal_isl(), f.isl);
+        }
+        miss
+    }
+}
@@ -0,0 +1,246 @@
+// 格子与网格的类型。对外的接口仍以Vec<Vec<i32>>为主，这里提供与之互相转换的方法。
+
+use std::convert::TryFrom;
+use std::ops::{Index, IndexMut};
+
+/// 局面上的一个格子。与i32的对应关系见`Cell::from_i32`。
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum Cell {
+    /// 雷，只出现在真实局面中（-1）
+    Mine,
+    /// 数字0~8，在游戏局面中表示已打开
+    Number(u8),
+    /// 未打开（10）
+    Covered,
+    /// 玩家标的雷，或算法确定的雷（11）
+    Flag,
+    /// 算法确定的非雷，仍未打开（12）
+    Safe,
+    /// 游戏失败后显示的标错的雷，即叉雷（14）
+    WrongFlag,
+    /// 游戏失败时踩到的雷，即红雷（15）
+    ExplodedMine,
+    /// 游戏失败后显示出来的其他的雷（16）
+    RevealedMine,
+    /// 双击时高亮、看起来像0的格子（18）
+    Pressed,
+}
+
+impl Cell {
+    /// -1为雷，0~8为数字，10未打开，11标雷，12确定非雷，14叉雷，15红雷，16失败后显示的雷，18双击高亮。
+    pub fn from_i32(v: i32) -> Option<Cell> {
+        match v {
+            -1 => Some(Cell::Mine),
+            0..=8 => Some(Cell::Number(v as u8)),
+            10 => Some(Cell::Covered),
+            11 => Some(Cell::Flag),
+            12 => Some(Cell::Safe),
+            14 => Some(Cell::WrongFlag),
+            15 => Some(Cell::ExplodedMine),
+            16 => Some(Cell::RevealedMine),
+            18 => Some(Cell::Pressed),
+            _ => None,
+        }
+    }
+    pub fn to_i32(self) -> i32 {
+        match self {
+            Cell::Mine => -1,
+            Cell::Number(n) => n as i32,
+            Cell::Covered => 10,
+            Cell::Flag => 11,
+            Cell::Safe => 12,
+            Cell::WrongFlag => 14,
+            Cell::ExplodedMine => 15,
+            Cell::RevealedMine => 16,
+            Cell::Pressed => 18,
+        }
+    }
+    /// 是数字（包括0）时返回数字
+    pub fn number(self) -> Option<u8> {
+        match self {
+            Cell::Number(n) => Some(n),
+            _ => None,
+        }
+    }
+    /// 在游戏局面中是否还没有被打开（标雷、确定非雷、高亮等都算没有打开）
+    pub fn is_covered(self) -> bool {
+        !matches!(self, Cell::Number(_) | Cell::Mine)
+    }
+}
+
+impl TryFrom<i32> for Cell {
+    type Error = i32;
+    fn try_from(v: i32) -> Result<Self, Self::Error> {
+        Cell::from_i32(v).ok_or(v)
+    }
+}
+
+impl From<Cell> for i32 {
+    fn from(cell: Cell) -> i32 {
+        cell.to_i32()
+    }
+}
+
+/// 兼容旧的整数局面：可以直接写`game_board[x][y] == Cell::Covered`
+impl PartialEq<Cell> for i32 {
+    fn eq(&self, other: &Cell) -> bool {
+        *self == other.to_i32()
+    }
+}
+
+impl PartialEq<i32> for Cell {
+    fn eq(&self, other: &i32) -> bool {
+        self.to_i32() == *other
+    }
+}
+
+/// 某格周围（不含自身）的格子的迭代器，按行优先的顺序。
+#[derive(Debug, Clone)]
+pub struct Neighbours {
+    x: usize,
+    y: usize,
+    i: usize,
+    j: usize,
+    i_end: usize,
+    j_start: usize,
+    j_end: usize,
+}
+
+impl Iterator for Neighbours {
+    type Item = (usize, usize);
+    fn next(&mut self) -> Option<(usize, usize)> {
+        while self.i < self.i_end {
+            let pos = (self.i, self.j);
+            self.j += 1;
+            if self.j == self.j_end {
+                self.j = self.j_start;
+                self.i += 1;
+            }
+            if pos != (self.x, self.y) {
+                return Some(pos);
+            }
+        }
+        None
+    }
+}
+
+/// 高为row、宽为column的局面上，(x, y)周围（不含自身）的格子
+pub fn neighbours(row: usize, column: usize, x: usize, y: usize) -> Neighbours {
+    let j_start = if y > 0 { y - 1 } else { 0 };
+    Neighbours {
+        x,
+        y,
+        i: if x > 0 { x - 1 } else { 0 },
+        j: j_start,
+        i_end: (x + 2).min(row),
+        j_start,
+        j_end: (y + 2).min(column),
+    }
+}
+
+/// 行优先存储的二维网格，第一个索引是行，第二个索引是列。
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub struct Grid<T> {
+    row: usize,
+    column: usize,
+    cells: Vec<T>,
+}
+
+impl<T: Clone> Grid<T> {
+    pub fn new(row: usize, column: usize, value: T) -> Grid<T> {
+        Grid {
+            row,
+            column,
+            cells: vec![value; row * column],
+        }
+    }
+    /// 从嵌套的Vec构造，各行的长度必须一致
+    pub fn from_vec(board: &[Vec<T>]) -> Grid<T> {
+        let row = board.len();
+        let column = if row > 0 { board[0].len() } else { 0 };
+        let cells: Vec<T> = board.iter().flatten().cloned().collect();
+        assert_eq!(cells.len(), row * column, "各行的长度不一致");
+        Grid { row, column, cells }
+    }
+    /// 转回嵌套的Vec
+    pub fn to_vec(&self) -> Vec<Vec<T>> {
+        if self.column == 0 {
+            return vec![vec![]; self.row];
+        }
+        self.cells.chunks(self.column).map(|r| r.to_vec()).collect()
+    }
+}
+
+impl<T> Grid<T> {
+    pub fn row(&self) -> usize {
+        self.row
+    }
+    pub fn column(&self) -> usize {
+        self.column
+    }
+    pub fn get(&self, x: usize, y: usize) -> Option<&T> {
+        if x < self.row && y < self.column {
+            self.cells.get(x * self.column + y)
+        } else {
+            None
+        }
+    }
+    /// (x, y)周围（不含自身）的格子
+    pub fn neighbours(&self, x: usize, y: usize) -> Neighbours {
+        neighbours(self.row, self.column, x, y)
+    }
+    /// 所有格子的位置，行优先
+    pub fn positions(&self) -> impl Iterator<Item = (usize, usize)> {
+        let column = self.column;
+        (0..self.row * column).map(move |id| (id / column, id % column))
+    }
+    /// 所有格子的位置与值，行优先
+    pub fn iter(&self) -> impl Iterator<Item = ((usize, usize), &T)> {
+        self.positions().zip(self.cells.iter())
+    }
+    pub fn map<U>(&self, f: impl Fn(&T) -> U) -> Grid<U> {
+        Grid {
+            row: self.row,
+            column: self.column,
+            cells: self.cells.iter().map(f).collect(),
+        }
+    }
+}
+
+impl<T> Index<(usize, usize)> for Grid<T> {
+    type Output = T;
+    fn index(&self, (x, y): (usize, usize)) -> &T {
+        assert!(x < self.row && y < self.column, "坐标越界");
+        &self.cells[x * self.column + y]
+    }
+}
+
+impl<T> IndexMut<(usize, usize)> for Grid<T> {
+    fn index_mut(&mut self, (x, y): (usize, usize)) -> &mut T {
+        assert!(x < self.row && y < self.column, "坐标越界");
+        &mut self.cells[x * self.column + y]
+    }
+}
+
+impl Grid<Cell> {
+    /// 从整数表示的局面或游戏局面转换，遇到不认识的值时返回其坐标
+    pub fn from_i32_board(board: &[Vec<i32>]) -> Result<Grid<Cell>, (usize, usize)> {
+        let grid = Grid::from_vec(board);
+        let mut cells = Vec::with_capacity(grid.cells.len());
+        for (pos, &v) in grid.iter() {
+            match Cell::from_i32(v) {
+                Some(c) => cells.push(c),
+                None => return Err(pos),
+            }
+        }
+        Ok(Grid {
+            row: grid.row,
+            column: grid.column,
+            cells,
+        })
+    }
+    /// 转回整数表示的局面或游戏局面
+    pub fn to_i32_board(&self) -> Vec<Vec<i32>> {
+        self.map(|c| c.to_i32()).to_vec()
+    }
+}
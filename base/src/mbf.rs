@@ -0,0 +1,91 @@
+// mbf局面文件。Minesweeper Arbiter、Minesweeper Clone等软件用它交换固定的局面。
+// 格式：宽（1字节）、高（1字节）、雷数（2字节，高位在前），随后是每个雷的(列, 行)，各1字节。
+
+use std::fs;
+
+/// 读写mbf局面失败的原因
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ErrMbf {
+    CanNotFindFile,
+    FileIsTooShort,
+    /// 局面的高或宽为0或超过255
+    InvalidBoardSize,
+    /// 雷的位置越界或重复
+    InvalidMinePosition,
+    /// 写文件失败，例如目录不存在或没有权限
+    CanNotWriteFile,
+}
+
+/// 解析mbf文件的二进制数据为局面。数字由雷重新计算。
+pub fn decode_mbf(raw_data: &[u8]) -> Result<Vec<Vec<i32>>, ErrMbf> {
+    if raw_data.len() < 4 {
+        return Err(ErrMbf::FileIsTooShort);
+    }
+    let width = raw_data[0] as usize;
+    let height = raw_data[1] as usize;
+    if width == 0 || height == 0 {
+        return Err(ErrMbf::InvalidBoardSize);
+    }
+    let mine_num = (raw_data[2] as usize) << 8 | raw_data[3] as usize;
+    if raw_data.len() < 4 + 2 * mine_num {
+        return Err(ErrMbf::FileIsTooShort);
+    }
+    let mut board = vec![vec![0; width]; height];
+    for i in 0..mine_num {
+        let y = raw_data[4 + 2 * i] as usize;
+        let x = raw_data[5 + 2 * i] as usize;
+        if x >= height || y >= width || board[x][y] == -1 {
+            return Err(ErrMbf::InvalidMinePosition);
+        }
+        board[x][y] = -1;
+    }
+    crate::utils::cal_board_numbers(&mut board);
+    Ok(board)
+}
+
+/// 把局面编码为mbf文件的二进制数据。高、宽都不能超过255。
+pub fn encode_mbf(board: &[Vec<i32>]) -> Result<Vec<u8>, ErrMbf> {
+    let height = board.len();
+    if height == 0 || height > 255 || board[0].is_empty() || board[0].len() > 255 {
+        return Err(ErrMbf::InvalidBoardSize);
+    }
+    let width = board[0].len();
+    let mut raw_data = vec![width as u8, height as u8, 0, 0];
+    let mut mine_num = 0;
+    for j in 0..width {
+        for (i, r) in board.iter().enumerate() {
+            if r[j] == -1 {
+                raw_data.push(j as u8);
+                raw_data.push(i as u8);
+                mine_num += 1;
+            }
+        }
+    }
+    raw_data[2] = (mine_num >> 8) as u8;
+    raw_data[3] = (mine_num % 256) as u8;
+    Ok(raw_data)
+}
+
+/// 读取mbf文件为局面
+pub fn read_mbf_file(file_name: &str) -> Result<Vec<Vec<i32>>, ErrMbf> {
+    match fs::read(file_name) {
+        Ok(raw_data) => decode_mbf(&raw_data),
+        Err(_) => Err(ErrMbf::CanNotFindFile),
+    }
+}
+
+/// 把局面保存为mbf文件，文件名不用带后缀。重名时自动加上编号，例如"board(2).mbf"。
+/// 返回实际保存的文件名。写文件失败时返回CanNotWriteFile。
+pub fn save_to_mbf_file(board: &[Vec<i32>], file_name: &str) -> Result<String, ErrMbf> {
+    let raw_data = encode_mbf(board)?;
+    let mut new_file_name = file_name.to_string() + ".mbf";
+    let mut id = 2;
+    while std::path::Path::new(&new_file_name).exists() {
+        new_file_name = format!("{}({}).mbf", file_name, id);
+        id += 1;
+    }
+    match fs::write(&new_file_name, &raw_data) {
+        Ok(_) => Ok(new_file_name),
+        Err(_) => Err(ErrMbf::CanNotWriteFile),
+    }
+}
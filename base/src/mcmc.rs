@@ -0,0 +1,183 @@
+// 马尔可夫链蒙特卡洛（MCMC）无猜埋雷：在无猜局面的集合上随机地交换雷，使局面近似服从该集合上的均匀分布
+
+use crate::algorithms::is_solvable;
+use crate::bitboard::BitBoard;
+use crate::grid::neighbours;
+use crate::utils::{random_seed, SeededRng};
+
+/// 找初始的无猜局面时，平均每个雷最多尝试加雷的次数
+const MCMC_INIT_TIMES: usize = 100;
+
+/// MCMC无猜埋雷的混合诊断，用于判断步数是否足够
+#[derive(Debug, Clone, PartialEq)]
+pub struct McmcDiagnostics {
+    /// 走的步数，即提议交换的次数
+    pub steps: usize,
+    /// 被接受的交换次数
+    pub accepted: usize,
+    /// 初始局面中从来没有被移动过的雷的数量。明显大于0说明链还记得初始局面，需要更多的步数
+    pub unmoved_mines: usize,
+    /// 链上各步局面的3BV的有效样本量。越接近步数说明混合得越好；只有个位数说明步数远远不够
+    pub bbbv_effective_sample_size: f64,
+}
+
+/// MCMC无猜埋雷。可以生成高密度的无猜局面，且雷近似服从所有无猜局面上的均匀分布。
+/// - 输入：高、宽、雷数、起手行数、起手列数、步数。
+/// - 返回：局面、是否成功、混合诊断。
+/// - 原理：先从没有雷的局面开始逐个随机加雷（加雷后仍然无猜才接受），得到一个无猜局面；然后每一步随机选一个雷和一个非雷
+///   （都不在起手的3*3内）交换，交换后仍然无猜就接受，否则退回。提议是对称的，所以链的平稳分布是交换能到达的无猜局面上的均匀分布。
+///   无猜的判据与[laymine_solvable](crate::laymine_solvable)相同，即[is_solvable](crate::is_solvable)。
+/// - 注意：步数有限时只是近似均匀。步数建议取雷数的数十倍，并检查诊断中的unmoved_mines为0、
+///   bbbv_effective_sample_size足够大。雷数太多、加雷加不上去时返回失败，此时局面的雷数不足。
+/// - 用python调用时的示例：
+/// ```python
+/// import ms_toollib as ms
+/// (board, flag, diagnostics) = ms.laymine_solvable_mcmc(16, 30, 150, 3, 20, 5000)
+/// print(diagnostics["accepted"], diagnostics["unmoved_mines"])
+/// ```
+pub fn laymine_solvable_mcmc(
+    row: usize,
+    column: usize,
+    mine_num: usize,
+    x0: usize,
+    y0: usize,
+    steps: usize,
+) -> (Vec<Vec<i32>>, bool, McmcDiagnostics) {
+    laymine_solvable_mcmc_seed(row, column, mine_num, x0, y0, steps, random_seed())
+}
+
+/// 可设种子的MCMC无猜埋雷。相同的种子与参数，在各平台埋出完全相同的局面。
+/// - 输入：高、宽、雷数、起手行数、起手列数、步数、种子。
+/// - 返回：局面、是否成功、混合诊断。
+pub fn laymine_solvable_mcmc_seed(
+    row: usize,
+    column: usize,
+    mine_num: usize,
+    x0: usize,
+    y0: usize,
+    steps: usize,
+    seed: u64,
+) -> (Vec<Vec<i32>>, bool, McmcDiagnostics) {
+    let mut diagnostics = McmcDiagnostics {
+        steps: 0,
+        accepted: 0,
+        unmoved_mines: mine_num,
+        bbbv_effective_sample_size: 0.0,
+    };
+    let mut board = vec![vec![0; column]; row];
+    // 起手的3*3内不能有雷，不参与交换
+    let mut mines = vec![];
+    let mut safes = vec![];
+    for i in 0..row {
+        for j in 0..column {
+            if i.abs_diff(x0) > 1 || j.abs_diff(y0) > 1 {
+                safes.push((i, j));
+            }
+        }
+    }
+    if safes.len() < mine_num {
+        return (board, false, diagnostics);
+    }
+    let mut rng = SeededRng::new(seed);
+    // 从没有雷的局面开始逐个随机加雷，加雷后仍然无猜才接受，得到初始的无猜局面
+    let mut tries = 0;
+    while mines.len() < mine_num {
+        if tries >= MCMC_INIT_TIMES * mine_num {
+            return (board, false, diagnostics);
+        }
+        tries += 1;
+        let b = rng.gen_range(safes.len());
+        let s = safes[b];
+        add_mine(&mut board, s);
+        if is_solvable(&board, x0, y0) {
+            mines.push(s);
+            safes.swap_remove(b);
+        } else {
+            remove_mine(&mut board, s);
+        }
+    }
+    if mines.is_empty() || safes.is_empty() {
+        return (board, true, diagnostics);
+    }
+    let mut unmoved = vec![vec![false; column]; row];
+    for &(i, j) in &mines {
+        unmoved[i][j] = true;
+    }
+    let mut bit_board = BitBoard::from_board(&board);
+    let mut bbbvs = Vec::with_capacity(steps);
+    for _ in 0..steps {
+        let a = rng.gen_range(mines.len());
+        let b = rng.gen_range(safes.len());
+        let (m, s) = (mines[a], safes[b]);
+        move_mine(&mut board, m, s);
+        if is_solvable(&board, x0, y0) {
+            mines[a] = s;
+            safes[b] = m;
+            unmoved[m.0][m.1] = false;
+            bit_board.remove_mine(m);
+            bit_board.set_mine(s);
+            diagnostics.accepted += 1;
+        } else {
+            move_mine(&mut board, s, m);
+        }
+        bbbvs.push(bit_board.cal_bbbv() as f64);
+    }
+    diagnostics.steps = steps;
+    diagnostics.unmoved_mines = unmoved.iter().flatten().filter(|&&u| u).count();
+    diagnostics.bbbv_effective_sample_size = cal_effective_sample_size(&bbbvs);
+    (board, true, diagnostics)
+}
+
+/// 在pos处（不是雷）埋雷，同时更新周围的数字
+fn add_mine(board: &mut [Vec<i32>], pos: (usize, usize)) {
+    for (i, j) in neighbours(board.len(), board[0].len(), pos.0, pos.1) {
+        if board[i][j] >= 0 {
+            board[i][j] += 1;
+        }
+    }
+    board[pos.0][pos.1] = -1;
+}
+
+/// 去掉pos处的雷，同时更新周围的数字
+fn remove_mine(board: &mut [Vec<i32>], pos: (usize, usize)) {
+    board[pos.0][pos.1] = 0;
+    for (i, j) in neighbours(board.len(), board[0].len(), pos.0, pos.1) {
+        if board[i][j] >= 0 {
+            board[i][j] -= 1;
+        } else {
+            board[pos.0][pos.1] += 1;
+        }
+    }
+}
+
+/// 把from处的雷移到to处（to不是雷）
+fn move_mine(board: &mut [Vec<i32>], from: (usize, usize), to: (usize, usize)) {
+    remove_mine(board, from);
+    add_mine(board, to);
+}
+
+/// 序列的有效样本量n / (1 + 2Σρ_k)，自相关ρ_k第一次不为正时截断。序列不变时返回1
+fn cal_effective_sample_size(xs: &[f64]) -> f64 {
+    let n = xs.len();
+    if n < 2 {
+        return n as f64;
+    }
+    let mean = xs.iter().sum::<f64>() / n as f64;
+    let var = xs.iter().map(|x| (x - mean) * (x - mean)).sum::<f64>() / n as f64;
+    if var <= 0.0 {
+        return 1.0;
+    }
+    let mut tau = 1.0;
+    for k in 1..n / 2 {
+        let rho = (0..n - k)
+            .map(|i| (xs[i] - mean) * (xs[i + k] - mean))
+            .sum::<f64>()
+            / n as f64
+            / var;
+        if rho <= 0.0 {
+            break;
+        }
+        tau += 2.0 * rho;
+    }
+    n as f64 / tau
+}
@@ -0,0 +1,367 @@
+// 定式识别：在游戏局面上找出1-1、1-2、1-2-1等经典的局部定式，用于教学与统计
+
+use crate::grid::neighbours;
+use crate::utils::refresh_matrixs;
+
+/// 模板中的一格
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum PatternCell {
+    /// 已打开的数字，数值是减去周围标雷后的有效数字（'0'~'8'）
+    Number(u8),
+    /// 未打开、定式不能确定的格子（'.'）
+    Covered,
+    /// 未打开、定式推出是雷的格子（'m'）
+    Mine,
+    /// 未打开、定式推出不是雷的格子（'s'）
+    Safe,
+    /// 局面内、不是未打开的格子，即数字、标雷或确定的非雷（'#'）
+    Known,
+    /// 局面外，即墙（'X'）
+    Wall,
+    /// 任意（'?'）
+    Any,
+}
+
+impl PatternCell {
+    fn is_covered(self) -> bool {
+        matches!(
+            self,
+            PatternCell::Covered | PatternCell::Mine | PatternCell::Safe
+        )
+    }
+}
+
+/// 构造定式失败的原因
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ErrPattern {
+    EmptyTemplate,
+    InconsistentRowLength,
+    InvalidCharacter(char),
+    /// 模板中未打开的格子超过16个
+    TemplateTooLarge,
+    /// 模板中的数字互相矛盾
+    Contradictory,
+    /// 标为'm'或's'的格子不能由模板中的数字推出，参数为其在模板中的位置
+    ImpliedCellNotForced(usize, usize),
+}
+
+/// 转到某个方向后的模板
+type OrientedCells = (Orientation, Vec<Vec<PatternCell>>);
+
+/// 定式的方向：先左右镜像（可选），再顺时针旋转rotation个90度
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct Orientation {
+    pub rotation: usize,
+    pub mirrored: bool,
+}
+
+/// 一个具名的定式。
+/// - 模板按行书写，行之间用'/'或换行分隔。字符含义见[PatternCell]。
+/// - 模板中每个数字周围的未打开格子都必须在模板里，且标为'.'、'm'或's'，匹配时会检查这一点，
+///   因此模板只需要写出定式本身。
+/// - 构造时用枚举检查'm'、's'确实能由模板中的数字推出，用户自定义的定式也不会给出错误的结论。
+#[derive(Debug, PartialEq, Clone)]
+pub struct Pattern {
+    pub name: String,
+    cells: Vec<Vec<PatternCell>>,
+}
+
+impl Pattern {
+    /// 例如`Pattern::new("1-2-1", "smsms/?121?")`
+    pub fn new(name: &str, template: &str) -> Result<Pattern, ErrPattern> {
+        let mut cells: Vec<Vec<PatternCell>> = vec![];
+        for line in template.split(['/', '\n']) {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let mut row = vec![];
+            for c in line.chars() {
+                row.push(match c {
+                    '0'..='8' => PatternCell::Number(c as u8 - b'0'),
+                    '.' => PatternCell::Covered,
+                    'm' => PatternCell::Mine,
+                    's' => PatternCell::Safe,
+                    '#' => PatternCell::Known,
+                    'X' => PatternCell::Wall,
+                    '?' => PatternCell::Any,
+                    _ => return Err(ErrPattern::InvalidCharacter(c)),
+                });
+            }
+            if !cells.is_empty() && row.len() != cells[0].len() {
+                return Err(ErrPattern::InconsistentRowLength);
+            }
+            cells.push(row);
+        }
+        if cells.is_empty() {
+            return Err(ErrPattern::EmptyTemplate);
+        }
+        let pattern = Pattern {
+            name: name.to_string(),
+            cells,
+        };
+        pattern.check()?;
+        Ok(pattern)
+    }
+    /// 模板，方向为默认方向
+    pub fn cells(&self) -> &Vec<Vec<PatternCell>> {
+        &self.cells
+    }
+    /// 枚举模板中未打开格子的所有情况，检查推出的格子
+    fn check(&self) -> Result<(), ErrPattern> {
+        let row = self.cells.len();
+        let column = self.cells[0].len();
+        let mut covered = vec![];
+        for i in 0..row {
+            for j in 0..column {
+                if self.cells[i][j].is_covered() {
+                    covered.push((i, j));
+                }
+            }
+        }
+        if covered.len() > 16 {
+            return Err(ErrPattern::TemplateTooLarge);
+        }
+        let mut equations = vec![];
+        for i in 0..row {
+            for j in 0..column {
+                if let PatternCell::Number(n) = self.cells[i][j] {
+                    let ids: Vec<usize> = neighbours(row, column, i, j)
+                        .filter_map(|pos| covered.iter().position(|&x| x == pos))
+                        .collect();
+                    equations.push((ids, n as u32));
+                }
+            }
+        }
+        let mut always_mine = (1u32 << covered.len()) - 1;
+        let mut always_safe = always_mine;
+        let mut solvable = false;
+        for s in 0u32..1 << covered.len() {
+            if equations
+                .iter()
+                .all(|(ids, n)| ids.iter().map(|&k| (s >> k) & 1).sum::<u32>() == *n)
+            {
+                solvable = true;
+                always_mine &= s;
+                always_safe &= !s;
+            }
+        }
+        if !solvable {
+            return Err(ErrPattern::Contradictory);
+        }
+        for (k, &(i, j)) in covered.iter().enumerate() {
+            let forced = match self.cells[i][j] {
+                PatternCell::Mine => always_mine >> k & 1 == 1,
+                PatternCell::Safe => always_safe >> k & 1 == 1,
+                _ => true,
+            };
+            if !forced {
+                return Err(ErrPattern::ImpliedCellNotForced(i, j));
+            }
+        }
+        Ok(())
+    }
+    /// 所有不同的方向
+    fn orientations(&self) -> Vec<OrientedCells> {
+        let mut ans: Vec<OrientedCells> = vec![];
+        for mirrored in [false, true] {
+            let mut cells = self.cells.clone();
+            if mirrored {
+                cells.iter_mut().for_each(|r| r.reverse());
+            }
+            for rotation in 0..4 {
+                if ans.iter().all(|(_, c)| *c != cells) {
+                    ans.push((Orientation { rotation, mirrored }, cells.clone()));
+                }
+                let (row, column) = (cells.len(), cells[0].len());
+                cells = (0..column)
+                    .map(|j| (0..row).map(|i| cells[row - 1 - i][j]).collect())
+                    .collect();
+            }
+        }
+        ans
+    }
+}
+
+/// 在局面上找到的一个定式
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct PatternMatch {
+    pub name: String,
+    /// 转到该方向后的模板的左上角在局面上的位置，可以在局面外
+    pub position: (isize, isize),
+    pub orientation: Orientation,
+    /// 定式中的数字
+    pub numbers: Vec<(usize, usize)>,
+    /// 定式推出的雷
+    pub mines: Vec<(usize, usize)>,
+    /// 定式推出的非雷
+    pub safes: Vec<(usize, usize)>,
+}
+
+/// 定式识别器，默认带有常见定式的目录，可以追加自定义的定式。
+/// - 用python调用时的示例：
+/// ```python
+/// import ms_toollib as ms
+/// for p in ms.find_patterns(game_board, [("my 2-2", "...?/m22#")]):
+///     print(p["name"], p["position"], p["mines"], p["safes"])
+/// ```
+#[derive(Debug, Clone)]
+pub struct PatternRecognizer {
+    patterns: Vec<(Pattern, Vec<OrientedCells>)>,
+}
+
+impl Default for PatternRecognizer {
+    fn default() -> Self {
+        PatternRecognizer::new()
+    }
+}
+
+impl PatternRecognizer {
+    /// 带有常见定式目录的识别器：1-1、1-2、1-2-1、1-2-2-1、贴墙的变体，以及洞
+    pub fn new() -> PatternRecognizer {
+        let mut recognizer = PatternRecognizer::empty();
+        for (name, template) in [
+            ("1-1", "#..s/#11?"),
+            ("1-1 wall", "X..s/X11?"),
+            ("1-2", "s..m/?12?"),
+            ("1-2 wall", "X..m/X12?"),
+            ("1-2-1", "smsms/?121?"),
+            ("1-2-2-1", "ssmmss/?1221?"),
+            ("hole", "#1#/#m#/###"),
+        ] {
+            recognizer.add_pattern(Pattern::new(name, template).unwrap());
+        }
+        recognizer
+    }
+    /// 没有任何定式的识别器
+    pub fn empty() -> PatternRecognizer {
+        PatternRecognizer { patterns: vec![] }
+    }
+    pub fn add_pattern(&mut self, pattern: Pattern) {
+        let orientations = pattern.orientations();
+        self.patterns.push((pattern, orientations));
+    }
+    pub fn patterns(&self) -> Vec<&Pattern> {
+        self.patterns.iter().map(|p| &p.0).collect()
+    }
+    /// 找出局面上所有定式。只在refresh_matrixs分出的段上找，即定式至少含有一个边缘格子。
+    /// 局面中可以有标雷（11）和非雷（12），但必须全部标对。
+    pub fn find(&self, board_of_game: &Vec<Vec<i32>>) -> Vec<PatternMatch> {
+        let (_, matrix_xs, _, _, _) = refresh_matrixs(board_of_game);
+        let mut ans = vec![];
+        for (pattern, orientations) in &self.patterns {
+            for (orientation, cells) in orientations {
+                let mut positions = vec![];
+                for &(x, y) in matrix_xs.iter().flatten() {
+                    for (i, r) in cells.iter().enumerate() {
+                        for (j, c) in r.iter().enumerate() {
+                            if c.is_covered() {
+                                positions.push((x as isize - i as isize, y as isize - j as isize));
+                            }
+                        }
+                    }
+                }
+                positions.sort_unstable();
+                positions.dedup();
+                for position in positions {
+                    if let Some(mut m) = match_at(board_of_game, cells, position) {
+                        m.name = pattern.name.clone();
+                        m.orientation = *orientation;
+                        ans.push(m);
+                    }
+                }
+            }
+        }
+        ans
+    }
+}
+
+/// 用默认的定式目录找出局面上所有定式
+pub fn find_patterns(board_of_game: &Vec<Vec<i32>>) -> Vec<PatternMatch> {
+    PatternRecognizer::new().find(board_of_game)
+}
+
+fn match_at(
+    board_of_game: &[Vec<i32>],
+    cells: &[Vec<PatternCell>],
+    position: (isize, isize),
+) -> Option<PatternMatch> {
+    let row = board_of_game.len();
+    let column = board_of_game[0].len();
+    let to_board = |i: usize, j: usize| -> Option<(usize, usize)> {
+        let x = position.0 + i as isize;
+        let y = position.1 + j as isize;
+        if x < 0 || y < 0 || x >= row as isize || y >= column as isize {
+            None
+        } else {
+            Some((x as usize, y as usize))
+        }
+    };
+    let mut ans = PatternMatch {
+        name: String::new(),
+        position,
+        orientation: Orientation {
+            rotation: 0,
+            mirrored: false,
+        },
+        numbers: vec![],
+        mines: vec![],
+        safes: vec![],
+    };
+    for i in 0..cells.len() {
+        for j in 0..cells[0].len() {
+            let pos = to_board(i, j);
+            match (cells[i][j], pos) {
+                (PatternCell::Any, _) => {}
+                (PatternCell::Wall, None) => {}
+                (_, None) => return None,
+                (PatternCell::Wall, Some(_)) => return None,
+                (PatternCell::Known, Some((x, y))) => {
+                    if board_of_game[x][y] == 10 {
+                        return None;
+                    }
+                }
+                (PatternCell::Number(n), Some((x, y))) => {
+                    let v = board_of_game[x][y];
+                    if !(0..=8).contains(&v) {
+                        return None;
+                    }
+                    let mut flags = 0;
+                    for (m, k) in neighbours(row, column, x, y) {
+                        match board_of_game[m][k] {
+                            11 => flags += 1,
+                            10 => {
+                                // 数字周围的未打开格子必须都在模板里
+                                let (ti, tj) = (m as isize - position.0, k as isize - position.1);
+                                if ti < 0
+                                    || tj < 0
+                                    || ti >= cells.len() as isize
+                                    || tj >= cells[0].len() as isize
+                                    || !cells[ti as usize][tj as usize].is_covered()
+                                {
+                                    return None;
+                                }
+                            }
+                            _ => {}
+                        }
+                    }
+                    if v - flags != n as i32 {
+                        return None;
+                    }
+                    ans.numbers.push((x, y));
+                }
+                (c, Some((x, y))) => {
+                    if board_of_game[x][y] != 10 {
+                        return None;
+                    }
+                    if c == PatternCell::Mine {
+                        ans.mines.push((x, y));
+                    } else if c == PatternCell::Safe {
+                        ans.safes.push((x, y));
+                    }
+                }
+            }
+        }
+    }
+    Some(ans)
+}
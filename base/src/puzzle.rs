@@ -0,0 +1,130 @@
+// 判雷练习题生成：从随机局面的起手开始推理，停在恰好只有一个或几个格子能判出、且必须用指定方法判出的局面
+
+use crate::algorithms::solve_enumerate;
+use crate::explain::{explain_round, Deduction, Proof};
+use crate::utils::{laymine_op_rng, random_seed, refresh_board, refresh_matrixs, SeededRng};
+
+/// 练习题要求的推理方法，与[Proof](crate::Proof)的三种证明对应
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PuzzleTier {
+    /// 单集合判雷
+    Direct,
+    /// 双集合判雷（减法公式）
+    Minus,
+    /// 枚举
+    Enumerate,
+}
+
+impl PuzzleTier {
+    fn of(proof: &Proof) -> PuzzleTier {
+        match proof {
+            Proof::Direct(_) => PuzzleTier::Direct,
+            Proof::Minus { .. } => PuzzleTier::Minus,
+            Proof::Enumerate(_) => PuzzleTier::Enumerate,
+        }
+    }
+}
+
+/// 一道判雷练习题
+#[derive(Debug, Clone, PartialEq)]
+pub struct Puzzle {
+    /// 真实局面
+    pub board: Vec<Vec<i32>>,
+    /// 题目，即部分打开的游戏局面。之前判出的雷已标上（11）
+    pub game_board: Vec<Vec<i32>>,
+    /// 答案，即题目中所有能判出的格子及其证明，都用要求的方法判出
+    pub solution: Vec<Deduction>,
+}
+
+/// 生成一道判雷练习题。
+/// - 输入：高、宽、雷数、要求的推理方法、能判出的格子数的上限、最多尝试的局面数。
+/// - 返回：练习题。用完尝试次数仍没有生成时返回None。
+/// - 原理：按win7规则在随机的起手位置埋雷并打开，然后一轮一轮地推理，每轮只用最简单的方法
+///   （见[solve_explain](crate::solve_explain)），标上判出的雷、打开判出的非雷。
+///   某一轮用的恰好是要求的方法、判出的格子不多于上限，且枚举也判不出其他格子时，停在这一轮之前的局面，作为题目。
+///   推理中断（需要猜雷）或扫完都没有停下时，换一个局面。
+/// - 注意：题目中不能用更简单的方法判出任何格子。不考虑剩余雷数。上限为1时最难生成，雷的密度越高越容易生成需要枚举的题目。
+/// - 用python调用时的示例：
+/// ```python
+/// import ms_toollib as ms
+/// puzzle = ms.generate_puzzle(8, 8, 12, "minus", 1, 1000)
+/// print(puzzle["game_board"], puzzle["solution"])
+/// ```
+pub fn generate_puzzle(
+    row: usize,
+    column: usize,
+    mine_num: usize,
+    tier: PuzzleTier,
+    max_cells: usize,
+    max_times: usize,
+) -> Option<Puzzle> {
+    generate_puzzle_seed(
+        row,
+        column,
+        mine_num,
+        tier,
+        max_cells,
+        max_times,
+        random_seed(),
+    )
+}
+
+/// 可设种子的判雷练习题生成。相同的种子与参数，在各平台生成完全相同的题目。
+/// - 输入：高、宽、雷数、要求的推理方法、能判出的格子数的上限、最多尝试的局面数、种子。
+/// - 返回：练习题。用完尝试次数仍没有生成时返回None。
+pub fn generate_puzzle_seed(
+    row: usize,
+    column: usize,
+    mine_num: usize,
+    tier: PuzzleTier,
+    max_cells: usize,
+    max_times: usize,
+    seed: u64,
+) -> Option<Puzzle> {
+    let mut rng = SeededRng::new(seed);
+    for _ in 0..max_times {
+        let x0 = rng.gen_range(row);
+        let y0 = rng.gen_range(column);
+        let board = laymine_op_rng(row, column, mine_num, x0, y0, &mut rng);
+        let mut game_board = vec![vec![10; column]; row];
+        refresh_board(&board, &mut game_board, vec![(x0, y0)]);
+        loop {
+            let found = explain_round(&game_board, 0);
+            if found.is_empty() {
+                break;
+            }
+            if PuzzleTier::of(&found[0].proof) == tier
+                && found.len() <= max_cells
+                && is_all_deduced(&game_board, &found)
+            {
+                return Some(Puzzle {
+                    board,
+                    game_board,
+                    solution: found,
+                });
+            }
+            let mut not_mine = vec![];
+            for d in found {
+                if d.is_mine {
+                    game_board[d.cell.0][d.cell.1] = 11;
+                } else {
+                    not_mine.push(d.cell);
+                }
+            }
+            refresh_board(&board, &mut game_board, not_mine);
+        }
+    }
+    None
+}
+
+/// found是否就是游戏局面中所有能判出的格子。简单的方法判出的格子，枚举都能判出，因此只需与枚举的结果比较
+fn is_all_deduced(game_board: &Vec<Vec<i32>>, found: &[Deduction]) -> bool {
+    let (matrix_as, matrix_xs, matrix_bs, _, _) = refresh_matrixs(game_board);
+    let (not_mine, is_mine) = solve_enumerate(&matrix_as, &matrix_xs, &matrix_bs);
+    let mut all: Vec<(usize, usize)> = not_mine.into_iter().chain(is_mine).collect();
+    all.sort_unstable();
+    all.dedup();
+    let mut cells: Vec<(usize, usize)> = found.iter().map(|d| d.cell).collect();
+    cells.sort_unstable();
+    all == cells
+}
@@ -0,0 +1,208 @@
+// 移雷：在与已打开的格子一致的前提下重新摆放未打开的格子里的雷，使某格是雷或不是雷。无猜、可猜等规则在游戏中用它救活或惩罚玩家
+
+use crate::grid::neighbours;
+use crate::utils::{cal_board_numbers, SeededRng};
+
+/// 移雷时最多搜索的节点数，超过后放弃
+const REARRANGE_MAX_NODES: usize = 1_000_000;
+
+/// 重新摆放未打开的格子里的雷，使cell是雷（is_mine）或不是雷，且与游戏局面中所有已打开的数字一致、雷的总数不变。
+/// - 输入：局面、游戏局面、格子、是否要是雷、种子。cell必须是未打开的格子。
+/// - 返回：新的局面。不可能满足时（或搜索超过上限时）返回None。
+/// - 原理：与已打开的数字相邻的未打开格子（边缘）逐格回溯搜索，每格先试原来的状态，以尽量少移动雷；
+///   其余的未打开格子（内部）先保留原来的雷，多退少补时随机选。
+/// - 注意：标雷、问号等都当作未打开的格子，标雷的位置在新的局面中可能不再是雷。
+pub fn rearrange_mines(
+    board: &[Vec<i32>],
+    game_board: &[Vec<i32>],
+    cell: (usize, usize),
+    is_mine: bool,
+    seed: u64,
+) -> Option<Vec<Vec<i32>>> {
+    let row = board.len();
+    let column = board[0].len();
+    if game_board[cell.0][cell.1] < 10 {
+        return None;
+    }
+    let mine_num = board.iter().flatten().filter(|&&x| x == -1).count();
+    let is_number = |i: usize, j: usize| (0..=8).contains(&game_board[i][j]);
+    // 边缘格子的编号
+    let mut id = vec![vec![usize::MAX; column]; row];
+    let mut frontier = vec![];
+    let mut inner = vec![];
+    for (i, r) in game_board.iter().enumerate() {
+        for (j, &v) in r.iter().enumerate() {
+            if v < 10 {
+                continue;
+            }
+            if neighbours(row, column, i, j).any(|(m, n)| is_number(m, n)) {
+                id[i][j] = frontier.len();
+                frontier.push((i, j));
+            } else {
+                inner.push((i, j));
+            }
+        }
+    }
+    // 每个数字给出的约束：周围的边缘格子中恰有need个雷
+    let mut constraint_cells: Vec<Vec<usize>> = vec![];
+    let mut need: Vec<i32> = vec![];
+    let mut cell_constraints = vec![vec![]; frontier.len()];
+    for (i, r) in game_board.iter().enumerate() {
+        for (j, &v) in r.iter().enumerate() {
+            if !(0..=8).contains(&v) {
+                continue;
+            }
+            let cells: Vec<usize> = neighbours(row, column, i, j)
+                .filter(|&(m, n)| id[m][n] != usize::MAX)
+                .map(|(m, n)| id[m][n])
+                .collect();
+            if cells.is_empty() {
+                continue;
+            }
+            for &k in &cells {
+                cell_constraints[k].push(need.len());
+            }
+            constraint_cells.push(cells);
+            need.push(v);
+        }
+    }
+    // 搜索顺序：沿着约束广度优先，使相关的格子挨在一起，尽早剪枝
+    let mut order = vec![];
+    let mut visited = vec![false; frontier.len()];
+    for s in 0..frontier.len() {
+        if visited[s] {
+            continue;
+        }
+        visited[s] = true;
+        order.push(s);
+        let mut head = order.len() - 1;
+        while head < order.len() {
+            let k = order[head];
+            head += 1;
+            for &c in &cell_constraints[k] {
+                for &t in &constraint_cells[c] {
+                    if !visited[t] {
+                        visited[t] = true;
+                        order.push(t);
+                    }
+                }
+            }
+        }
+    }
+    let target = id[cell.0][cell.1];
+    let mut search = Search {
+        order,
+        cell_constraints,
+        need,
+        left: constraint_cells.iter().map(|c| c.len() as i32).collect(),
+        prefer: frontier.iter().map(|&(i, j)| board[i][j] == -1).collect(),
+        fixed: (target, is_mine),
+        assign: vec![false; frontier.len()],
+        inner_num: inner.len(),
+        inner_fixed: if target == usize::MAX {
+            Some(is_mine)
+        } else {
+            None
+        },
+        mine_num,
+        nodes: 0,
+    };
+    if !search.dfs(0, 0) {
+        return None;
+    }
+    let mut new_board = vec![vec![0; column]; row];
+    let mut frontier_mines = 0;
+    for (k, &(i, j)) in frontier.iter().enumerate() {
+        if search.assign[k] {
+            new_board[i][j] = -1;
+            frontier_mines += 1;
+        }
+    }
+    // 内部的格子：先保留原来的雷，多退少补
+    let mut rng = SeededRng::new(seed);
+    let mut inner_need = mine_num - frontier_mines;
+    if target == usize::MAX && is_mine {
+        new_board[cell.0][cell.1] = -1;
+        inner_need -= 1;
+    }
+    let (mut old_mines, mut others): (Vec<_>, Vec<_>) = inner
+        .into_iter()
+        .filter(|&x| x != cell)
+        .partition(|&(i, j)| board[i][j] == -1);
+    rng.shuffle(&mut old_mines);
+    rng.shuffle(&mut others);
+    for (i, j) in old_mines.into_iter().chain(others).take(inner_need) {
+        new_board[i][j] = -1;
+    }
+    cal_board_numbers(&mut new_board);
+    Some(new_board)
+}
+
+/// 边缘格子的回溯搜索
+struct Search {
+    /// 搜索的顺序
+    order: Vec<usize>,
+    cell_constraints: Vec<Vec<usize>>,
+    /// 各约束还需要的雷数
+    need: Vec<i32>,
+    /// 各约束还没有确定的格子数
+    left: Vec<i32>,
+    /// 各格子原来是不是雷，先试原来的状态
+    prefer: Vec<bool>,
+    /// 固定状态的边缘格子，不在边缘上时编号为usize::MAX
+    fixed: (usize, bool),
+    assign: Vec<bool>,
+    inner_num: usize,
+    /// 固定状态的格子在内部时，它的状态
+    inner_fixed: Option<bool>,
+    mine_num: usize,
+    nodes: usize,
+}
+
+impl Search {
+    fn dfs(&mut self, k: usize, mines: usize) -> bool {
+        self.nodes += 1;
+        if self.nodes > REARRANGE_MAX_NODES || mines > self.mine_num {
+            return false;
+        }
+        if k == self.order.len() {
+            // 内部的格子要能放下剩余的雷
+            let inner_need = self.mine_num - mines;
+            return match self.inner_fixed {
+                Some(true) => inner_need >= 1 && inner_need <= self.inner_num,
+                Some(false) => inner_need < self.inner_num,
+                None => inner_need <= self.inner_num,
+            };
+        }
+        let x = self.order[k];
+        let choices = if x == self.fixed.0 {
+            vec![self.fixed.1]
+        } else {
+            vec![self.prefer[x], !self.prefer[x]]
+        };
+        for mine in choices {
+            let mut ok = true;
+            for &c in &self.cell_constraints[x] {
+                self.left[c] -= 1;
+                if mine {
+                    self.need[c] -= 1;
+                }
+                if self.need[c] < 0 || self.need[c] > self.left[c] {
+                    ok = false;
+                }
+            }
+            let found = ok && self.dfs(k + 1, mines + mine as usize);
+            for &c in &self.cell_constraints[x] {
+                self.left[c] += 1;
+                if mine {
+                    self.need[c] += 1;
+                }
+            }
+            if found {
+                self.assign[x] = mine;
+                return true;
+            }
+        }
+        false
+    }
+}
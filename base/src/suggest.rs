@@ -0,0 +1,167 @@
+// 提示引擎：推荐下一步点哪里。能判出非雷时推荐非雷，否则对每个可以猜的格子打分
+
+use crate::algorithms::{
+    cal_number_counts, cal_possibility, get_all_not_and_is_mine_on_board, mark_board,
+    SegmentTableCache,
+};
+use crate::count::{LogNumber, MineCount};
+use crate::grid::neighbours;
+use crate::utils::refresh_matrixs;
+use std::collections::HashMap;
+
+/// 猜雷得分中进步的权重：期望多判出一格，相当于是雷的概率低2%
+const PROGRESS_WEIGHT: f64 = 0.02;
+
+/// 推荐的理由
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum MoveReason {
+    /// 确定不是雷
+    Safe,
+    /// 猜雷。后两项都以该格不是雷为前提。
+    Guess {
+        mine_probability: f64,
+        /// 打开后是0（开空）的概率
+        zero_probability: f64,
+        /// 打开后期望新判出的格子数。开空时只计入周围的格子，不计入连锁打开的格子。
+        expected_solvable: f64,
+        /// 综合得分，越大越好：(1 - 是雷的概率) * (1 + 0.02 * (开空的概率 + 期望新判出的格子数))
+        score: f64,
+    },
+}
+
+/// 一条推荐：点开哪个格子，以及理由
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct SuggestedMove {
+    pub cell: (usize, usize),
+    pub reason: MoveReason,
+}
+
+/// 提示引擎，推荐下一步点开的格子。
+/// - 输入：游戏局面、雷数（含义与[cal_possibility](crate::cal_possibility)相同）。局面中可以有标雷（11）和非雷（12），但必须全部标对。
+/// - 返回：排好序的推荐。能判出非雷时，返回所有非雷（按位置排列）；否则返回所有可以猜的格子，
+///   按得分从高到低排列。局面与雷数矛盾时返回Err(1)。
+/// - 注意：猜雷时要对每个格子的每种可能的数字重新计数、判雷，较慢。内部未知区域中周围都是内部格子的格子，
+///   按周围未知格的数量只算一次。
+/// - 用python调用时的示例：
+/// ```python
+/// import ms_toollib as ms
+/// moves = ms.suggest_move(game_board, 99)
+/// print(moves[0]["cell"], moves[0]["method"], moves[0].get("mine_probability"))
+/// ```
+pub fn suggest_move(
+    board_of_game: &[Vec<i32>],
+    mine_num: f64,
+) -> Result<Vec<SuggestedMove>, usize> {
+    let mut board = board_of_game.to_vec();
+    mark_board(&mut board);
+    let (p, p_unknow, mine_num_range, _) = cal_possibility(&board, mine_num)?;
+    // 雷的比例换算成雷数，使各种假设下的计数可以比较
+    let mine_num = mine_num_range[1];
+    let row = board.len();
+    let column = board[0].len();
+    let mut poss = vec![vec![p_unknow; column]; row];
+    for ((x, y), q) in &p {
+        poss[*x][*y] = *q;
+    }
+
+    let mut ans = vec![];
+    for i in 0..row {
+        for j in 0..column {
+            if board[i][j] == 12 || board[i][j] == 10 && poss[i][j] == 0.0 {
+                ans.push(SuggestedMove {
+                    cell: (i, j),
+                    reason: MoveReason::Safe,
+                });
+            }
+        }
+    }
+    if !ans.is_empty() {
+        return Ok(ans);
+    }
+
+    let frontier: Vec<(usize, usize)> = p.iter().map(|x| x.0).collect();
+    let is_interior = |x: usize, y: usize| board[x][y] == 10 && !frontier.contains(&(x, y));
+    // 周围都是内部格子的格子，按周围格子数缓存
+    let mut interior_cache: HashMap<usize, (f64, f64)> = HashMap::new();
+    let mut cache = SegmentTableCache::default();
+    for i in 0..row {
+        for j in 0..column {
+            if board[i][j] != 10 || poss[i][j] >= 1.0 {
+                continue;
+            }
+            let key = if is_interior(i, j)
+                && neighbours(row, column, i, j).all(|(m, n)| is_interior(m, n))
+            {
+                Some(neighbours(row, column, i, j).count())
+            } else {
+                None
+            };
+            let (zero_probability, expected_solvable) =
+                match key.and_then(|k| interior_cache.get(&k)) {
+                    Some(t) => *t,
+                    None => {
+                        let t = cal_reveal_outcomes(&board, mine_num, (i, j), &mut cache);
+                        if let Some(k) = key {
+                            interior_cache.insert(k, t);
+                        }
+                        t
+                    }
+                };
+            let mine_probability = poss[i][j];
+            ans.push(SuggestedMove {
+                cell: (i, j),
+                reason: MoveReason::Guess {
+                    mine_probability,
+                    zero_probability,
+                    expected_solvable,
+                    score: (1.0 - mine_probability)
+                        * (1.0 + PROGRESS_WEIGHT * (zero_probability + expected_solvable)),
+                },
+            });
+        }
+    }
+    let score = |m: &SuggestedMove| match m.reason {
+        MoveReason::Guess { score, .. } => score,
+        MoveReason::Safe => f64::INFINITY,
+    };
+    ans.sort_by(|a, b| score(b).total_cmp(&score(a)));
+    Ok(ans)
+}
+
+/// 以cell不是雷为前提，打开后是0的概率，以及期望新判出的格子数
+fn cal_reveal_outcomes(
+    board: &Vec<Vec<i32>>,
+    mine_num: usize,
+    cell: (usize, usize),
+    cache: &mut SegmentTableCache,
+) -> (f64, f64) {
+    let outcomes = cal_number_counts(board, mine_num, cell, cache);
+    let mut total = LogNumber::zero();
+    for (_, c, _) in &outcomes {
+        total.accumulate(c);
+    }
+    if total.is_zero() {
+        return (0.0, 0.0);
+    }
+    let covered = neighbours(board.len(), board[0].len(), cell.0, cell.1)
+        .filter(|&(m, n)| board[m][n] == 10)
+        .count();
+    let mut zero_probability = 0.0;
+    let mut expected_solvable = 0.0;
+    for (number, c, mut b) in outcomes {
+        let q = c.ratio(&total);
+        if number == 0 {
+            zero_probability = q;
+        }
+        let (mut a_s, mut x_s, mut b_s, _, _) = refresh_matrixs(&b);
+        let (mut not_mine, mut is_mine) =
+            get_all_not_and_is_mine_on_board(&mut a_s, &mut x_s, &mut b_s, &mut b);
+        not_mine.append(&mut is_mine);
+        not_mine.sort_unstable();
+        not_mine.dedup();
+        // 开空时周围的格子都能确定
+        let solvable = if number == 0 { covered } else { 0 } + not_mine.len();
+        expected_solvable += q * solvable as f64;
+    }
+    (zero_probability, expected_solvable)
+}
@@ -3,12 +3,18 @@ use itertools::Itertools;
 use rand::seq::SliceRandom;
 #[cfg(any(feature = "py", feature = "rs"))]
 use rand::thread_rng;
+#[cfg(any(feature = "py", feature = "rs"))]
+use rand::Rng;
 use std::cmp::{max, min};
+use std::collections::HashMap;
 // use std::convert::TryInto;
 #[cfg(feature = "js")]
 use getrandom::getrandom;
 
 use crate::board;
+use crate::count::MineCount;
+use num_bigint::BigUint;
+use crate::grid::{neighbours, Cell};
 
 use crate::ENUM_LIMIT;
 
@@ -105,38 +111,30 @@ pub fn refresh_matrix(
     for i in 0..row {
         for j in 0..column {
             if BoardofGame[i][j] > 0 && BoardofGame[i][j] < 10 {
-                let mut flag: bool = false;
-                for m in max(1, i) - 1..min(row, i + 2) {
-                    for n in max(1, j) - 1..min(column, j + 2) {
-                        if BoardofGame[m][n] == 10 {
-                            flag = true;
-                        }
-                    }
-                }
+                let flag =
+                    neighbours(row, column, i, j).any(|(m, n)| BoardofGame[m][n] == Cell::Covered);
                 if flag {
                     MatrixA.push(vec![0; MatrixAColumnNum]);
                     Matrixb.push(BoardofGame[i][j]);
                     MatrixARowNum += 1;
-                    for m in max(1, i) - 1..min(row, i + 2) {
-                        for n in max(1, j) - 1..min(column, j + 2) {
-                            if BoardofGame[m][n] == 11 {
-                                Matrixb[MatrixARowNum - 1] -= 1
-                            } else if BoardofGame[m][n] == 10 {
-                                let mut flag_exit: bool = false;
-                                for idMatrixx in 0..MatrixAColumnNum {
-                                    if Matrixx[idMatrixx].0 == m && Matrixx[idMatrixx].1 == n {
-                                        flag_exit = true;
-                                        MatrixA[MatrixARowNum - 1][idMatrixx] = 1;
-                                    }
+                    for (m, n) in neighbours(row, column, i, j) {
+                        if BoardofGame[m][n] == Cell::Flag {
+                            Matrixb[MatrixARowNum - 1] -= 1
+                        } else if BoardofGame[m][n] == Cell::Covered {
+                            let mut flag_exit: bool = false;
+                            for idMatrixx in 0..MatrixAColumnNum {
+                                if Matrixx[idMatrixx].0 == m && Matrixx[idMatrixx].1 == n {
+                                    flag_exit = true;
+                                    MatrixA[MatrixARowNum - 1][idMatrixx] = 1;
                                 }
-                                if !flag_exit {
-                                    for ii in 0..MatrixARowNum {
-                                        MatrixA[ii].push(0)
-                                    }
-                                    Matrixx.push((m, n));
-                                    MatrixAColumnNum += 1;
-                                    MatrixA[MatrixARowNum - 1][MatrixAColumnNum - 1] = 1;
+                            }
+                            if !flag_exit {
+                                for ii in 0..MatrixARowNum {
+                                    MatrixA[ii].push(0)
                                 }
+                                Matrixx.push((m, n));
+                                MatrixAColumnNum += 1;
+                                MatrixA[MatrixARowNum - 1][MatrixAColumnNum - 1] = 1;
                             }
                         }
                     }
@@ -435,6 +433,86 @@ impl js_shuffle for Vec<i32> {
     }
 }
 
+/// 可设种子的伪随机数发生器（xoshiro256**），用于可复现的埋雷。
+/// - 只用到u64的整数运算，因此在Rust、Python、Javascript（WASM）、C中，同一个种子产生完全相同的序列。
+/// - 不是密码学安全的随机数发生器。
+#[derive(Clone, Debug)]
+pub struct SeededRng {
+    state: [u64; 4],
+}
+
+// splitmix64，用于把种子展开成发生器的状态
+fn splitmix64(x: &mut u64) -> u64 {
+    *x = x.wrapping_add(0x9E3779B97F4A7C15);
+    let mut z = *x;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+    z ^ (z >> 31)
+}
+
+impl SeededRng {
+    pub fn new(seed: u64) -> SeededRng {
+        let mut x = seed;
+        SeededRng {
+            state: [
+                splitmix64(&mut x),
+                splitmix64(&mut x),
+                splitmix64(&mut x),
+                splitmix64(&mut x),
+            ],
+        }
+    }
+    /// 由种子和流编号构造。同一个种子的不同流互不相关，用于多次尝试、多线程。
+    pub fn new_stream(seed: u64, stream: u64) -> SeededRng {
+        let mut x = stream;
+        SeededRng::new(seed ^ splitmix64(&mut x))
+    }
+    pub fn next_u64(&mut self) -> u64 {
+        let result = self.state[1].wrapping_mul(5).rotate_left(7).wrapping_mul(9);
+        let t = self.state[1] << 17;
+        self.state[2] ^= self.state[0];
+        self.state[3] ^= self.state[1];
+        self.state[1] ^= self.state[2];
+        self.state[0] ^= self.state[3];
+        self.state[2] ^= t;
+        self.state[3] = self.state[3].rotate_left(45);
+        result
+    }
+    /// 获取0~limit-1范围内均匀分布的随机整数
+    pub fn gen_range(&mut self, limit: usize) -> usize {
+        if limit <= 1 {
+            return 0;
+        }
+        let limit = limit as u64;
+        let zone = u64::MAX - u64::MAX % limit;
+        loop {
+            let t = self.next_u64();
+            if t < zone {
+                return (t % limit) as usize;
+            }
+        }
+    }
+    /// Fisher-Yates洗牌
+    pub fn shuffle<T>(&mut self, v: &mut [T]) {
+        for i in (1..v.len()).rev() {
+            let id = self.gen_range(i + 1);
+            v.swap(i, id);
+        }
+    }
+}
+
+// 从系统中获取一个随机的种子，用于不设种子的调用
+pub(crate) fn random_seed() -> u64 {
+    #[cfg(any(feature = "py", feature = "rs"))]
+    return thread_rng().gen::<u64>();
+    #[cfg(feature = "js")]
+    {
+        let mut a = [0u8; 8];
+        getrandom(&mut a).unwrap();
+        return u64::from_be_bytes(a);
+    }
+}
+
 /// 通用标准埋雷引擎。
 /// - 标准埋雷规则：起手位置非雷，其余位置的雷服从均匀分布。
 /// - 输出：二维的局面，其中0代表空，1~8代表1~8，-1代表雷。
@@ -454,6 +532,47 @@ pub fn laymine(row: usize, column: usize, MineNum: usize, X0: usize, Y0: usize)
     #[cfg(feature = "js")]
     Board1Dim.shuffle_();
 
+    laymine_from_1dim(&Board1Dim, row, column, X0, Y0)
+}
+
+/// 可设种子的通用标准埋雷引擎。相同的种子与参数，在Rust、Python、Javascript、C中埋出完全相同的局面。
+/// - 标准埋雷规则：起手位置非雷，其余位置的雷服从均匀分布。
+/// - 输出：二维的局面，其中0代表空，1~8代表1~8，-1代表雷。
+pub fn laymine_seed(
+    row: usize,
+    column: usize,
+    mine_num: usize,
+    x0: usize,
+    y0: usize,
+    seed: u64,
+) -> Vec<Vec<i32>> {
+    laymine_rng(row, column, mine_num, x0, y0, &mut SeededRng::new(seed))
+}
+
+pub(crate) fn laymine_rng(
+    row: usize,
+    column: usize,
+    mine_num: usize,
+    x0: usize,
+    y0: usize,
+    rng: &mut SeededRng,
+) -> Vec<Vec<i32>> {
+    let area: usize = row * column - 1;
+    let mut board_1dim = vec![0; area - mine_num];
+    board_1dim.append(&mut vec![-1; mine_num]);
+    rng.shuffle(&mut board_1dim);
+    laymine_from_1dim(&board_1dim, row, column, x0, y0)
+}
+
+// 把洗好的一维雷序列（不含起手位置）按列优先铺到局面上，并计算数字
+fn laymine_from_1dim(
+    Board1Dim: &Vec<i32>,
+    row: usize,
+    column: usize,
+    X0: usize,
+    Y0: usize,
+) -> Vec<Vec<i32>> {
+    let area: usize = row * column - 1;
     let mut Board1Dim_2: Vec<i32> = vec![];
     Board1Dim_2.reserve(area + 1);
     let pointer = X0 + Y0 * row;
@@ -492,19 +611,7 @@ pub fn laymine_op(
     X0: usize,
     Y0: usize,
 ) -> Vec<Vec<i32>> {
-    let mut areaOp = 9;
-    if X0 == 0 || Y0 == 0 || X0 == row - 1 || Y0 == column - 1 {
-        if X0 == 0 && Y0 == 0
-            || X0 == 0 && Y0 == column - 1
-            || X0 == row - 1 && Y0 == 0
-            || X0 == row - 1 && Y0 == column - 1
-        {
-            areaOp = 4;
-        } else {
-            areaOp = 6;
-        }
-    }
-    let area = row * column - areaOp;
+    let area = row * column - op_area(row, column, X0, Y0);
     let mut Board1Dim = vec![0; area - MineNum];
     Board1Dim.append(&mut vec![-1; MineNum]);
 
@@ -517,6 +624,65 @@ pub fn laymine_op(
     #[cfg(feature = "js")]
     Board1Dim.shuffle_();
 
+    laymine_op_from_1dim(&Board1Dim, row, column, X0, Y0)
+}
+
+/// 可设种子的通用win7规则埋雷引擎。相同的种子与参数，在各平台埋出完全相同的局面。
+/// - win7规则：起手位置开空，其余位置的雷服从均匀分布。
+/// - 输出：二维的局面，其中0代表空，1~8代表1~8，-1代表雷。
+pub fn laymine_op_seed(
+    row: usize,
+    column: usize,
+    mine_num: usize,
+    x0: usize,
+    y0: usize,
+    seed: u64,
+) -> Vec<Vec<i32>> {
+    laymine_op_rng(row, column, mine_num, x0, y0, &mut SeededRng::new(seed))
+}
+
+pub(crate) fn laymine_op_rng(
+    row: usize,
+    column: usize,
+    mine_num: usize,
+    x0: usize,
+    y0: usize,
+    rng: &mut SeededRng,
+) -> Vec<Vec<i32>> {
+    let area = row * column - op_area(row, column, x0, y0);
+    let mut board_1dim = vec![0; area - mine_num];
+    board_1dim.append(&mut vec![-1; mine_num]);
+    rng.shuffle(&mut board_1dim);
+    laymine_op_from_1dim(&board_1dim, row, column, x0, y0)
+}
+
+// 起手位置周围（含自身）的格子数，即win7规则下必然非雷的格子数
+fn op_area(row: usize, column: usize, x0: usize, y0: usize) -> usize {
+    let mut area_op = 9;
+    if x0 == 0 || y0 == 0 || x0 == row - 1 || y0 == column - 1 {
+        if x0 == 0 && y0 == 0
+            || x0 == 0 && y0 == column - 1
+            || x0 == row - 1 && y0 == 0
+            || x0 == row - 1 && y0 == column - 1
+        {
+            area_op = 4;
+        } else {
+            area_op = 6;
+        }
+    }
+    area_op
+}
+
+// 把洗好的一维雷序列（不含起手位置周围）按列优先铺到局面上，并计算数字
+fn laymine_op_from_1dim(
+    Board1Dim: &Vec<i32>,
+    row: usize,
+    column: usize,
+    X0: usize,
+    Y0: usize,
+) -> Vec<Vec<i32>> {
+    let areaOp = op_area(row, column, X0, Y0);
+    let area = row * column - areaOp;
     let mut Board = vec![vec![0; column]; row];
     let mut skip = 0;
     for i in 0..(area + areaOp) {
@@ -575,27 +741,38 @@ pub fn cal_bbbv(board: &Vec<Vec<i32>>) -> usize {
 pub fn refresh_board(
     board: &Vec<Vec<i32>>,
     boardofGame: &mut Vec<Vec<i32>>,
-    mut clicked_poses: Vec<(usize, usize)>,
+    clicked_poses: Vec<(usize, usize)>,
 ) {
+    refresh_board_opened(board, boardofGame, clicked_poses);
+}
+
+/// 同[refresh_board]，并返回新打开的数字（含0）格子，用于增量地更新约束
+pub(crate) fn refresh_board_opened(
+    board: &Vec<Vec<i32>>,
+    board_of_game: &mut Vec<Vec<i32>>,
+    mut clicked_poses: Vec<(usize, usize)>,
+) -> Vec<(usize, usize)> {
     // println!("{:?}", ClickedPoses);
     let row = board.len();
     let column = board[0].len();
     let mut loss_flag = false;
+    let mut opened = vec![];
     while let Some(top) = clicked_poses.pop() {
         let (i, j) = top;
+        if board[i][j] >= 0 && !(0..=8).contains(&board_of_game[i][j]) {
+            opened.push((i, j));
+        }
         if board[i][j] > 0 {
-            boardofGame[i][j] = board[i][j];
+            board_of_game[i][j] = board[i][j];
         } else if board[i][j] == 0 {
-            boardofGame[i][j] = 0;
-            for m in max(1, i) - 1..min(row, i + 2) {
-                for n in max(1, j) - 1..min(column, j + 2) {
-                    if (i != m || j != n) && (boardofGame[m][n] == 10 || boardofGame[m][n] == 12) {
-                        clicked_poses.push((m, n));
-                    }
+            board_of_game[i][j] = 0;
+            for (m, n) in neighbours(row, column, i, j) {
+                if board_of_game[m][n] == Cell::Covered || board_of_game[m][n] == Cell::Safe {
+                    clicked_poses.push((m, n));
                 }
             }
         } else {
-            boardofGame[i][j] = 15; // 标红雷，此处是雷，且踩到了
+            board_of_game[i][j] = Cell::ExplodedMine.to_i32(); // 标红雷，此处是雷，且踩到了
             loss_flag = true;
         }
     }
@@ -603,105 +780,13 @@ pub fn refresh_board(
     if loss_flag {
         for i in 0..row {
             for j in 0..column {
-                if boardofGame[i][j] == 11 && board[i][j] != -1 {
-                    boardofGame[i][j] = 14; // 叉雷，即标错的雷
+                if board_of_game[i][j] == Cell::Flag && board[i][j] != Cell::Mine {
+                    board_of_game[i][j] = Cell::WrongFlag.to_i32(); // 叉雷，即标错的雷
                 }
             }
         }
     }
-}
-
-#[derive(Clone, Debug)]
-pub struct BigNumber {
-    // 科学计数法表示的大数字
-    // 必定大于等于1，a必定满足小于10大于等于1
-    pub a: f64,
-    pub b: i32,
-}
-
-impl BigNumber {
-    fn a_become_smaller_than(&mut self, thrd: f64) {
-        // 如果big_number大于thrd
-        // 把位数都放到指数上，使其满足a小于10大于等于1
-        if self.a < thrd {
-            return;
-        }
-        while self.a >= 10.0 {
-            self.a /= 10.0;
-            self.b += 1;
-        }
-    }
-    pub fn mul_usize(&mut self, k: usize) {
-        // 与usize相乘
-        if k == 0 {
-            self.a = 0.0;
-            self.b = 1;
-        } else {
-            self.a *= k as f64;
-            self.a_become_smaller_than(10.0);
-        }
-    }
-    pub fn mul_big_number(&mut self, k: &BigNumber) {
-        // 与big_number相乘, big_number必须至少为1
-        self.a *= k.a;
-        self.b += k.b;
-        self.a_become_smaller_than(10.0);
-    }
-    pub fn add_big_number(&mut self, k: &BigNumber) {
-        let mut ka = k.a;
-        let mut kb = k.b;
-        while self.b > kb {
-            ka /= 10.0;
-            kb += 1;
-        }
-        while self.b < kb {
-            self.a /= 10.0;
-            self.b += 1;
-        }
-        self.a += ka;
-        self.a_become_smaller_than(10.0);
-    }
-    pub fn div_big_num(&mut self, k: &BigNumber) -> f64 {
-        // 计算大数相除
-        let mut ans = self.a / k.a;
-        while self.b < k.b {
-            ans /= 10.0;
-            self.b += 1;
-        }
-        while self.b > k.b {
-            ans *= 10.0;
-            self.b -= 1;
-        }
-        ans
-    }
-    pub fn div_usize(&mut self, k: usize) {
-        // 计算大数除以正整数。这里被除数大于等于0；除数大于等于1
-        if self.a < 1e-8 && self.b == 1 {
-            return;
-        } else {
-            self.a /= k as f64;
-            while self.a < 1.0 {
-                self.a *= 10.0;
-                self.b -= 1;
-            }
-        }
-    }
-}
-
-pub fn C(n: usize, k: usize) -> BigNumber {
-    // n不超过1e10
-    if n < k + k {
-        return C(n, n - k);
-    };
-    let maximum_limit: f64 = 1e208;
-    let mut c = BigNumber { a: 1.0, b: 0 };
-    for i in 0..k {
-        c.a *= (n - i) as f64;
-        c.a /= (i + 1) as f64;
-        c.a_become_smaller_than(maximum_limit);
-    }
-    c.a_become_smaller_than(10.0);
-    c
+    opened
 }
 
 pub fn C_query<T, U>(n: T, k: U) -> usize
@@ -928,11 +1013,11 @@ fn cal_cell_and_equation_map(matrix_a: &Vec<Vec<i32>>) -> (Vec<Vec<usize>>, Vec<
     (cell_to_equation_map, equation_to_cell_map)
 }
 
-fn cal_table_minenum_recursion_step(
+fn cal_table_minenum_recursion_step<T: MineCount>(
     idx: usize,
-    current_amount: usize,
-    table_minenum: &mut [Vec<usize>; 2],
-    table_cell_minenum: &mut Vec<Vec<usize>>,
+    current_amount: T,
+    table_minenum: &mut Vec<T>,
+    table_cell_minenum: &mut Vec<Vec<T>>,
     // mut upper_limit: usize,
     // lower_limit: usize,
     matrixA_squeeze: &Vec<Vec<i32>>,
@@ -948,10 +1033,15 @@ fn cal_table_minenum_recursion_step(
     if idx == cells_num {
         //终止条件
         let total_mines_num: usize = mine_vec.iter().sum();
-        table_minenum[1][total_mines_num] += current_amount;
+        table_minenum[total_mines_num].accumulate(&current_amount);
         for (idn, n) in mine_vec.iter().enumerate() {
-            table_cell_minenum[total_mines_num][idn] +=
-                current_amount * n / combination_relationship[idn].len();
+            if *n > 0 {
+                table_cell_minenum[total_mines_num][idn].accumulate(
+                    &current_amount
+                        .mul_usize(*n)
+                        .div_usize(combination_relationship[idn].len()),
+                );
+            }
         }
         return true;
     }
@@ -992,7 +1082,7 @@ fn cal_table_minenum_recursion_step(
         }
         let is_end = cal_table_minenum_recursion_step(
             idx + 1,
-            current_amount * C_query(combination_relationship[idx].len(), u),
+            current_amount.mul_usize(C_query(combination_relationship[idx].len(), u)),
             table_minenum,
             table_cell_minenum,
             &matrixA_squeeze,
@@ -1011,13 +1101,37 @@ fn cal_table_minenum_recursion_step(
     false
 }
 
+/// 递归算法，得到雷数分布表和每格是雷情况数表，顺便计算最小、最大雷数。段的长度不能超过枚举极限。  
+/// - 返回：[雷数, 每种雷数的情况数]、每种雷数下每个复合格中某一格是雷的情况数。
+/// - 注意：情况数超过usize时取usize::MAX。需要精确的情况数时用[cal_table_minenum_recursion_generic]。
+#[allow(clippy::type_complexity)]
 pub fn cal_table_minenum_recursion(
-    matrixA_squeeze: &Vec<Vec<i32>>,
+    matrix_a_squeeze: &Vec<Vec<i32>>,
     matrixx_squeeze: &Vec<(usize, usize)>,
     matrix_b: &Vec<i32>,
     combination_relationship: &Vec<Vec<usize>>,
 ) -> Result<([Vec<usize>; 2], Vec<Vec<usize>>), usize> {
-    // 递归算法，得到雷数分布表和每格是雷情况数表，顺便计算最小、最大雷数
+    let to_usize = |x: &BigUint| usize::try_from(x).unwrap_or(usize::MAX);
+    let (mine_num, table, table_cell) = cal_table_minenum_recursion_generic::<BigUint>(
+        matrix_a_squeeze,
+        matrixx_squeeze,
+        matrix_b,
+        combination_relationship,
+    )?;
+    Ok((
+        [mine_num, table.iter().map(to_usize).collect()],
+        table_cell.iter().map(|r| r.iter().map(to_usize).collect()).collect(),
+    ))
+}
+
+/// 同[cal_table_minenum_recursion]，情况数的类型可以是精确的BigUint，也可以是LogNumber、f64等，见[MineCount]。  
+/// - 返回：雷数、每种雷数的情况数、每种雷数下每个复合格中某一格是雷的情况数。
+pub fn cal_table_minenum_recursion_generic<T: MineCount>(
+    matrixA_squeeze: &Vec<Vec<i32>>,
+    matrixx_squeeze: &Vec<(usize, usize)>,
+    matrix_b: &Vec<i32>,
+    combination_relationship: &Vec<Vec<usize>>,
+) -> Result<(Vec<usize>, Vec<T>, Vec<Vec<T>>), usize> {
     // 输入矩阵必须是非空的，且行列数必须匹配
     // 行数和列数至少为1
     let cells_num = matrixx_squeeze.len();
@@ -1031,18 +1145,16 @@ pub fn cal_table_minenum_recursion(
     // cells_num_total指合并前的格子数
 
     let mut flag_legal_board = true;
-    let mut table_minenum: [Vec<usize>; 2] = [
-        (0..cells_num_total + 1).collect::<Vec<usize>>(),
-        vec![0; cells_num_total + 1],
-    ];
+    let mut mine_nums: Vec<usize> = (0..cells_num_total + 1).collect();
+    let mut table_minenum: Vec<T> = vec![T::zero(); cells_num_total + 1];
     let (cell_to_equation_map, equation_to_cell_map) = cal_cell_and_equation_map(&matrixA_squeeze);
     // 计算两个映射表以减少复杂度
     // println!("cell_to_equation_map = {:?}; equation_to_cell_map = {:?}", cell_to_equation_map, equation_to_cell_map);
 
-    let mut table_cell_minenum: Vec<Vec<usize>> = vec![vec![0; cells_num]; cells_num_total + 1];
+    let mut table_cell_minenum: Vec<Vec<T>> = vec![vec![T::zero(); cells_num]; cells_num_total + 1];
     cal_table_minenum_recursion_step(
         0,
-        1,
+        T::one(),
         &mut table_minenum,
         &mut table_cell_minenum,
         &matrixA_squeeze,
@@ -1053,9 +1165,9 @@ pub fn cal_table_minenum_recursion(
         &equation_to_cell_map,
         &mut (vec![0; cells_num]),
     );
-    while table_minenum[1][0] == 0 {
-        table_minenum[0].remove(0);
-        table_minenum[1].remove(0);
+    while table_minenum[0].is_zero() {
+        mine_nums.remove(0);
+        table_minenum.remove(0);
         table_cell_minenum.remove(0);
         if table_cell_minenum.is_empty() {
             flag_legal_board = false;
@@ -1063,19 +1175,283 @@ pub fn cal_table_minenum_recursion(
         }
     }
     if flag_legal_board {
-        while table_minenum[1][table_cell_minenum.len() - 1] == 0 {
-            table_minenum[0].pop();
-            table_minenum[1].pop();
+        while table_minenum[table_cell_minenum.len() - 1].is_zero() {
+            mine_nums.pop();
+            table_minenum.pop();
             table_cell_minenum.pop();
         }
     }
     if flag_legal_board {
-        Ok((table_minenum, table_cell_minenum))
+        Ok((mine_nums, table_minenum, table_cell_minenum))
     } else {
         return Err(1);
     }
 }
 
+/// 以雷数为自变量的多项式，系数为情况数。offset为第0项对应的雷数。
+#[derive(Clone, Debug)]
+pub(crate) struct MineNumPoly<T> {
+    pub offset: usize,
+    pub coef: Vec<T>,
+}
+
+impl<T: MineCount> MineNumPoly<T> {
+    pub fn zero() -> MineNumPoly<T> {
+        MineNumPoly {
+            offset: 0,
+            coef: vec![],
+        }
+    }
+    pub fn one() -> MineNumPoly<T> {
+        MineNumPoly {
+            offset: 0,
+            coef: vec![T::one()],
+        }
+    }
+    /// 雷数为m的项的系数
+    pub fn get(&self, m: usize) -> T {
+        if m >= self.offset && m < self.offset + self.coef.len() {
+            self.coef[m - self.offset].clone()
+        } else {
+            T::zero()
+        }
+    }
+    /// self += other * factor * x^shift
+    pub fn add_shifted(&mut self, other: &MineNumPoly<T>, shift: usize, factor: &T) {
+        let start = other.offset + shift;
+        if self.coef.is_empty() {
+            self.offset = start;
+        }
+        if start < self.offset {
+            let mut coef = vec![T::zero(); self.offset - start];
+            coef.append(&mut self.coef);
+            self.coef = coef;
+            self.offset = start;
+        }
+        let end = start + other.coef.len();
+        if end > self.offset + self.coef.len() {
+            self.coef.resize(end - self.offset, T::zero());
+        }
+        for (i, c) in other.coef.iter().enumerate() {
+            self.coef[start - self.offset + i].accumulate(&c.times(factor));
+        }
+    }
+    pub fn mul(&self, other: &MineNumPoly<T>) -> MineNumPoly<T> {
+        if self.coef.is_empty() || other.coef.is_empty() {
+            return MineNumPoly::zero();
+        }
+        let mut coef = vec![T::zero(); self.coef.len() + other.coef.len() - 1];
+        for (i, a) in self.coef.iter().enumerate() {
+            if a.is_zero() {
+                continue;
+            }
+            for (j, b) in other.coef.iter().enumerate() {
+                coef[i + j].accumulate(&a.times(b));
+            }
+        }
+        MineNumPoly {
+            offset: self.offset + other.offset,
+            coef,
+        }
+    }
+}
+
+/// 动态规划的处理顺序：每次选择处理后"活跃方程"（已处理与未处理的格子都涉及的方程）增加最少的格子。
+/// 局面的边缘近似一条线，按此顺序处理时活跃方程很少，状态数也就很少。
+fn cal_dp_order(
+    cell_to_equation_map: &Vec<Vec<usize>>,
+    equation_to_cell_map: &Vec<Vec<usize>>,
+) -> Vec<usize> {
+    let cells_num = cell_to_equation_map.len();
+    let mut remain_cells_num: Vec<usize> = equation_to_cell_map.iter().map(|x| x.len()).collect();
+    let mut is_active = vec![false; equation_to_cell_map.len()];
+    let mut is_done = vec![false; cells_num];
+    let mut order = Vec::with_capacity(cells_num);
+    for _ in 0..cells_num {
+        let mut best = (i32::MAX, i32::MAX, 0);
+        for c in (0..cells_num).filter(|&c| !is_done[c]) {
+            let mut delta = 0;
+            let mut touched = 0;
+            for &e in &cell_to_equation_map[c] {
+                if is_active[e] {
+                    touched += 1;
+                    if remain_cells_num[e] == 1 {
+                        delta -= 1;
+                    }
+                } else if remain_cells_num[e] > 1 {
+                    delta += 1;
+                }
+            }
+            if (delta, -touched) < (best.0, best.1) {
+                best = (delta, -touched, c);
+            }
+        }
+        let c = best.2;
+        is_done[c] = true;
+        for &e in &cell_to_equation_map[c] {
+            remain_cells_num[e] -= 1;
+            is_active[e] = remain_cells_num[e] > 0;
+        }
+        order.push(c);
+    }
+    order
+}
+
+/// 动态规划算法，得到雷数分布表和每格是雷情况数表。含义与[cal_table_minenum_recursion]相同，
+/// 但没有枚举长度的限制，可以计算几百格长的段。  
+/// - 原理：按[cal_dp_order]的顺序逐个处理（复合）格子，状态为活跃方程还需要的雷数，
+/// 每个状态记录已处理的格子的雷数分布。前向、后向各做一遍，相乘即得每格的是雷情况数。
+/// - 返回：雷数、每种雷数的情况数、每种雷数下每个复合格中某一格是雷的情况数。
+pub fn cal_table_minenum_dp<T: MineCount>(
+    matrixA_squeeze: &Vec<Vec<i32>>,
+    matrix_b: &Vec<i32>,
+    combination_relationship: &Vec<Vec<usize>>,
+) -> Result<(Vec<usize>, Vec<T>, Vec<Vec<T>>), usize> {
+    let cells_num = combination_relationship.len();
+    let (cell_to_equation_map, equation_to_cell_map) = cal_cell_and_equation_map(matrixA_squeeze);
+    let order = cal_dp_order(&cell_to_equation_map, &equation_to_cell_map);
+    // 每一步处理后，每个方程剩余的未处理格子的容量（格子数之和）
+    let mut capacity: Vec<i32> = equation_to_cell_map
+        .iter()
+        .map(|x| {
+            x.iter()
+                .map(|&c| combination_relationship[c].len() as i32)
+                .sum()
+        })
+        .collect();
+    // 每一步之前的活跃方程，以及每一步的状态转移所需的信息
+    let mut actives: Vec<Vec<usize>> = vec![vec![]];
+    let mut remain_cells_num: Vec<usize> = equation_to_cell_map.iter().map(|x| x.len()).collect();
+    // (新活跃方程来自旧状态的位置或None, 是否被当前格子涉及), 当前格子涉及的方程（旧位置或None、处理后容量）
+    let mut transitions = vec![];
+    for &c in &order {
+        let active = actives.last().unwrap();
+        for &e in &cell_to_equation_map[c] {
+            remain_cells_num[e] -= 1;
+            capacity[e] -= combination_relationship[c].len() as i32;
+        }
+        let mut next_active: Vec<usize> = active
+            .iter()
+            .chain(cell_to_equation_map[c].iter())
+            .cloned()
+            .filter(|&e| remain_cells_num[e] > 0)
+            .collect();
+        next_active.sort_unstable();
+        next_active.dedup();
+        let sources: Vec<(Option<usize>, usize, bool)> = next_active
+            .iter()
+            .map(|e| {
+                (
+                    active.iter().position(|x| x == e),
+                    *e,
+                    cell_to_equation_map[c].contains(e),
+                )
+            })
+            .collect();
+        let checks: Vec<(Option<usize>, usize, i32)> = cell_to_equation_map[c]
+            .iter()
+            .map(|e| (active.iter().position(|x| x == e), *e, capacity[*e]))
+            .collect();
+        transitions.push((sources, checks));
+        actives.push(next_active);
+    }
+    // 状态转移，返回转移后的状态，不合法时返回None
+    let step = |k: usize, state: &Vec<i8>, u: usize| -> Option<Vec<i8>> {
+        let (sources, checks) = &transitions[k];
+        for (pos, e, cap) in checks {
+            let remain = match pos {
+                Some(p) => state[*p] as i32,
+                None => matrix_b[*e],
+            } - u as i32;
+            if remain < 0 || remain > *cap {
+                return None;
+            }
+        }
+        Some(
+            sources
+                .iter()
+                .map(|(pos, e, touched)| {
+                    let remain = match pos {
+                        Some(p) => state[*p] as i32,
+                        None => matrix_b[*e],
+                    };
+                    (remain - if *touched { u as i32 } else { 0 }) as i8
+                })
+                .collect(),
+        )
+    };
+    // 前向
+    let mut forward: Vec<HashMap<Vec<i8>, MineNumPoly<T>>> = vec![HashMap::new(); cells_num + 1];
+    forward[0].insert(vec![], MineNumPoly::one());
+    for k in 0..cells_num {
+        let size = combination_relationship[order[k]].len();
+        let (last, next) = forward.split_at_mut(k + 1);
+        for (state, poly) in &last[k] {
+            for u in 0..size + 1 {
+                if let Some(next_state) = step(k, state, u) {
+                    next[0]
+                        .entry(next_state)
+                        .or_insert_with(MineNumPoly::zero)
+                        .add_shifted(poly, u, &T::from_usize(C_query(size, u)));
+                }
+            }
+        }
+    }
+    if forward[cells_num].is_empty() {
+        return Err(1);
+    }
+    // 后向，同时计算每格的是雷情况数
+    let mut backward: HashMap<Vec<i8>, MineNumPoly<T>> = HashMap::new();
+    backward.insert(vec![], MineNumPoly::one());
+    let mut table_cell_poly = vec![MineNumPoly::zero(); cells_num];
+    for k in (0..cells_num).rev() {
+        let size = combination_relationship[order[k]].len();
+        let mut new_backward = HashMap::new();
+        for (state, poly) in &forward[k] {
+            let mut b = MineNumPoly::zero();
+            let mut b_is_mine = b.clone();
+            for u in 0..size + 1 {
+                if let Some(next_state) = step(k, state, u) {
+                    if let Some(next_poly) = backward.get(&next_state) {
+                        b.add_shifted(next_poly, u, &T::from_usize(C_query(size, u)));
+                        if u > 0 {
+                            // 复合格中某一格是雷的情况数
+                            b_is_mine.add_shifted(
+                                next_poly,
+                                u,
+                                &T::from_usize(C_query(size - 1, u - 1)),
+                            );
+                        }
+                    }
+                }
+            }
+            if b.coef.is_empty() {
+                continue;
+            }
+            if !b_is_mine.coef.is_empty() {
+                let t = poly.mul(&b_is_mine);
+                table_cell_poly[order[k]].add_shifted(&t, 0, &T::one());
+            }
+            new_backward.insert(state.clone(), b);
+        }
+        backward = new_backward;
+    }
+    let total = backward.remove(&vec![]).unwrap();
+    let mut mine_nums = vec![];
+    let mut table_minenum = vec![];
+    let mut table_cell_minenum = vec![];
+    for (i, c) in total.coef.iter().enumerate() {
+        if c.is_zero() {
+            continue;
+        }
+        let m = total.offset + i;
+        mine_nums.push(m);
+        table_minenum.push(c.clone());
+        table_cell_minenum.push(table_cell_poly.iter().map(|p| p.get(m)).collect());
+    }
+    Ok((mine_nums, table_minenum, table_cell_minenum))
+}
+
 pub fn cal_table_minenum_enum(
     matrixA_squeeze: &Vec<Vec<i32>>,
     matrixx_squeeze: &Vec<(usize, usize)>,
@@ -1383,97 +1759,6 @@ pub fn unsolvable_structure(BoardCheck: &Vec<Vec<i32>>) -> bool {
     false
 }
 
-// 专用于高级局面的3BV快速计算
-pub fn cal_bbbv_exp(Board: &Vec<Vec<i32>>) -> usize {
-    let mut board = Board.clone();
-    let mut op_id = 0;
-    let mut op_list = [false; 200];
-    let mut bv = 0;
-    for x in 0..16 {
-        for y in 0..30 {
-            if board[x][y] > 0 {
-                board[x][y] = 1000000;
-                bv += 1;
-            } else if board[x][y] == 0 {
-                let mut min_op_id = 1000;
-                let mut flag_op = false; // 该空周围有无空的标志位
-                if x >= 1 {
-                    for j in max(1, y) - 1..min(30, y + 2) {
-                        if board[x - 1][j] > 999999 {
-                            board[x - 1][j] = 1;
-                            bv -= 1;
-                        } else if Board[x - 1][j] == 0 {
-                            if board[x - 1][j] < min_op_id {
-                                if flag_op {
-                                    op_list[min_op_id as usize] = false;
-                                } else {
-                                    flag_op = true;
-                                }
-                                min_op_id = board[x - 1][j];
-                            }
-                        }
-                    }
-                }
-                if y >= 1 {
-                    if board[x][y - 1] > 999999 {
-                        board[x][y - 1] = 1;
-                        bv -= 1;
-                    } else if Board[x][y - 1] == 0 {
-                        if board[x][y - 1] < min_op_id {
-                            if flag_op {
-                                op_list[min_op_id as usize] = false;
-                            } else {
-                                flag_op = true;
-                            }
-                            min_op_id = board[x][y - 1];
-                        }
-                    }
-                }
-                if !flag_op {
-                    op_id += 1;
-                    op_list[op_id as usize] = true;
-                }
-            }
-        }
-    }
-    for x in (0..16).rev() {
-        for y in (0..30).rev() {
-            if board[x][y] == 0 {
-                if x <= 14 {
-                    for j in max(1, y) - 1..min(30, y + 2) {
-                        if board[x + 1][j] > 999999 {
-                            board[x + 1][j] = 1;
-                            bv -= 1;
-                        } else if Board[x + 1][j] == 0 {
-                            if board[x + 1][j] < board[x][y] {
-                                op_list[board[x][y] as usize] = false;
-                                board[x][y] = board[x + 1][j];
-                            }
-                        }
-                    }
-                }
-                if y <= 28 {
-                    if board[x][y + 1] > 999999 {
-                        board[x][y + 1] = 1;
-                        bv -= 1;
-                    } else if Board[x][y + 1] == 0 {
-                        if board[x][y + 1] < board[x][y] {
-                            op_list[board[x][y] as usize] = false;
-                            board[x][y] = board[x][y + 1];
-                        }
-                    }
-                }
-            }
-        }
-    }
-    for i in 0..op_id + 1 {
-        if op_list[i] {
-            bv += 1;
-        }
-    }
-    bv
-}
-
 // 把局面合法化：只能合法化简单的情况，不能应付所有的情况！因为检查一个局面是否合法也是NP难的
 // 配合局面光学识别算法
 // 局面中标记的标准是10为待判的雷，1到8，11为已知必然是雷的位置，12是已知必然非雷的位置
@@ -1749,12 +2034,10 @@ pub fn cal_board_numbers(board: &mut Vec<Vec<i32>>) {
     let width = board[0].len();
     for x in 0..height {
         for y in 0..width {
-            if board[x][y] == -1 {
-                for j in max(1, x) - 1..min(height, x + 2) {
-                    for k in max(1, y) - 1..min(width, y + 2) {
-                        if board[j][k] >= 0 {
-                            board[j][k] += 1;
-                        }
+            if board[x][y] == Cell::Mine {
+                for (j, k) in neighbours(height, width, x, y) {
+                    if board[j][k] >= 0 {
+                        board[j][k] += 1;
                     }
                 }
             }
@@ -0,0 +1,146 @@
+// 游戏局面的自洽性检查：找出局面中所有的矛盾，而不是像legalize_board那样悄悄改掉
+
+use crate::algorithms::solve_global;
+use crate::grid::neighbours;
+use crate::utils::{cal_table_minenum_dp, combine, refresh_matrixs};
+
+/// 游戏局面中的一处矛盾
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Contradiction {
+    /// 不认识的值。游戏局面中只能有0到8、10、11、12
+    InvalidValue { cell: (usize, usize), value: i32 },
+    /// 数字大于周围未打开的格子（含标雷，不含非雷12）的数量
+    NumberTooLarge {
+        cell: (usize, usize),
+        number: i32,
+        covered: usize,
+    },
+    /// 数字周围标的雷比数字多
+    OverFlagged {
+        cell: (usize, usize),
+        number: i32,
+        flags: usize,
+    },
+    /// 这一段未打开的格子无论怎样埋雷，都满足不了周围的数字
+    Unsatisfiable(Vec<(usize, usize)>),
+    /// 雷的总数放不下。局面能容纳的雷数在min到max之间
+    MineCountOutOfRange {
+        mine_num: usize,
+        min: usize,
+        max: usize,
+    },
+    /// 数字能证明不是雷的标雷
+    WrongFlag((usize, usize)),
+}
+
+/// 检查游戏局面是否自洽，返回所有的矛盾。
+/// - 输入：游戏局面、总雷数（含已标出的雷）。
+/// - 返回：矛盾的列表，为空说明局面自洽。检查分三层，前一层有矛盾时不再做后面的检查：
+///   格子的值与每个数字各自的约束；所有数字合在一起的约束与雷的总数；标错的雷。
+/// - 注意：判断标雷是否标错时不看玩家的标记（11、12全当成10），用[solve_global](crate::solve_global)判雷，
+///   段的长度超出枚举极限时，该段上标错的雷可能查不出。
+/// - 用python调用时的示例：
+/// ```python
+/// import ms_toollib as ms
+/// for c in ms.validate_game_board(game_board, 99):
+///     print(c["kind"], c.get("cell"))
+/// ```
+pub fn validate_game_board(board_of_game: &[Vec<i32>], mine_num: usize) -> Vec<Contradiction> {
+    let mut ans = cal_number_contradictions(board_of_game);
+    if !ans.is_empty() {
+        return ans;
+    }
+    let board: Vec<Vec<i32>> = board_of_game
+        .iter()
+        .map(|r| r.iter().map(|&v| if v > 10 { 10 } else { v }).collect())
+        .collect();
+    let (matrix_a_s, matrix_x_s, matrix_b_s, unknow_block, _) = refresh_matrixs(&board);
+    let mut min_mine_num = 0;
+    let mut max_mine_num = unknow_block;
+    for i in 0..matrix_a_s.len() {
+        let (matrix_a_squeeze, _, comb_relp) = combine(&matrix_a_s[i], &matrix_x_s[i]);
+        match cal_table_minenum_dp::<f64>(&matrix_a_squeeze, &matrix_b_s[i], &comb_relp) {
+            Ok((mine_nums, _, _)) => {
+                min_mine_num += mine_nums[0];
+                max_mine_num += mine_nums[mine_nums.len() - 1];
+            }
+            Err(_) => ans.push(Contradiction::Unsatisfiable(matrix_x_s[i].clone())),
+        }
+    }
+    if !ans.is_empty() {
+        return ans;
+    }
+    if mine_num < min_mine_num || mine_num > max_mine_num {
+        return vec![Contradiction::MineCountOutOfRange {
+            mine_num,
+            min: min_mine_num,
+            max: max_mine_num,
+        }];
+    }
+    let (not_mine, _) = solve_global(&matrix_a_s, &matrix_x_s, &matrix_b_s, &board, mine_num);
+    for (i, r) in board_of_game.iter().enumerate() {
+        for (j, &v) in r.iter().enumerate() {
+            if v == 11 && not_mine.contains(&(i, j)) {
+                ans.push(Contradiction::WrongFlag((i, j)));
+            }
+        }
+    }
+    ans
+}
+
+/// 数字是否有矛盾，供计算概率时先行检查。局面中有14~18等标记时不检查，与原来一样忽略这些格子，
+/// 以免带着失败、高亮标记的局面算不出概率。
+pub(crate) fn has_number_contradiction(board_of_game: &[Vec<i32>]) -> bool {
+    let ans = cal_number_contradictions(board_of_game);
+    !ans.is_empty()
+        && !ans
+            .iter()
+            .any(|c| matches!(c, Contradiction::InvalidValue { .. }))
+}
+
+/// 格子的值与每个数字各自的约束的矛盾。周围没有未打开格子的数字不会进入矩阵，只能在这里查出。
+pub(crate) fn cal_number_contradictions(board_of_game: &[Vec<i32>]) -> Vec<Contradiction> {
+    let row = board_of_game.len();
+    let column = board_of_game[0].len();
+    let mut ans = vec![];
+    for i in 0..row {
+        for j in 0..column {
+            let number = board_of_game[i][j];
+            if !(0..=8).contains(&number) {
+                if !(10..=12).contains(&number) {
+                    ans.push(Contradiction::InvalidValue {
+                        cell: (i, j),
+                        value: number,
+                    });
+                }
+                continue;
+            }
+            let mut flags = 0;
+            let mut covered = 0;
+            for (m, n) in neighbours(row, column, i, j) {
+                match board_of_game[m][n] {
+                    10 => covered += 1,
+                    11 => {
+                        covered += 1;
+                        flags += 1
+                    }
+                    _ => {}
+                }
+            }
+            if flags > number as usize {
+                ans.push(Contradiction::OverFlagged {
+                    cell: (i, j),
+                    number,
+                    flags,
+                });
+            } else if covered < number as usize {
+                ans.push(Contradiction::NumberTooLarge {
+                    cell: (i, j),
+                    number,
+                    covered,
+                });
+            }
+        }
+    }
+    ans
+}
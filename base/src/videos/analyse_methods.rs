@@ -1,7 +1,12 @@
 use crate::algorithms::{solve_direct, solve_enumerate, solve_minus};
+use crate::board::GameBoard;
+use crate::endgame::solve_endgame;
+use crate::grid::neighbours;
+use crate::pattern::PatternRecognizer;
 use crate::utils::{is_good_chording, refresh_matrix, refresh_matrixs};
 use crate::MouseState;
 use crate::videos::base_video::{BaseVideo, ErrReadVideoReason, VideoActionStateRecorder};
+use std::collections::BTreeMap;
 
 // 录像的事件分析。参与分析的录像必须已经计算出对应的数据。
 // error: 高风险的猜雷（猜对概率0.05）√
@@ -17,6 +22,8 @@ use crate::videos::base_video::{BaseVideo, ErrReadVideoReason, VideoActionStateR
 // suspect: 点击速度过快(0.01)
 // suspect: 鼠标移动过快(2)
 // suspect: 笔直的鼠标轨迹(101%)√
+// feature: 判雷的依据与用时√
+// warning: 残局非最优的猜雷(胜率0.250，最优0.500)√
 pub fn analyse_high_risk_guess(video: &mut BaseVideo) {
     let mut x;
     let mut y;
@@ -386,3 +393,234 @@ pub fn analyse_super_fl_local(video: &mut BaseVideo) {
         // println!("{:?}", video.video_action_state_recorder[last_ide].mouse_state);
     }
 }
+
+/// 一次正确判雷的依据：局部定式，或者判雷引擎的层级
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub enum JudgeCategory {
+    /// 定式的名称，见PatternRecognizer
+    Pattern(String),
+    /// 单集合、双集合判出的非雷，即GameBoard::get_basic_not_mine
+    BasicNotMine,
+    BasicIsMine,
+    /// 只有枚举才能判出的非雷，即GameBoard::get_enum_not_mine
+    EnumNotMine,
+    EnumIsMine,
+}
+
+impl JudgeCategory {
+    /// 汇总时用的名称
+    pub fn name(&self) -> String {
+        match self {
+            JudgeCategory::Pattern(name) => name.clone(),
+            JudgeCategory::BasicNotMine => "basic_not_mine".to_string(),
+            JudgeCategory::BasicIsMine => "basic_is_mine".to_string(),
+            JudgeCategory::EnumNotMine => "enum_not_mine".to_string(),
+            JudgeCategory::EnumIsMine => "enum_is_mine".to_string(),
+        }
+    }
+    // 难度，双击涉及多个格子时取最难的
+    fn rank(&self) -> u8 {
+        match self {
+            JudgeCategory::BasicNotMine | JudgeCategory::BasicIsMine => 0,
+            JudgeCategory::Pattern(_) => 1,
+            JudgeCategory::EnumNotMine | JudgeCategory::EnumIsMine => 2,
+        }
+    }
+}
+
+/// 一次正确的判雷：事件的索引、依据，以及距上一次有效操作的用时（秒）
+#[derive(Debug, PartialEq, Clone)]
+pub struct JudgeEvent {
+    pub event_id: usize,
+    pub category: JudgeCategory,
+    pub reaction_time: f64,
+}
+
+/// 找出录像中所有正确的判雷，即打开判出的非雷、标判出的雷、双击打开判出的非雷。猜雷、错误的标雷不计入。
+/// 第一次有效操作没有用时，不计入。
+pub fn cal_judge_events(video: &mut BaseVideo) -> Vec<JudgeEvent> {
+    let recognizer = PatternRecognizer::new();
+    let mut ans = vec![];
+    let mut last_time = None;
+    for ide in 0..video.video_action_state_recorder.len() {
+        let e = &video.video_action_state_recorder[ide];
+        if e.useful_level == 0 {
+            continue;
+        }
+        let x = (e.y / video.cell_pixel_size as u16) as usize;
+        let y = (e.x / video.cell_pixel_size as u16) as usize;
+        let time = e.time;
+        let game_board = &mut video.game_board_stream[e.prior_game_board_id];
+        let (cells, is_mine) = if e.useful_level == 3 {
+            let (row, column) = (game_board.game_board.len(), game_board.game_board[0].len());
+            let cells = neighbours(row, column, x, y)
+                .filter(|&(i, j)| game_board.game_board[i][j] == 10)
+                .collect();
+            (cells, false)
+        } else if e.useful_level == 2 && e.mouse == "lr" {
+            (vec![(x, y)], false)
+        } else if e.useful_level == 1 && e.mouse == "rc" && game_board.game_board[x][y] == 10 {
+            (vec![(x, y)], true)
+        } else {
+            (vec![], false)
+        };
+        if let Some(t) = last_time {
+            if let Some(category) = cal_judge_category(game_board, &recognizer, &cells, is_mine) {
+                ans.push(JudgeEvent {
+                    event_id: ide,
+                    category,
+                    reaction_time: time - t,
+                });
+            }
+        }
+        last_time = Some(time);
+    }
+    ans
+}
+
+// 格子能否判出，以及依据。先找定式，找不到再看判雷引擎的层级
+// is_none_or要求rust 1.82，这里用map_or兼容更老的编译器
+#[allow(clippy::unnecessary_map_or)]
+fn cal_judge_category(
+    game_board: &mut GameBoard,
+    recognizer: &PatternRecognizer,
+    cells: &[(usize, usize)],
+    is_mine: bool,
+) -> Option<JudgeCategory> {
+    if cells.is_empty() {
+        return None;
+    }
+    let basic_is_mine = game_board.get_basic_is_mine().clone();
+    let enum_is_mine = game_board.get_enum_is_mine().clone();
+    // 玩家的标雷可能是错的，只保留能判出的
+    let mut board = game_board.game_board.clone();
+    for i in 0..board.len() {
+        for j in 0..board[0].len() {
+            if board[i][j] == 11 && !basic_is_mine.contains(&(i, j)) && !enum_is_mine.contains(&(i, j)) {
+                board[i][j] = 10;
+            }
+        }
+    }
+    let matches = recognizer.find(&board);
+    let mut ans: Option<JudgeCategory> = None;
+    for cell in cells {
+        let category = if let Some(m) = matches.iter().find(|m| {
+            if is_mine {
+                m.mines.contains(cell)
+            } else {
+                m.safes.contains(cell)
+            }
+        }) {
+            JudgeCategory::Pattern(m.name.clone())
+        } else if is_mine && basic_is_mine.contains(cell) {
+            JudgeCategory::BasicIsMine
+        } else if is_mine && enum_is_mine.contains(cell) {
+            JudgeCategory::EnumIsMine
+        } else if !is_mine && game_board.get_basic_not_mine().contains(cell) {
+            JudgeCategory::BasicNotMine
+        } else if !is_mine && game_board.get_enum_not_mine().contains(cell) {
+            JudgeCategory::EnumNotMine
+        } else {
+            return None;
+        };
+        if ans.as_ref().map_or(true, |a| category.rank() > a.rank()) {
+            ans = Some(category);
+        }
+    }
+    ans
+}
+
+pub fn analyse_judge_speed(video: &mut BaseVideo) {
+    for e in cal_judge_events(video) {
+        video.video_action_state_recorder[e.event_id].comments = format!(
+            "{}feature: 判雷依据{}(用时{:.3}s);",
+            video.video_action_state_recorder[e.event_id].comments,
+            e.category.name(),
+            e.reaction_time
+        );
+    }
+}
+
+/// 残局中参与分析的最多的未打开格子数，以及与局面相容的雷的分布的最大数量
+const ENDGAME_MAX_CELLS: usize = 24;
+const ENDGAME_BUDGET: usize = 1000;
+
+pub fn analyse_endgame_guess(video: &mut BaseVideo) {
+    // 功能：残局中的每一次猜雷，与最优策略下的胜率比较
+    for ide in 2..video.video_action_state_recorder.len() {
+        let e = &video.video_action_state_recorder[ide];
+        if e.useful_level != 2 || e.mouse != "lr" {
+            continue;
+        }
+        let x = (e.y / video.cell_pixel_size as u16) as usize;
+        let y = (e.x / video.cell_pixel_size as u16) as usize;
+        let prior = e.prior_game_board_id;
+        // 玩家的标雷不一定对，全部当作未打开
+        let board: Vec<Vec<i32>> = video.game_board_stream[prior]
+            .game_board
+            .iter()
+            .map(|r| r.iter().map(|&v| if v > 10 { 10 } else { v }).collect())
+            .collect();
+        if board.iter().flatten().filter(|&&v| v == 10).count() > ENDGAME_MAX_CELLS
+            || board[x][y] != 10
+            || video.game_board_stream[prior].get_poss()[x][y] <= 0.0
+        {
+            continue;
+        }
+        let Ok((_, moves)) = solve_endgame(&board, video.mine_num, ENDGAME_BUDGET) else {
+            continue;
+        };
+        let best = moves[0].win_probability;
+        let p = moves
+            .iter()
+            .find(|m| m.cell == (x, y))
+            .map_or(0.0, |m| m.win_probability);
+        let comment = if moves[0].wins > 0 && p < best {
+            format!("warning: 残局非最优的猜雷(胜率{:.3}，最优{:.3});", p, best)
+        } else {
+            format!("feature: 残局最优的猜雷(胜率{:.3});", p)
+        };
+        video.video_action_state_recorder[ide].comments.push_str(&comment);
+    }
+}
+
+/// 跨录像汇总各类判雷的用时。
+/// - 用法：每个录像分析完（analyse）后用add_video加入，再用report得到各类的次数与平均用时。
+#[derive(Debug, Clone, Default)]
+pub struct JudgeSpeedStats {
+    // 名称 -> (次数, 总用时)
+    stats: BTreeMap<String, (usize, f64)>,
+}
+
+impl JudgeSpeedStats {
+    pub fn new() -> JudgeSpeedStats {
+        JudgeSpeedStats::default()
+    }
+    pub fn add_video(&mut self, video: &mut BaseVideo) {
+        self.add_events(&cal_judge_events(video));
+    }
+    pub fn add_events(&mut self, events: &[JudgeEvent]) {
+        for e in events {
+            let t = self.stats.entry(e.category.name()).or_insert((0, 0.0));
+            t.0 += 1;
+            t.1 += e.reaction_time;
+        }
+    }
+    pub fn count(&self, category: &str) -> usize {
+        self.stats.get(category).map_or(0, |t| t.0)
+    }
+    /// 平均用时（秒），没有记录时为None
+    pub fn mean_time(&self, category: &str) -> Option<f64> {
+        self.stats.get(category).map(|t| t.1 / t.0 as f64)
+    }
+    /// 各类的名称、次数、平均用时，按平均用时从长到短排列
+    pub fn report(&self) -> Vec<(String, usize, f64)> {
+        let mut ans: Vec<(String, usize, f64)> = self
+            .stats
+            .iter()
+            .map(|(k, t)| (k.clone(), t.0, t.1 / t.0 as f64))
+            .collect();
+        ans.sort_by(|a, b| b.2.total_cmp(&a.2));
+        ans
+    }
+}
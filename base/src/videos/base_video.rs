@@ -1,15 +1,26 @@
 // 录像相关的类，局面在board
 
 use crate::board::GameBoard;
+use crate::constraint::ConstraintSystem;
+use crate::exec::{exec_map, ExecOptions, ExecStatus};
 use crate::cal_cell_nums;
+use crate::algorithms::{is_able_to_solve, is_guess_while_needless};
+use crate::grid::{neighbours, Cell, Grid};
+use crate::rearrange::rearrange_mines;
 use crate::miscellaneous::{s_to_ms, time_ms_between};
-use crate::utils::{cal_bbbv, cal_isl, cal_op, refresh_board};
+use crate::utils::{
+    cal_bbbv, cal_board_numbers, cal_isl, cal_op, laymine_op_rng, laymine_rng, random_seed, refresh_board_opened,
+    SeededRng,
+};
 use crate::videos::analyse_methods::{
-    analyse_high_risk_guess, analyse_jump_judge, analyse_mouse_trace, analyse_needless_guess,
-    analyse_super_fl_local, analyse_survive_poss, analyse_vision_transfer,
+    analyse_endgame_guess, analyse_high_risk_guess, analyse_judge_speed, analyse_jump_judge, analyse_mouse_trace,
+    analyse_needless_guess, analyse_super_fl_local, analyse_survive_poss, analyse_vision_transfer,
+    cal_judge_events, JudgeEvent,
 };
-use std::cmp::{max, min};
+use crate::videos::snapshot::MinesweeperBoardSnapshot;
+use std::collections::VecDeque;
 use std::fs;
+use std::sync::Mutex;
 use std::time::{Instant, SystemTime, UNIX_EPOCH};
 
 /// 没有时间、像素观念的局面状态机，侧重分析操作与局面的交互、推衍局面。在线地统计左右双击次数、ce次数、左键、右键、双击、当前解决的3BV。  
@@ -66,6 +77,23 @@ pub struct MinesweeperBoard {
     pre_flag_num: usize,
     // 中键是否按下，配合“m”、“mc”、“mr”。
     middle_hold: bool,
+    /// 首次左键的规则，默认不保护，即按给定的局面打开
+    pub first_click_rule: FirstClickRule,
+    /// 猜雷的规则，默认不干预
+    pub guess_rule: GuessRule,
+    // 首次左键、猜雷的规则埋雷、移雷用的种子，每用一次换一个
+    rule_seed: u64,
+    // 是否已经左键打开过格子
+    first_clicked: bool,
+    // 可以撤销的步，最多history_limit步
+    history: VecDeque<MinesweeperBoardSnapshot>,
+    // 撤销后可以重做的步
+    future: Vec<MinesweeperBoardSnapshot>,
+    history_limit: usize,
+    // 最近一次step打开的数字格子，录像分析时用来增量地更新约束
+    last_opened: Vec<(usize, usize)>,
+    // 最近一次step是否按首次左键或猜雷的规则埋雷、移雷
+    mines_moved: bool,
 }
 
 impl Default for MinesweeperBoard {
@@ -88,6 +116,15 @@ impl Default for MinesweeperBoard {
             pointer_y: 0,
             pre_flag_num: 0,
             middle_hold: false,
+            first_click_rule: FirstClickRule::NoRule,
+            guess_rule: GuessRule::Standard,
+            rule_seed: 0,
+            first_clicked: false,
+            history: VecDeque::new(),
+            future: vec![],
+            history_limit: 0,
+            last_opened: vec![],
+            mines_moved: false,
         }
     }
 }
@@ -106,13 +143,32 @@ impl MinesweeperBoard {
             ..MinesweeperBoard::default()
         }
     }
+    /// 最近一次step是否按首次左键或猜雷的规则埋雷、移雷，即局面是否变了
+    pub fn is_mines_moved(&self) -> bool {
+        self.mines_moved
+    }
+    /// 网格表示的局面，不认识的值视为未打开
+    pub fn board_grid(&self) -> Grid<Cell> {
+        Grid::from_vec(&self.board).map(|&v| Cell::from_i32(v).unwrap_or(Cell::Covered))
+    }
+    /// 网格表示的游戏局面，不认识的值视为未打开
+    pub fn game_board_grid(&self) -> Grid<Cell> {
+        Grid::from_vec(&self.game_board).map(|&v| Cell::from_i32(v).unwrap_or(Cell::Covered))
+    }
     /// Playing状态下的左击，没有按下抬起之分
     fn left_click(&mut self, x: usize, y: usize) -> Result<u8, ()> {
         self.left += 1;
-        if self.game_board[x][y] != 10 {
+        if self.game_board[x][y] != Cell::Covered {
             return Ok(0);
         }
-        refresh_board(&self.board, &mut self.game_board, vec![(x, y)]);
+        if !self.first_clicked {
+            self.first_clicked = true;
+            self.apply_first_click_rule(x, y);
+        } else {
+            self.apply_guess_rule(x, y);
+        }
+        let mut opened = refresh_board_opened(&self.board, &mut self.game_board, vec![(x, y)]);
+        self.last_opened.append(&mut opened);
         match self.board[x][y] {
             0 => {
                 self.bbbv_solved += 1;
@@ -141,36 +197,160 @@ impl MinesweeperBoard {
             }
         }
     }
+    /// 设置首次左键的规则。只能在游戏开始（首次左键）前设置。
+    /// - 注意：首次左键时埋雷的规则（Arbiter、Win7），雷数取当时局面中雷的数量，局面的布局被丢弃。
+    pub fn set_first_click_rule(&mut self, rule: FirstClickRule) -> Result<u8, ()> {
+        self.set_first_click_rule_seed(rule, random_seed())
+    }
+    /// 可设种子的设置首次左键的规则。相同的种子、局面与操作，首次左键后的局面相同。
+    pub fn set_first_click_rule_seed(&mut self, rule: FirstClickRule, seed: u64) -> Result<u8, ()> {
+        if self.first_clicked
+            || (self.game_board_state != GameBoardState::Ready
+                && self.game_board_state != GameBoardState::PreFlaging)
+        {
+            return Err(());
+        }
+        self.first_click_rule = rule;
+        self.rule_seed = seed;
+        Ok(0)
+    }
+    /// 首次左键(x, y)时，按规则埋雷或移雷
+    fn apply_first_click_rule(&mut self, x: usize, y: usize) {
+        let mine_num = self.board.iter().flatten().filter(|&&v| v == Cell::Mine).count();
+        let mut rng = SeededRng::new(self.rule_seed);
+        self.rule_seed = rng.next_u64();
+        match self.first_click_rule {
+            FirstClickRule::NoRule => return,
+            FirstClickRule::Arbiter => {
+                self.board = laymine_rng(self.row, self.column, mine_num, x, y, &mut rng);
+            }
+            FirstClickRule::Win7 => {
+                // 格子不够开空时，退化为起手不是雷
+                let op_area = neighbours(self.row, self.column, x, y).count() + 1;
+                self.board = if mine_num + op_area <= self.row * self.column {
+                    laymine_op_rng(self.row, self.column, mine_num, x, y, &mut rng)
+                } else {
+                    laymine_rng(self.row, self.column, mine_num, x, y, &mut rng)
+                };
+            }
+            FirstClickRule::Xp | FirstClickRule::Relocate => {
+                if self.board[x][y] != Cell::Mine {
+                    return;
+                }
+                let free: Vec<(usize, usize)> = (0..self.row)
+                    .flat_map(|i| (0..self.column).map(move |j| (i, j)))
+                    .filter(|&(i, j)| self.board[i][j] != Cell::Mine)
+                    .collect();
+                if free.is_empty() {
+                    return;
+                }
+                // XP移到从左上角开始，按行找到的第一个不是雷的格子
+                let (i, j) = match self.first_click_rule {
+                    FirstClickRule::Xp => free[0],
+                    _ => free[rng.gen_range(free.len())],
+                };
+                for v in self.board.iter_mut().flatten() {
+                    if *v != Cell::Mine {
+                        *v = 0;
+                    }
+                }
+                self.board[x][y] = 0;
+                self.board[i][j] = Cell::Mine.to_i32();
+                cal_board_numbers(&mut self.board);
+            }
+        }
+        self.mines_moved = true;
+        self.recount_flaged_list();
+    }
+    /// 移雷后，按新的局面重新统计曾经被标过的雷及其ce。
+    /// 原来标对的格子可能不再是雷，标错的格子可能变成了雷
+    fn recount_flaged_list(&mut self) {
+        let mut old_list = std::mem::take(&mut self.flagedList);
+        old_list.sort_unstable();
+        old_list.dedup();
+        for i in 0..self.row {
+            for j in 0..self.column {
+                if self.board[i][j] == Cell::Mine
+                    && (self.game_board[i][j] == Cell::Flag || old_list.binary_search(&(i, j)).is_ok())
+                {
+                    self.flagedList.push((i, j));
+                }
+            }
+        }
+        self.ce = self.ce + self.flagedList.len() - old_list.len();
+    }
+    /// 设置猜雷的规则。只能在游戏开始（首次左键）前设置。
+    pub fn set_guess_rule(&mut self, rule: GuessRule) -> Result<u8, ()> {
+        if self.first_clicked
+            || (self.game_board_state != GameBoardState::Ready
+                && self.game_board_state != GameBoardState::PreFlaging)
+        {
+            return Err(());
+        }
+        self.guess_rule = rule;
+        Ok(0)
+    }
+    /// 左键(x, y)前，按猜雷的规则判断玩家是否在猜雷，需要时移雷，使该格必然是雷或必然不是雷
+    fn apply_guess_rule(&mut self, x: usize, y: usize) {
+        let able_to_solve = || is_able_to_solve(&mut self.game_board.clone(), &(x, y));
+        let needless = || is_guess_while_needless(&mut self.game_board.clone(), &(x, y));
+        let must_be_mine = match self.guess_rule {
+            GuessRule::Standard => None,
+            GuessRule::StrongNoGuess => (!able_to_solve()).then_some(true),
+            GuessRule::WeakNoGuess => match needless() {
+                2 => Some(false),
+                3 => Some(true),
+                _ => None,
+            },
+            GuessRule::QuasiNoGuess => (needless() == 3).then_some(true),
+            GuessRule::StrongGuessable => (!able_to_solve()).then_some(false),
+            GuessRule::WeakGuessable => (needless() == 2).then_some(false),
+        };
+        let is_mine = match must_be_mine {
+            Some(t) => t,
+            None => return,
+        };
+        if (self.board[x][y] == Cell::Mine) == is_mine {
+            return;
+        }
+        let mut rng = SeededRng::new(self.rule_seed);
+        self.rule_seed = rng.next_u64();
+        if let Some(board) = rearrange_mines(&self.board, &self.game_board, (x, y), is_mine, rng.next_u64()) {
+            self.board = board;
+            self.mines_moved = true;
+            self.recount_flaged_list();
+        }
+    }
     /// Playing状态下的右击，没有按下抬起之分
     fn right_click(&mut self, x: usize, y: usize) -> Result<u8, ()> {
         self.right += 1;
         if self.game_board[x][y] < 10 {
             return Ok(0);
         } else {
-            if self.board[x][y] != -1 {
-                match self.game_board[x][y] {
-                    10 => {
-                        self.game_board[x][y] = 11;
+            if self.board[x][y] != Cell::Mine {
+                match Cell::from_i32(self.game_board[x][y]) {
+                    Some(Cell::Covered) => {
+                        self.game_board[x][y] = Cell::Flag.to_i32();
                         self.flag += 1;
                     }
-                    11 => {
-                        self.game_board[x][y] = 10;
+                    Some(Cell::Flag) => {
+                        self.game_board[x][y] = Cell::Covered.to_i32();
                         self.flag -= 1;
                     }
                     _ => return Err(()),
                 }
             } else {
-                match self.game_board[x][y] {
-                    10 => {
-                        self.game_board[x][y] = 11;
+                match Cell::from_i32(self.game_board[x][y]) {
+                    Some(Cell::Covered) => {
+                        self.game_board[x][y] = Cell::Flag.to_i32();
                         self.flag += 1;
                         if !self.flagedList.contains(&(x, y)) {
                             self.ce += 1;
                         }
                         self.flagedList.push((x, y));
                     }
-                    11 => {
-                        self.game_board[x][y] = 10;
+                    Some(Cell::Flag) => {
+                        self.game_board[x][y] = Cell::Covered.to_i32();
                         self.flag -= 1;
                     }
                     _ => return Err(()),
@@ -190,23 +370,19 @@ impl MinesweeperBoard {
         let mut flagedNum = 0; // 双击点周围的标雷数
         let mut surround3BV = 0; // 周围的3BV
         let mut flag_ch_op = false; // 是否通过双击开空了：一次双击最多打开一个空
-        for i in max(1, x) - 1..min(self.row, x + 2) {
-            for j in max(1, y) - 1..min(self.column, y + 2) {
-                if i != x || j != y {
-                    if self.game_board[i][j] == 11 {
-                        flagedNum += 1
-                    }
-                    if self.game_board[i][j] == 10 {
-                        chordingCells.push((i, j));
-                        flagChordingUseful = true;
-                        if self.board[i][j] > 0 {
-                            if self.num_is_3BV(i, j) {
-                                surround3BV += 1;
-                            }
-                        } else if self.board[i][j] == 0 {
-                            flag_ch_op = true;
-                        }
+        for (i, j) in neighbours(self.row, self.column, x, y) {
+            if self.game_board[i][j] == Cell::Flag {
+                flagedNum += 1
+            }
+            if self.game_board[i][j] == Cell::Covered {
+                chordingCells.push((i, j));
+                flagChordingUseful = true;
+                if self.board[i][j] > 0 {
+                    if self.num_is_3BV(i, j) {
+                        surround3BV += 1;
                     }
+                } else if self.board[i][j] == 0 {
+                    flag_ch_op = true;
                 }
             }
         }
@@ -217,11 +393,12 @@ impl MinesweeperBoard {
                 self.bbbv_solved += 1;
             }
             for ch in &chordingCells {
-                if self.board[ch.0][ch.1] == -1 {
+                if self.board[ch.0][ch.1] == Cell::Mine {
                     self.game_board_state = GameBoardState::Loss;
                 }
             }
-            refresh_board(&self.board, &mut self.game_board, chordingCells);
+            let mut opened = refresh_board_opened(&self.board, &mut self.game_board, chordingCells);
+            self.last_opened.append(&mut opened);
             if self.is_win() {
                 self.game_board_state = GameBoardState::Win;
             }
@@ -236,14 +413,7 @@ impl MinesweeperBoard {
         if self.board[x][y] <= 0 {
             return false;
         }
-        for i in max(1, x) - 1..min(self.row, x + 2) {
-            for j in max(1, y) - 1..min(self.column, y + 2) {
-                if self.board[i][j] == 0 {
-                    return false;
-                }
-            }
-        }
-        true
+        !neighbours(self.row, self.column, x, y).any(|(i, j)| self.board[i][j] == 0)
     }
     /// 返回的值的含义是：0：没有任何作用的操作，例如左键数字、踩雷。  
     /// 1：推进了局面，但没有改变ai对局面的判断，特指标雷。  
@@ -254,8 +424,33 @@ impl MinesweeperBoard {
     ///     l（左键按下或抬起）, r（右键按下或抬起）, m（中键按下或抬起）。  
     /// ## 注意事项：
     /// - 在理想的鼠标状态机中，有些情况是不可能的，例如右键没有抬起就按下两次，但在阿比特中就观察到这种事情。
-    // 局面外按下的事件，以及连带的释放一律对鼠标状态没有任何影响，UI框架不会激活回调
+    /// - 打开了撤销（见[set_history_limit](MinesweeperBoard::set_history_limit)）时，每一步都可以撤销；出错的步不改变任何状态，也不记录。
+    ///   没有打开撤销时，出错的步可能已经改变了一部分状态，例如点击次数、鼠标状态。
     pub fn step(&mut self, e: &str, pos: (usize, usize)) -> Result<u8, ()> {
+        self.last_opened.clear();
+        self.mines_moved = false;
+        if self.history_limit == 0 {
+            return self.step_once(e, pos);
+        }
+        let snapshot = self.snapshot();
+        let ans = match self.step_once(e, pos) {
+            Ok(ans) => ans,
+            Err(e) => {
+                self.restore(&snapshot);
+                self.last_opened.clear();
+                self.mines_moved = false;
+                return Err(e);
+            }
+        };
+        if self.history.len() >= self.history_limit {
+            self.history.pop_front();
+        }
+        self.history.push_back(snapshot);
+        self.future.clear();
+        Ok(ans)
+    }
+    // 局面外按下的事件，以及连带的释放一律对鼠标状态没有任何影响，UI框架不会激活回调
+    fn step_once(&mut self, e: &str, pos: (usize, usize)) -> Result<u8, ()> {
         // println!("e: {:?}", e);
         if pos.0 == self.row && pos.1 == self.column && (e == "rc" || e == "lc" || e == "cc") {
             // 这里按理应该报错，局面外的按下不该进来
@@ -670,6 +865,91 @@ impl MinesweeperBoard {
         self.game_board_state = GameBoardState::Ready;
         self.pointer_x = 0;
         self.pointer_y = 0;
+        self.first_clicked = false;
+        self.history.clear();
+        self.future.clear();
+        self.last_opened.clear();
+        self.mines_moved = false;
+    }
+    /// 当前的全部状态的快照，不含撤销、重做的历史
+    pub fn snapshot(&self) -> MinesweeperBoardSnapshot {
+        MinesweeperBoardSnapshot {
+            board: self.board.clone(),
+            game_board: self.game_board.clone(),
+            flaged_list: self.flagedList.clone(),
+            left: self.left,
+            right: self.right,
+            double: self.double,
+            ce: self.ce,
+            flag: self.flag,
+            bbbv_solved: self.bbbv_solved,
+            mouse_state: self.mouse_state,
+            game_board_state: self.game_board_state,
+            pointer_x: self.pointer_x,
+            pointer_y: self.pointer_y,
+            pre_flag_num: self.pre_flag_num,
+            middle_hold: self.middle_hold,
+            first_click_rule: self.first_click_rule,
+            guess_rule: self.guess_rule,
+            rule_seed: self.rule_seed,
+            first_clicked: self.first_clicked,
+        }
+    }
+    /// 恢复到快照时的状态。快照可以来自另一个局面状态机，局面的尺寸也可以不同。撤销、重做的历史不变。
+    pub fn restore(&mut self, snapshot: &MinesweeperBoardSnapshot) {
+        self.board = snapshot.board.clone();
+        self.game_board = snapshot.game_board.clone();
+        self.flagedList = snapshot.flaged_list.clone();
+        self.left = snapshot.left;
+        self.right = snapshot.right;
+        self.double = snapshot.double;
+        self.ce = snapshot.ce;
+        self.flag = snapshot.flag;
+        self.bbbv_solved = snapshot.bbbv_solved;
+        self.row = snapshot.board.len();
+        self.column = snapshot.board[0].len();
+        self.mouse_state = snapshot.mouse_state;
+        self.game_board_state = snapshot.game_board_state;
+        self.pointer_x = snapshot.pointer_x;
+        self.pointer_y = snapshot.pointer_y;
+        self.pre_flag_num = snapshot.pre_flag_num;
+        self.middle_hold = snapshot.middle_hold;
+        self.first_click_rule = snapshot.first_click_rule;
+        self.guess_rule = snapshot.guess_rule;
+        self.rule_seed = snapshot.rule_seed;
+        self.first_clicked = snapshot.first_clicked;
+    }
+    /// 设置最多可以撤销的步数，为0时不记录（默认）。每一步记录一个快照，在录像分析等不需要撤销的场合应保持为0。
+    pub fn set_history_limit(&mut self, limit: usize) {
+        self.history_limit = limit;
+        while self.history.len() > limit {
+            self.history.pop_front();
+        }
+        if limit == 0 {
+            self.future.clear();
+        }
+    }
+    /// 撤销一步。没有可以撤销的步时返回false
+    pub fn undo(&mut self) -> bool {
+        match self.history.pop_back() {
+            Some(snapshot) => {
+                self.future.push(self.snapshot());
+                self.restore(&snapshot);
+                true
+            }
+            None => false,
+        }
+    }
+    /// 重做一步被撤销的步。撤销后又步进过、或没有撤销过时返回false
+    pub fn redo(&mut self) -> bool {
+        match self.future.pop() {
+            Some(snapshot) => {
+                self.history.push_back(self.snapshot());
+                self.restore(&snapshot);
+                true
+            }
+            None => false,
+        }
     }
     // 清空状态机里的点击次数
     fn clear_click_num(&mut self) {
@@ -699,6 +979,91 @@ pub enum MouseState {
     Undefined,
 }
 
+/// 首次左键的规则。录像中记录在mode里，其中11~13是本工具箱自定义的mode，见[BaseVideo::mode]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum FirstClickRule {
+    /// 不保护，按预先埋好的局面打开，首次左键可能踩雷。mode为13
+    NoRule,
+    /// 阿比特（标准）：首次左键时埋雷，该格不是雷，其余位置的雷服从均匀分布。mode为0
+    Arbiter,
+    /// win7：首次左键时埋雷，该格开空，其余位置的雷服从均匀分布。mode为4
+    Win7,
+    /// XP：预先埋雷，首次左键是雷时，把雷移到左上角；左上角已经是雷时，按行向右、向下找第一个不是雷的格子。mode为11
+    Xp,
+    /// 预先埋雷，首次左键是雷时，把雷移到随机的一个不是雷的格子。mode为12
+    Relocate,
+}
+
+impl FirstClickRule {
+    /// 录像中记录的游戏模式
+    pub fn to_mode(&self) -> u16 {
+        match self {
+            FirstClickRule::Arbiter => 0,
+            FirstClickRule::Win7 => 4,
+            FirstClickRule::Xp => 11,
+            FirstClickRule::Relocate => 12,
+            FirstClickRule::NoRule => 13,
+        }
+    }
+    /// 从录像中记录的游戏模式得到规则，其他模式返回None
+    pub fn from_mode(mode: u16) -> Option<FirstClickRule> {
+        match mode {
+            0 => Some(FirstClickRule::Arbiter),
+            4 => Some(FirstClickRule::Win7),
+            11 => Some(FirstClickRule::Xp),
+            12 => Some(FirstClickRule::Relocate),
+            13 => Some(FirstClickRule::NoRule),
+            _ => None,
+        }
+    }
+}
+
+/// 猜雷的规则。每次左键打开格子前，用[is_able_to_solve](crate::is_able_to_solve)或
+/// [is_guess_while_needless](crate::is_guess_while_needless)判断玩家是否在猜雷，
+/// 再按规则在与已打开的格子一致的前提下移雷（见[rearrange_mines](crate::rearrange_mines)），救活或惩罚玩家。
+/// 首次左键和双击不判断。录像中记录在mode里。
+/// - 注意：与已打开的格子矛盾而移不了雷时，不干预。
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum GuessRule {
+    /// 不干预，由局面决定。标准、竞速无猜（预先埋好无猜的局面）等模式
+    Standard,
+    /// 强无猜：打开不能判定的格子必然踩雷。mode为6
+    StrongNoGuess,
+    /// 弱无猜：可能判雷时猜雷（不必要的猜雷）必然踩雷，必要的猜雷必然不踩雷。mode为7
+    WeakNoGuess,
+    /// 准无猜：不必要的猜雷必然踩雷，必要的猜雷由局面决定。mode为8
+    QuasiNoGuess,
+    /// 强可猜：打开不能判定的格子必然不踩雷。mode为9
+    StrongGuessable,
+    /// 弱可猜：必要的猜雷必然不踩雷，不必要的猜雷由局面决定。mode为10
+    WeakGuessable,
+}
+
+impl GuessRule {
+    /// 录像中记录的游戏模式。不干预时返回None，此时记录首次左键的规则
+    pub fn to_mode(&self) -> Option<u16> {
+        match self {
+            GuessRule::Standard => None,
+            GuessRule::StrongNoGuess => Some(6),
+            GuessRule::WeakNoGuess => Some(7),
+            GuessRule::QuasiNoGuess => Some(8),
+            GuessRule::StrongGuessable => Some(9),
+            GuessRule::WeakGuessable => Some(10),
+        }
+    }
+    /// 从录像中记录的游戏模式得到规则，其他模式返回None
+    pub fn from_mode(mode: u16) -> Option<GuessRule> {
+        match mode {
+            6 => Some(GuessRule::StrongNoGuess),
+            7 => Some(GuessRule::WeakNoGuess),
+            8 => Some(GuessRule::QuasiNoGuess),
+            9 => Some(GuessRule::StrongGuessable),
+            10 => Some(GuessRule::WeakGuessable),
+            _ => None,
+        }
+    }
+}
+
 /// 游戏局面状态
 #[derive(Debug, PartialEq, Clone, Copy)]
 pub enum GameBoardState {
@@ -948,7 +1313,9 @@ pub struct BaseVideo {
     pub is_fair: bool,
     /// 是不是盲扫，初始是false，不是盲扫的话，分析完还是false
     pub nf: bool,
-    /// 游戏模式。0->标准、1->upk；2->cheat；3->Density（来自Viennasweeper、clone软件）、4->win7、5->竞速无猜、6->强无猜、7->弱无猜、8->准无猜、9->强可猜、10->弱可猜
+    /// 游戏模式。0->标准、1->upk；2->cheat；3->Density（来自Viennasweeper、clone软件）、4->win7、5->竞速无猜、6->强无猜、7->弱无猜、8->准无猜、9->强可猜、10->弱可猜、
+    /// 11->XP（首次左键是雷时移到左上角）、12->首次左键是雷时移到随机位置、13->首次左键不保护，见[FirstClickRule]。  
+    /// 注意：11~13是本工具箱自定义的，其他软件不会生成，其他读录像的工具也不一定认识。
     pub mode: u16,
     /// 游戏难度（级别）。3是初级；4是中级；5是高级；6是自定义。
     pub level: u8,
@@ -1162,6 +1529,11 @@ impl BaseVideo {
 
         let a = self.minesweeper_board.step(e, (x, y))?;
         self.game_board_state = self.minesweeper_board.game_board_state;
+        // 首次左键、猜雷的规则可能埋雷或移雷
+        if self.minesweeper_board.is_mines_moved() {
+            self.board = self.minesweeper_board.board.clone();
+            self.static_params.bbbv = cal_bbbv(&self.board);
+        }
         match self.game_board_state {
             GameBoardState::Ready => {
                 self.game_board_stream.clear();
@@ -1313,7 +1685,11 @@ impl BaseVideo {
     /// 重置游戏状态等，不重置标识等很多局都不会变的数据。点脸等，重开。
     pub fn reset(&mut self, row: usize, column: usize, pix_size: u8) {
         self.game_board_stream.clear();
-        self.minesweeper_board = MinesweeperBoard::new(vec![vec![0; column]; row]);
+        let mut minesweeper_board = MinesweeperBoard::new(vec![vec![0; column]; row]);
+        minesweeper_board.first_click_rule = self.minesweeper_board.first_click_rule;
+        minesweeper_board.guess_rule = self.minesweeper_board.guess_rule;
+        minesweeper_board.rule_seed = self.minesweeper_board.rule_seed;
+        self.minesweeper_board = minesweeper_board;
         self.width = column;
         self.height = row;
         self.cell_pixel_size = pix_size;
@@ -1394,6 +1770,8 @@ impl BaseVideo {
         let mut first_game_board = GameBoard::new(self.mine_num);
         first_game_board.set_game_board(&vec![vec![10; self.width]; self.height]);
         self.game_board_stream.push(first_game_board);
+        // 增量地维护局面的约束，只跟随打开的数字，不管玩家的标雷
+        let mut system = ConstraintSystem::new(self.height, self.width);
         for ide in 0..self.video_action_state_recorder.len() {
             // 控制svi的生命周期
             let mut svi = &mut self.video_action_state_recorder[ide];
@@ -1415,6 +1793,10 @@ impl BaseVideo {
                 if u_level >= 1 {
                     let mut g_b = GameBoard::new(self.mine_num);
                     g_b.set_game_board(&b.game_board);
+                    for &(i, j) in &b.last_opened {
+                        system.reveal((i, j), b.game_board[i][j]);
+                    }
+                    g_b.set_matrixs(system.matrixs());
                     self.game_board_stream.push(g_b);
                     if old_state != GameBoardState::Playing {
                         self.delta_time = svi.time;
@@ -1557,10 +1939,16 @@ impl BaseVideo {
                 "vision_transfer" => analyse_vision_transfer(self),
                 "survive_poss" => analyse_survive_poss(self),
                 "super_fl_local" => analyse_super_fl_local(self),
+                "judge_speed" => analyse_judge_speed(self),
+                "endgame_guess" => analyse_endgame_guess(self),
                 _ => continue,
             };
         }
     }
+    /// 录像中所有正确的判雷，及其依据（定式或判雷引擎的层级）与用时。跨录像的汇总见JudgeSpeedStats。
+    pub fn cal_judge_events(&mut self) -> Vec<JudgeEvent> {
+        cal_judge_events(self)
+    }
     pub fn print_event(&self) {
         let mut num = 0;
         for e in &self.video_action_state_recorder {
@@ -1781,6 +2169,32 @@ impl BaseVideo {
         self.mode = mode;
         Ok(0)
     }
+    /// 设置首次左键的规则，并记录到mode。只能在游戏开始前设置，见[MinesweeperBoard::set_first_click_rule]
+    pub fn set_first_click_rule(&mut self, rule: FirstClickRule) -> Result<u8, ()> {
+        self.minesweeper_board.set_first_click_rule(rule)?;
+        self.update_mode();
+        Ok(0)
+    }
+    /// 可设种子的设置首次左键的规则，并记录到mode
+    pub fn set_first_click_rule_seed(&mut self, rule: FirstClickRule, seed: u64) -> Result<u8, ()> {
+        self.minesweeper_board.set_first_click_rule_seed(rule, seed)?;
+        self.update_mode();
+        Ok(0)
+    }
+    /// 设置猜雷的规则，并记录到mode。只能在游戏开始前设置，见[GuessRule]
+    pub fn set_guess_rule(&mut self, rule: GuessRule) -> Result<u8, ()> {
+        self.minesweeper_board.set_guess_rule(rule)?;
+        self.update_mode();
+        Ok(0)
+    }
+    /// 按规则记录mode。有猜雷的规则时记录猜雷的规则，否则记录首次左键的规则
+    fn update_mode(&mut self) {
+        self.mode = self
+            .minesweeper_board
+            .guess_rule
+            .to_mode()
+            .unwrap_or(self.minesweeper_board.first_click_rule.to_mode());
+    }
     pub fn set_software(&mut self, software: Vec<u8>) -> Result<u8, ()> {
         if self.game_board_state != GameBoardState::Loss
             && self.game_board_state != GameBoardState::Win
@@ -2335,3 +2749,36 @@ impl BaseVideo {
         }
     }
 }
+
+/// 多线程批量分析录像，每个录像依次调用analyse和analyse_for_features。
+/// - 输入：已经解析过（parse_video）的录像、要分析的特征（为空时只调用analyse）、执行选项。
+/// - 返回：结束的状态。进度的总工作量为录像数，被取消或超时时，后面的录像没有被分析。
+/// - 用法示例：
+/// ```rust,no_run
+/// use ms_toollib::{analyse_videos, EvfVideo, ExecOptions};
+/// let mut videos = vec![];
+/// for file_name in ["a.evf", "b.evf"] {
+///     let mut video = EvfVideo::new(file_name);
+///     if video.parse_video().is_ok() {
+///         videos.push(video.data);
+///     }
+/// }
+/// analyse_videos(&mut videos, &["high_risk_guess", "needless_guess"], &ExecOptions::default());
+/// ```
+pub fn analyse_videos(
+    videos: &mut [BaseVideo],
+    controller: &[&str],
+    options: &ExecOptions,
+) -> ExecStatus {
+    let video_num = videos.len();
+    let videos: Vec<Mutex<&mut BaseVideo>> = videos.iter_mut().map(Mutex::new).collect();
+    let (_, status) = exec_map(options, video_num, video_num, |i, ctx| {
+        let mut video = videos[i].lock().unwrap();
+        video.analyse();
+        if !controller.is_empty() {
+            video.analyse_for_features(controller.to_vec());
+        }
+        ctx.advance(1);
+    });
+    status
+}
@@ -7,8 +7,11 @@ pub use evf_video::{EvfVideo};
 pub mod mvf_video; 
 pub use mvf_video::{MvfVideo};
 pub mod base_video; 
-pub use base_video::{MinesweeperBoard, GameBoardState, MouseState, BaseVideo};
+pub use base_video::{analyse_videos, FirstClickRule, GuessRule, MinesweeperBoard, GameBoardState, MouseState, BaseVideo};
+mod snapshot;
+pub use snapshot::{ErrSnapshot, MinesweeperBoardSnapshot};
 mod analyse_methods;
+pub use analyse_methods::{JudgeCategory, JudgeEvent, JudgeSpeedStats};
 
 
 
@@ -0,0 +1,260 @@
+// 局面状态机的快照：撤销、重做，以及把下到一半的局保存下来、以后接着下
+
+use crate::grid::Cell;
+use crate::videos::base_video::{FirstClickRule, GameBoardState, GuessRule, MouseState};
+
+/// 当前的快照格式版本
+const SNAPSHOT_VERSION: u8 = 1;
+
+/// 解析快照失败的原因
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ErrSnapshot {
+    DataIsTooShort,
+    /// 不认识的版本，可能是更新的工具生成的
+    UnsupportedVersion,
+    /// 局面的高或宽为0
+    InvalidBoardSize,
+    /// 格子、指针、鼠标状态、局面状态、规则等的取值不认识
+    InvalidValue,
+}
+
+/// [MinesweeperBoard](crate::MinesweeperBoard)在某一时刻的全部状态，用[MinesweeperBoard::snapshot](crate::MinesweeperBoard::snapshot)得到，
+/// 用[MinesweeperBoard::restore](crate::MinesweeperBoard::restore)恢复。
+/// - 用python调用时的示例：
+/// ```python
+/// import ms_toollib as ms
+/// v = ms.MinesweeperBoard(board)
+/// v.step_flow([('lc', (0, 0)), ('lr', (0, 0))])
+/// data = v.snapshot() # 可以存到文件里
+/// v.step_flow([('lc', (3, 3)), ('lr', (3, 3))])
+/// v.restore(data) # 回到打开(3, 3)之前
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct MinesweeperBoardSnapshot {
+    pub board: Vec<Vec<i32>>,
+    pub game_board: Vec<Vec<i32>>,
+    /// 曾经被标过的雷
+    pub flaged_list: Vec<(usize, usize)>,
+    pub left: usize,
+    pub right: usize,
+    pub double: usize,
+    pub ce: usize,
+    pub flag: usize,
+    pub bbbv_solved: usize,
+    pub mouse_state: MouseState,
+    pub game_board_state: GameBoardState,
+    pub pointer_x: usize,
+    pub pointer_y: usize,
+    pub pre_flag_num: usize,
+    pub middle_hold: bool,
+    pub first_click_rule: FirstClickRule,
+    pub guess_rule: GuessRule,
+    pub rule_seed: u64,
+    pub first_clicked: bool,
+}
+
+impl MinesweeperBoardSnapshot {
+    /// 编码为二进制数据。
+    /// - 格式：版本（1字节）、高、宽（各2字节）、局面与游戏局面（按行优先，每格1字节）、
+    ///   曾经被标过的雷的数量（4字节）及其位置（各2+2字节）、各计数器与指针（各4字节）、
+    ///   鼠标状态、局面状态（各1字节）、中键是否按下（1字节）、首次左键与猜雷的规则（各2字节，即mode）、
+    ///   种子（8字节）、是否已经左键打开过格子（1字节）。多字节的数都是高位在前。
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let height = self.board.len();
+        let width = self.board[0].len();
+        let mut raw_data = vec![SNAPSHOT_VERSION];
+        raw_data.extend_from_slice(&(height as u16).to_be_bytes());
+        raw_data.extend_from_slice(&(width as u16).to_be_bytes());
+        for b in [&self.board, &self.game_board] {
+            raw_data.extend(b.iter().flatten().map(|&v| v as i8 as u8));
+        }
+        raw_data.extend_from_slice(&(self.flaged_list.len() as u32).to_be_bytes());
+        for &(x, y) in &self.flaged_list {
+            raw_data.extend_from_slice(&(x as u16).to_be_bytes());
+            raw_data.extend_from_slice(&(y as u16).to_be_bytes());
+        }
+        for v in [
+            self.left,
+            self.right,
+            self.double,
+            self.ce,
+            self.flag,
+            self.bbbv_solved,
+            self.pointer_x,
+            self.pointer_y,
+            self.pre_flag_num,
+        ] {
+            raw_data.extend_from_slice(&(v as u32).to_be_bytes());
+        }
+        raw_data.push(mouse_state_to_u8(self.mouse_state));
+        raw_data.push(game_board_state_to_u8(self.game_board_state));
+        raw_data.push(self.middle_hold as u8);
+        raw_data.extend_from_slice(&self.first_click_rule.to_mode().to_be_bytes());
+        raw_data.extend_from_slice(&self.guess_rule.to_mode().unwrap_or(0).to_be_bytes());
+        raw_data.extend_from_slice(&self.rule_seed.to_be_bytes());
+        raw_data.push(self.first_clicked as u8);
+        raw_data
+    }
+    /// 从二进制数据解码，格式见[to_bytes](MinesweeperBoardSnapshot::to_bytes)
+    pub fn from_bytes(raw_data: &[u8]) -> Result<MinesweeperBoardSnapshot, ErrSnapshot> {
+        let mut reader = Reader {
+            raw_data,
+            offset: 0,
+        };
+        if reader.get_u8()? != SNAPSHOT_VERSION {
+            return Err(ErrSnapshot::UnsupportedVersion);
+        }
+        let height = reader.get_u16()? as usize;
+        let width = reader.get_u16()? as usize;
+        if height == 0 || width == 0 {
+            return Err(ErrSnapshot::InvalidBoardSize);
+        }
+        let mut boards = vec![];
+        for _ in 0..2 {
+            let mut b = vec![vec![0; width]; height];
+            for v in b.iter_mut().flatten() {
+                *v = reader.get_u8()? as i8 as i32;
+            }
+            boards.push(b);
+        }
+        let game_board = boards.pop().unwrap();
+        let board = boards.pop().unwrap();
+        // 局面只能有雷和数字，游戏局面只能有认识的格子
+        if board.iter().flatten().any(|v| !(-1..=8).contains(v))
+            || game_board
+                .iter()
+                .flatten()
+                .any(|&v| Cell::from_i32(v).is_none())
+        {
+            return Err(ErrSnapshot::InvalidValue);
+        }
+        let flaged_num = reader.get_u32()? as usize;
+        let mut flaged_list = vec![];
+        for _ in 0..flaged_num {
+            let x = reader.get_u16()? as usize;
+            let y = reader.get_u16()? as usize;
+            if x >= height || y >= width {
+                return Err(ErrSnapshot::InvalidValue);
+            }
+            flaged_list.push((x, y));
+        }
+        let mut counters = [0; 9];
+        for v in counters.iter_mut() {
+            *v = reader.get_u32()? as usize;
+        }
+        // 判断是否扫开时，从指针所在的行往后找，行不能越界，列可以在末尾
+        if counters[6] >= height || counters[7] > width {
+            return Err(ErrSnapshot::InvalidValue);
+        }
+        let mouse_state = mouse_state_from_u8(reader.get_u8()?)?;
+        let game_board_state = game_board_state_from_u8(reader.get_u8()?)?;
+        let middle_hold = reader.get_u8()? != 0;
+        let first_click_rule =
+            FirstClickRule::from_mode(reader.get_u16()?).ok_or(ErrSnapshot::InvalidValue)?;
+        let guess_rule = match reader.get_u16()? {
+            0 => GuessRule::Standard,
+            mode => GuessRule::from_mode(mode).ok_or(ErrSnapshot::InvalidValue)?,
+        };
+        let rule_seed = reader.get_u64()?;
+        let first_clicked = reader.get_u8()? != 0;
+        Ok(MinesweeperBoardSnapshot {
+            board,
+            game_board,
+            flaged_list,
+            left: counters[0],
+            right: counters[1],
+            double: counters[2],
+            ce: counters[3],
+            flag: counters[4],
+            bbbv_solved: counters[5],
+            mouse_state,
+            game_board_state,
+            pointer_x: counters[6],
+            pointer_y: counters[7],
+            pre_flag_num: counters[8],
+            middle_hold,
+            first_click_rule,
+            guess_rule,
+            rule_seed,
+            first_clicked,
+        })
+    }
+}
+
+struct Reader<'a> {
+    raw_data: &'a [u8],
+    offset: usize,
+}
+
+impl Reader<'_> {
+    fn get_bytes<const N: usize>(&mut self) -> Result<[u8; N], ErrSnapshot> {
+        let t = self
+            .raw_data
+            .get(self.offset..self.offset + N)
+            .ok_or(ErrSnapshot::DataIsTooShort)?;
+        self.offset += N;
+        Ok(t.try_into().unwrap())
+    }
+    fn get_u8(&mut self) -> Result<u8, ErrSnapshot> {
+        Ok(self.get_bytes::<1>()?[0])
+    }
+    fn get_u16(&mut self) -> Result<u16, ErrSnapshot> {
+        Ok(u16::from_be_bytes(self.get_bytes()?))
+    }
+    fn get_u32(&mut self) -> Result<u32, ErrSnapshot> {
+        Ok(u32::from_be_bytes(self.get_bytes()?))
+    }
+    fn get_u64(&mut self) -> Result<u64, ErrSnapshot> {
+        Ok(u64::from_be_bytes(self.get_bytes()?))
+    }
+}
+
+fn mouse_state_to_u8(mouse_state: MouseState) -> u8 {
+    match mouse_state {
+        MouseState::UpUp => 0,
+        MouseState::UpDown => 1,
+        MouseState::UpDownNotFlag => 2,
+        MouseState::DownUp => 3,
+        MouseState::Chording => 4,
+        MouseState::ChordingNotFlag => 5,
+        MouseState::DownUpAfterChording => 6,
+        MouseState::Undefined => 7,
+    }
+}
+
+fn mouse_state_from_u8(v: u8) -> Result<MouseState, ErrSnapshot> {
+    match v {
+        0 => Ok(MouseState::UpUp),
+        1 => Ok(MouseState::UpDown),
+        2 => Ok(MouseState::UpDownNotFlag),
+        3 => Ok(MouseState::DownUp),
+        4 => Ok(MouseState::Chording),
+        5 => Ok(MouseState::ChordingNotFlag),
+        6 => Ok(MouseState::DownUpAfterChording),
+        7 => Ok(MouseState::Undefined),
+        _ => Err(ErrSnapshot::InvalidValue),
+    }
+}
+
+fn game_board_state_to_u8(game_board_state: GameBoardState) -> u8 {
+    match game_board_state {
+        GameBoardState::Ready => 0,
+        GameBoardState::PreFlaging => 1,
+        GameBoardState::Playing => 2,
+        GameBoardState::Loss => 3,
+        GameBoardState::Win => 4,
+        GameBoardState::Display => 5,
+    }
+}
+
+fn game_board_state_from_u8(v: u8) -> Result<GameBoardState, ErrSnapshot> {
+    match v {
+        0 => Ok(GameBoardState::Ready),
+        1 => Ok(GameBoardState::PreFlaging),
+        2 => Ok(GameBoardState::Playing),
+        3 => Ok(GameBoardState::Loss),
+        4 => Ok(GameBoardState::Win),
+        5 => Ok(GameBoardState::Display),
+        _ => Err(ErrSnapshot::InvalidValue),
+    }
+}
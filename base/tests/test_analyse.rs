@@ -1,6 +1,10 @@
 // 测试录像分析模块
-use ms_toollib::{AvfVideo, BaseVideo, EvfVideo, MinesweeperBoard, RmvVideo, MvfVideo};
+use ms_toollib::{
+    AvfVideo, BaseVideo, ErrSnapshot, EvfVideo, FirstClickRule, GameBoardState, GuessRule, JudgeCategory, JudgeSpeedStats,
+    MinesweeperBoard, MinesweeperBoardSnapshot, MvfVideo, RmvVideo,
+};
 use std::thread;
+use std::time::Duration;
 
 #[test]
 fn minesweeper_board_works() {
@@ -172,6 +176,202 @@ fn EvfVideo_works() {
     video.data.print_comments();
 }
 
+#[test]
+fn first_click_rule_works() {
+    // 测试首次左键的规则：XP把雷移到左上角，win7开空，不保护时踩雷
+    let board = vec![
+        vec![0, 1, -1, 1],
+        vec![0, 1, 1, 1],
+        vec![1, 1, 0, 0],
+        vec![-1, 1, 0, 0],
+    ];
+    let mut my_board = MinesweeperBoard::new(board.clone());
+    my_board.set_first_click_rule(FirstClickRule::Xp).unwrap();
+    my_board.step("lc", (0, 2)).unwrap();
+    my_board.step("lr", (0, 2)).unwrap();
+    assert!(my_board.is_mines_moved());
+    assert_eq!(my_board.board[0][0], -1);
+    assert_eq!(my_board.board[0][1], 1);
+    assert_eq!(my_board.game_board[0][2], 0);
+    assert_eq!(my_board.game_board_state, GameBoardState::Playing);
+    my_board.step("lc", (3, 3)).unwrap();
+    assert!(!my_board.is_mines_moved());
+    let mut my_board = MinesweeperBoard::new(board.clone());
+    my_board.set_first_click_rule_seed(FirstClickRule::Relocate, 7).unwrap();
+    my_board.step_flow(vec![("lc", (3, 0)), ("lr", (3, 0))]).unwrap();
+    assert_ne!(my_board.board[3][0], -1);
+    assert_eq!(my_board.board.iter().flatten().filter(|&&x| x == -1).count(), 2);
+    for x0 in 0..4 {
+        let mut my_board = MinesweeperBoard::new(board.clone());
+        my_board.set_first_click_rule(FirstClickRule::Win7).unwrap();
+        my_board.step_flow(vec![("lc", (x0, 3)), ("lr", (x0, 3))]).unwrap();
+        assert_eq!(my_board.board[x0][3], 0);
+        assert_eq!(my_board.board.iter().flatten().filter(|&&x| x == -1).count(), 2);
+    }
+    let mut my_board = MinesweeperBoard::new(board.clone());
+    my_board.step_flow(vec![("lc", (0, 2)), ("lr", (0, 2))]).unwrap();
+    assert_eq!(my_board.game_board_state, GameBoardState::Loss);
+    assert!(my_board.set_first_click_rule(FirstClickRule::Xp).is_err());
+    // 录像记录规则，且记录的是首次左键后的局面
+    let mut video = BaseVideo::new_before_game(board, 16);
+    video.set_first_click_rule_seed(FirstClickRule::Arbiter, 7).unwrap();
+    assert_eq!(video.mode, 0);
+    video.set_first_click_rule_seed(FirstClickRule::Xp, 7).unwrap();
+    assert_eq!(video.mode, 11);
+    assert_eq!(FirstClickRule::from_mode(video.mode), Some(FirstClickRule::Xp));
+    video.step("lc", (8, 40)).unwrap();
+    video.step("lr", (8, 40)).unwrap();
+    assert_eq!(video.board[0][0], -1);
+    assert_eq!(video.board, video.minesweeper_board.board);
+}
+
+#[test]
+fn first_click_rule_evf_works() {
+    // 测试首次左键的规则记录在evf的mode里，存成文件再读回来不变。11~13是本工具箱自定义的mode
+    let mut board = vec![vec![0; 8]; 8];
+    board[7][7] = -1;
+    board[6][6] = 1;
+    board[6][7] = 1;
+    board[7][6] = 1;
+    for rule in [
+        FirstClickRule::Arbiter,
+        FirstClickRule::Win7,
+        FirstClickRule::Xp,
+        FirstClickRule::Relocate,
+        FirstClickRule::NoRule,
+    ] {
+        let mut video = BaseVideo::new_before_game(board.clone(), 16);
+        video.set_first_click_rule_seed(rule, 7).unwrap();
+        video.step("lc", (1, 1)).unwrap();
+        video.step("lr", (1, 1)).unwrap();
+        // 首次左键后埋雷、移雷，按真实的局面打开剩下的格子
+        for i in 0..8 {
+            for j in 0..8 {
+                if video.minesweeper_board.board[i][j] != -1 && video.minesweeper_board.game_board[i][j] == 10 {
+                    video.step("lc", (i * 16 + 1, j * 16 + 1)).unwrap();
+                    video.step("lr", (i * 16 + 1, j * 16 + 1)).unwrap();
+                }
+            }
+        }
+        assert_eq!(video.game_board_state, GameBoardState::Win);
+        video.generate_evf_v0_raw_data();
+        let file_name = std::env::temp_dir().join(format!("ms_toollib_mode_{}", rule.to_mode()));
+        let file_name = file_name.to_str().unwrap();
+        let _ = std::fs::remove_file(format!("{}.evf", file_name));
+        video.save_to_evf_file(file_name);
+        let mut video = EvfVideo::new(&format!("{}.evf", file_name));
+        video.parse_video().unwrap();
+        std::fs::remove_file(format!("{}.evf", file_name)).unwrap();
+        assert_eq!(video.data.mode, rule.to_mode());
+        assert_eq!(FirstClickRule::from_mode(video.data.mode), Some(rule));
+    }
+}
+
+#[test]
+fn guess_rule_works() {
+    // 测试猜雷的规则：左上角两格是二选一，必要的猜雷在弱无猜下不踩雷，在强无猜下必然踩雷
+    let board = vec![vec![-1, 1, 0], vec![1, 1, 0]];
+    let play = |rule: GuessRule, cell: (usize, usize)| {
+        let mut my_board = MinesweeperBoard::new(board.clone());
+        my_board.set_guess_rule(rule).unwrap();
+        my_board.step_flow(vec![("lc", (0, 2)), ("lr", (0, 2))]).unwrap();
+        my_board.step_flow(vec![("lc", cell), ("lr", cell)]).unwrap();
+        assert!(my_board.set_guess_rule(GuessRule::Standard).is_err());
+        my_board
+    };
+    let my_board = play(GuessRule::WeakNoGuess, (0, 0));
+    assert_eq!(my_board.board, vec![vec![1, 1, 0], vec![-1, 1, 0]]);
+    assert_eq!(my_board.game_board_state, GameBoardState::Win);
+    // 移雷后，原来标错的旗子变成标对，计入ce
+    let mut my_board = MinesweeperBoard::new(board.clone());
+    my_board.set_guess_rule(GuessRule::WeakNoGuess).unwrap();
+    my_board.step_flow(vec![("lc", (0, 2)), ("lr", (0, 2))]).unwrap();
+    my_board.step_flow(vec![("rc", (1, 0)), ("rr", (1, 0))]).unwrap();
+    assert_eq!(my_board.ce, 1);
+    my_board.step_flow(vec![("lc", (0, 0)), ("lr", (0, 0))]).unwrap();
+    assert_eq!(my_board.board[1][0], -1);
+    assert_eq!(my_board.ce, 3);
+    let my_board = play(GuessRule::StrongGuessable, (0, 0));
+    assert_eq!(my_board.game_board_state, GameBoardState::Win);
+    let my_board = play(GuessRule::QuasiNoGuess, (0, 0));
+    assert_eq!(my_board.game_board_state, GameBoardState::Loss);
+    let my_board = play(GuessRule::StrongNoGuess, (1, 0));
+    assert_eq!(my_board.board[1][0], -1);
+    assert_eq!(my_board.game_board_state, GameBoardState::Loss);
+    let my_board = play(GuessRule::Standard, (1, 0));
+    assert_eq!(my_board.game_board_state, GameBoardState::Win);
+    // 录像记录猜雷的规则
+    let mut video = BaseVideo::new_before_game(board, 16);
+    video.set_first_click_rule(FirstClickRule::Xp).unwrap();
+    video.set_guess_rule(GuessRule::WeakNoGuess).unwrap();
+    assert_eq!(video.mode, 7);
+    assert_eq!(GuessRule::from_mode(video.mode), Some(GuessRule::WeakNoGuess));
+    video.step("lc", (8, 40)).unwrap();
+    video.step("lr", (8, 40)).unwrap();
+    video.step("lc", (8, 8)).unwrap();
+    video.step("lr", (8, 8)).unwrap();
+    assert_eq!(video.game_board_state, GameBoardState::Win);
+    assert_eq!(video.board, vec![vec![1, 1, 0], vec![-1, 1, 0]]);
+}
+
+#[test]
+fn minesweeper_board_undo_works() {
+    // 测试撤销、重做与快照：撤销后与步进前完全相同，快照编码后能原样恢复
+    let board = vec![
+        vec![0, 1, -1, 1],
+        vec![0, 1, 1, 1],
+        vec![1, 1, 0, 0],
+        vec![-1, 1, 0, 0],
+    ];
+    let mut my_board = MinesweeperBoard::new(board);
+    my_board.set_history_limit(100);
+    assert!(!my_board.undo());
+    let start = my_board.snapshot();
+    my_board.step_flow(vec![("lc", (0, 0)), ("lr", (0, 0))]).unwrap();
+    let opened = my_board.snapshot();
+    my_board.step_flow(vec![("rc", (0, 2)), ("rr", (0, 2))]).unwrap();
+    assert_eq!(my_board.flag, 1);
+    assert_eq!(my_board.ce, 2);
+    let flaged = my_board.snapshot();
+    assert!(my_board.undo() && my_board.undo());
+    assert_eq!(my_board.snapshot(), opened);
+    assert!(my_board.redo() && my_board.redo());
+    assert!(!my_board.redo());
+    assert_eq!(my_board.snapshot(), flaged);
+    // 撤销后步进，不能再重做
+    assert!(my_board.undo());
+    my_board.step("rr", (0, 2)).unwrap();
+    assert!(!my_board.redo());
+    while my_board.undo() {}
+    assert_eq!(my_board.snapshot(), start);
+    // 出错的步不改变状态：状态机不能处理12
+    my_board.step_flow(vec![("lc", (0, 0)), ("lr", (0, 0))]).unwrap();
+    my_board.game_board[3][3] = 12;
+    let before = my_board.snapshot();
+    assert!(my_board.step("rc", (3, 3)).is_err());
+    assert_eq!(my_board.snapshot(), before);
+    assert_eq!(my_board.right, 0);
+    my_board.game_board[3][3] = 10;
+    // 保存下到一半的局，在另一个状态机中接着下
+    let data = flaged.to_bytes();
+    let loaded = MinesweeperBoardSnapshot::from_bytes(&data).unwrap();
+    assert_eq!(loaded, flaged);
+    assert!(MinesweeperBoardSnapshot::from_bytes(&data[..data.len() - 1]).is_err());
+    // 篡改过的快照：局面中不能有10，游戏局面中不能有9，指针不能越界
+    for (offset, value) in [(5, 10), (5 + 16, 9), (data.len() - 25, 4)] {
+        let mut bad_data = data.clone();
+        bad_data[offset] = value;
+        assert_eq!(MinesweeperBoardSnapshot::from_bytes(&bad_data), Err(ErrSnapshot::InvalidValue));
+    }
+    let mut my_board = MinesweeperBoard::new(vec![vec![0; 2]; 2]);
+    my_board.restore(&loaded);
+    my_board.step_flow(vec![("lc", (2, 3)), ("lr", (2, 3))]).unwrap();
+    my_board.step_flow(vec![("lc", (0, 3)), ("lr", (0, 3))]).unwrap();
+    assert_eq!(my_board.game_board_state, GameBoardState::Win);
+    assert_eq!(my_board.game_board_grid().to_i32_board(), my_board.game_board);
+    assert_eq!(my_board.board_grid().to_i32_board(), my_board.board);
+}
+
 #[test]
 fn BaseVideo_works() {
     let board = vec![
@@ -328,3 +528,63 @@ fn BaseVideo_works_2() {
 
 
 
+
+#[test]
+fn judge_speed_works() {
+    // 测试判雷依据与用时的统计。打开左下角后，局面上是1-2-1
+    let board = vec![
+        vec![1, -1, 2, -1, 1],
+        vec![1, 1, 2, 1, 1],
+        vec![0, 0, 0, 0, 0],
+    ];
+    let mut video = BaseVideo::new_before_game(board, 16);
+    video.step("lc", (32, 0)).unwrap();
+    video.step("lr", (32, 0)).unwrap();
+    thread::sleep(Duration::from_millis(50));
+    video.step("lc", (0, 0)).unwrap();
+    video.step("lr", (0, 0)).unwrap();
+    thread::sleep(Duration::from_millis(50));
+    video.step("rc", (0, 16)).unwrap();
+    video.step("rr", (0, 16)).unwrap();
+    thread::sleep(Duration::from_millis(50));
+    video.step("lc", (0, 32)).unwrap();
+    video.step("lr", (0, 32)).unwrap();
+    video.step("lc", (0, 64)).unwrap();
+    video.step("lr", (0, 64)).unwrap();
+    let events = video.cal_judge_events();
+    assert_eq!(events.len(), 4);
+    assert_eq!(events[0].category, JudgeCategory::Pattern("1-2".to_string()));
+    assert_eq!(events[1].category, JudgeCategory::Pattern("1-2".to_string()));
+    assert_eq!(events[2].category, JudgeCategory::Pattern("1-1 wall".to_string()));
+    assert!(events[0].reaction_time >= 0.04);
+    let mut stats = JudgeSpeedStats::new();
+    stats.add_video(&mut video);
+    stats.add_video(&mut video);
+    assert_eq!(stats.count("1-2"), 4);
+    assert_eq!(stats.count("enum_not_mine"), 0);
+    assert!(stats.mean_time("1-1 wall").is_some());
+    println!("{:?}", stats.report());
+    video.analyse_for_features(vec!["judge_speed"]);
+    assert!(video.video_action_state_recorder[events[0].event_id]
+        .comments
+        .contains("1-2"));
+}
+
+#[test]
+fn endgame_guess_works() {
+    // 测试残局猜雷的分析。打开左下角后，上面一行是二选一
+    let board = vec![vec![-1, 1], vec![1, 1], vec![0, 0]];
+    let mut video = BaseVideo::new_before_game(board, 16);
+    video.step("lc", (32, 0)).unwrap();
+    video.step("lr", (32, 0)).unwrap();
+    video.step("lc", (0, 16)).unwrap();
+    video.step("lr", (0, 16)).unwrap();
+    video.analyse_for_features(vec!["endgame_guess"]);
+    let comments: Vec<_> = video
+        .video_action_state_recorder
+        .iter()
+        .map(|e| e.comments.clone())
+        .collect();
+    println!("{:?}", comments);
+    assert!(comments[3].contains("feature: 残局最优的猜雷(胜率0.500)"));
+}
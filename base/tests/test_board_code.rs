@@ -0,0 +1,126 @@
+use ms_toollib::{
+    decode_board_code, decode_mbf, encode_board_code, encode_mbf, laymine, laymine_mbf_boards,
+    laymine_mbf_boards_seed, Board, ErrBoardCode, ErrMbf, ErrParseBoard, GameBoard,
+};
+
+// 测试局面码、mbf文件的编码与解码
+
+#[test]
+fn board_code_works() {
+    let board = laymine(16, 30, 99, 3, 20);
+    let code = encode_board_code(&board, Some((3, 20))).unwrap();
+    println!("{}", code);
+    let (board_2, start) = decode_board_code(&code).unwrap();
+    assert_eq!(board, board_2);
+    assert_eq!(start, Some((3, 20)));
+
+    let board = laymine(9, 9, 10, 0, 0);
+    let code = Board::new(board.clone()).to_code(None).unwrap();
+    let (board_2, start) = Board::from_code(&code).unwrap();
+    assert_eq!(board, board_2.board);
+    assert_eq!(start, None);
+}
+
+#[test]
+fn board_code_errors_works() {
+    let board = laymine(8, 8, 10, 0, 0);
+    let code = encode_board_code(&board, None).unwrap();
+    // 改动一个字符，校验和应当不通过
+    let mut broken = code.clone().into_bytes();
+    broken[6] = if broken[6] == b'A' { b'B' } else { b'A' };
+    assert_eq!(
+        decode_board_code(&String::from_utf8(broken).unwrap()),
+        Err(ErrBoardCode::ChecksumMismatch)
+    );
+    assert_eq!(
+        decode_board_code("AB*C"),
+        Err(ErrBoardCode::InvalidCharacter)
+    );
+    assert_eq!(decode_board_code("AQA"), Err(ErrBoardCode::CodeIsTooShort));
+    assert_eq!(
+        encode_board_code(&vec![vec![0; 256]; 2], None),
+        Err(ErrBoardCode::InvalidBoardSize)
+    );
+}
+
+#[test]
+fn mbf_works() {
+    // 8*8、1雷，雷在第0行第2列
+    let raw_data = vec![8, 8, 0, 1, 2, 0];
+    let board = decode_mbf(&raw_data).unwrap();
+    assert_eq!(board[0][2], -1);
+    assert_eq!(board[1][1], 1);
+    assert_eq!(encode_mbf(&board).unwrap(), raw_data);
+    assert_eq!(decode_mbf(&[8, 8, 0, 2, 2, 0]), Err(ErrMbf::FileIsTooShort));
+    assert_eq!(
+        decode_mbf(&[8, 8, 0, 1, 8, 0]),
+        Err(ErrMbf::InvalidMinePosition)
+    );
+
+    let board = Board::new(laymine(16, 30, 99, 0, 0));
+    let file_name = std::env::temp_dir().join("ms_toollib_mbf_works");
+    let file_name = board.save_to_mbf_file(file_name.to_str().unwrap()).unwrap();
+    let board_2 = Board::from_mbf_file(&file_name).unwrap();
+    std::fs::remove_file(&file_name).unwrap();
+    assert_eq!(board.board, board_2.board);
+    let file_name = std::env::temp_dir()
+        .join("ms_toollib_no_such_dir")
+        .join("board");
+    assert_eq!(
+        board.save_to_mbf_file(file_name.to_str().unwrap()),
+        Err(ErrMbf::CanNotWriteFile)
+    );
+}
+
+#[test]
+fn laymine_mbf_boards_works() {
+    let boards = laymine_mbf_boards_seed(8, 8, 10, 0, 0, 10, (10, 20), true, 2022);
+    assert_eq!(boards.len(), 10);
+    assert_eq!(
+        boards,
+        laymine_mbf_boards_seed(8, 8, 10, 0, 0, 10, (10, 20), true, 2022)
+    );
+    for raw_data in boards {
+        let mut board = Board::from_mbf(&raw_data).unwrap();
+        assert!(board.get_bbbv() >= 10 && board.get_bbbv() <= 20);
+    }
+    assert_eq!(
+        laymine_mbf_boards(8, 8, 10, 0, 0, 3, (10, 20), true).len(),
+        3
+    );
+}
+
+#[test]
+fn board_notation_works() {
+    // 测试局面的文本表示
+    let board = Board::new(laymine(16, 30, 99, 0, 0));
+    let board_2: Board = board.to_string().parse().unwrap();
+    assert_eq!(board.board, board_2.board);
+    let board: Board = "
+        * . . .
+        . . . .
+        . . . *
+    "
+    .parse()
+    .unwrap();
+    println!("{}", board);
+    assert_eq!(board.board[1], vec![1, 1, 1, 1]);
+    assert_eq!(
+        "*0\n00".parse::<Board>().err(),
+        Some(ErrParseBoard::InvalidNumber(0, 1))
+    );
+    assert_eq!(
+        "*1\n1".parse::<Board>().err(),
+        Some(ErrParseBoard::InconsistentRowLength)
+    );
+
+    let text = "mines: 10\n.F1oS\n!*X12";
+    let game_board: GameBoard = text.parse().unwrap();
+    assert_eq!(game_board.game_board[0], vec![10, 11, 1, 18, 12]);
+    assert_eq!(game_board.game_board[1], vec![15, 16, 14, 1, 2]);
+    assert_eq!(game_board.to_string(), text);
+    assert_eq!(
+        ".F?".parse::<GameBoard>().err(),
+        Some(ErrParseBoard::InvalidCharacter('?'))
+    );
+}
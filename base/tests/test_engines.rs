@@ -2,7 +2,15 @@ use ms_toollib::{
     cal_is_op_possibility_cells, cal_possibility, cal_possibility_onboard, is_guess_while_needless,
     is_solvable, mark_board, solve_direct, solve_enumerate, is_able_to_solve,
 };
-use ms_toollib::{cal_table_minenum_recursion, combine, refresh_matrix, refresh_matrixs};
+use ms_toollib::{
+    cal_possibility_budget, cal_possibility_exact, cal_table_minenum_dp, cal_table_minenum_recursion, cal_table_minenum_recursion_generic, combine, laymine_seed,
+    laymine_op_seed, neighbours, refresh_board, refresh_matrix, refresh_matrixs, solve_explain,
+    suggest_move, cal_number_distribution_onboard, cal_op_possibility_onboard,
+    cal_information_gain_onboard, solve_endgame, solve_global, is_solvable_global, mark_board_global,
+    validate_game_board, cal_possibility_exec, cal_difficulty, laymine_filtered_seed, Board, LaymineFilter, CancelToken, ExecOptions, Contradiction, ConstraintSystem, generate_puzzle_seed, PuzzleTier, rearrange_mines, GameBoard, BigRational, ErrEndgame, BigUint, MoveReason, Proof, Relation,
+};
+
+use num_traits::ToPrimitive;
 
 // 测试各种引擎类的函数
 
@@ -254,3 +262,656 @@ let mut game_board = vec![
 
 
 
+
+#[test]
+fn cal_table_minenum_dp_works() {
+    // 测试动态规划引擎，与递归枚举引擎的结果对比
+    let game_board = vec![
+        vec![0, 0, 1, 10, 10, 10, 10, 10],
+        vec![0, 0, 2, 10, 10, 10, 10, 10],
+        vec![1, 1, 3, 11, 10, 10, 10, 10],
+        vec![10, 10, 4, 10, 10, 10, 10, 10],
+        vec![10, 10, 10, 10, 10, 10, 10, 10],
+        vec![10, 10, 10, 10, 10, 10, 10, 10],
+        vec![10, 10, 10, 10, 10, 10, 10, 10],
+        vec![10, 10, 10, 10, 10, 10, 10, 10],
+    ];
+    let (matrix_a, matrix_x, matrix_b) = refresh_matrix(&game_board);
+    let (matrix_a_s, matrix_x_s, combination_relationship) = combine(&matrix_a, &matrix_x);
+    let (table, table_cell) = cal_table_minenum_recursion(
+        &matrix_a_s,
+        &matrix_x_s,
+        &matrix_b,
+        &combination_relationship,
+    )
+    .unwrap();
+    let (mine_num, table_dp, table_cell_dp) =
+        cal_table_minenum_dp::<f64>(&matrix_a_s, &matrix_b, &combination_relationship).unwrap();
+    assert_eq!(table[0], mine_num);
+    assert_eq!(table[1].iter().map(|&x| x as f64).collect::<Vec<_>>(), table_dp);
+    for i in 0..table_cell.len() {
+        assert_eq!(
+            table_cell[i].iter().map(|&x| x as f64).collect::<Vec<_>>(),
+            table_cell_dp[i]
+        );
+    }
+    // 精确的情况数
+    let (mine_num, table, table_cell) = cal_table_minenum_recursion_generic::<BigUint>(
+        &matrix_a_s,
+        &matrix_x_s,
+        &matrix_b,
+        &combination_relationship,
+    )
+    .unwrap();
+    let (mine_num_dp, table_dp, table_cell_dp) =
+        cal_table_minenum_dp::<BigUint>(&matrix_a_s, &matrix_b, &combination_relationship)
+            .unwrap();
+    assert_eq!(mine_num, mine_num_dp);
+    assert_eq!(table, table_dp);
+    assert_eq!(table_cell, table_cell_dp);
+}
+
+#[test]
+fn cal_possibility_long_segment_works() {
+    // 测试概率计算引擎处理几百格长的段：左半边全部打开，右半边斜着隔三打开一条，雷都没有标
+    let board = laymine_seed(30, 30, 180, 0, 0, 2022);
+    let mut game_board = vec![vec![10; 30]; 30];
+    for i in 0..30 {
+        for j in 0..30 {
+            if board[i][j] >= 0 && (j < 15 || (i + j) % 4 != 0) {
+                game_board[i][j] = board[i][j];
+            }
+        }
+    }
+    // 打开的0周围必须都打开，否则不是合法的局面
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for i in 0..30 {
+            for j in 0..30 {
+                if game_board[i][j] == 0 {
+                    for (m, n) in neighbours(30, 30, i, j) {
+                        if game_board[m][n] == 10 {
+                            game_board[m][n] = board[m][n];
+                            changed = true;
+                        }
+                    }
+                }
+            }
+        }
+    }
+    let (_, matrix_x_s, _, _, _) = refresh_matrixs(&game_board);
+    let max_len = matrix_x_s.iter().map(|x| x.len()).max().unwrap();
+    println!("最长的段的长度：{:?}", max_len);
+    let (p, p_unknow, _, exceed_len) = cal_possibility(&game_board, 180.0).unwrap();
+    assert_eq!(exceed_len, max_len);
+    // 期望的雷数等于总雷数
+    let unknow_num = game_board.iter().flatten().filter(|&&x| x == 10).count() - p.len();
+    let mine_num_expected = p.iter().map(|x| x.1).sum::<f64>() + p_unknow * unknow_num as f64;
+    assert!((mine_num_expected - 180.0).abs() < 1e-6);
+    // 打开区域里的雷必然是雷
+    for ((x, y), poss) in &p {
+        if *y < 14 {
+            assert_eq!(board[*x][*y] == -1, (poss - 1.0).abs() < 1e-9);
+        }
+    }
+    // 有预算时，超出预算的段视为内部未知区域
+    let (p_budget, _, _, _) = cal_possibility_budget(&game_board, 180.0, Some(10)).unwrap();
+    assert!(p_budget.len() < p.len());
+}
+
+#[test]
+fn cal_possibility_exact_works() {
+    // 测试精确的概率计算引擎：与对数表示的结果一致，且期望的雷数精确等于总雷数
+    let game_board = vec![
+        vec![10, 10, 1, 1, 10, 1, 0, 0],
+        vec![10, 10, 1, 10, 10, 3, 2, 1],
+        vec![10, 10, 10, 10, 10, 10, 10, 10],
+        vec![10, 10, 10, 10, 10, 10, 10, 10],
+        vec![10, 10, 10, 10, 10, 10, 10, 10],
+        vec![10, 10, 10, 10, 2, 10, 10, 10],
+        vec![10, 10, 10, 10, 10, 10, 10, 10],
+        vec![10, 10, 10, 10, 10, 10, 10, 10],
+    ];
+    let (p, p_unknow, range, _) = cal_possibility(&game_board, 10.0).unwrap();
+    let (p_exact, p_unknow_exact, range_exact, _) =
+        cal_possibility_exact(&game_board, 10.0).unwrap();
+    assert_eq!(range, range_exact);
+    for (a, b) in p.iter().zip(p_exact.iter()) {
+        assert_eq!(a.0, b.0);
+        assert!((a.1 - b.1.to_f64().unwrap()).abs() < 1e-12);
+    }
+    assert!((p_unknow - p_unknow_exact.to_f64().unwrap()).abs() < 1e-12);
+    let unknow_num = game_board.iter().flatten().filter(|&&x| x == 10).count() - p.len();
+    let mut mine_num_expected =
+        p_unknow_exact * BigRational::from_integer((unknow_num as i64).into());
+    for (_, poss) in p_exact {
+        mine_num_expected += poss;
+    }
+    assert_eq!(mine_num_expected, BigRational::from_integer(10.into()));
+    // 1周围只有两格，第四格是内部未知区域，共2个雷
+    let game_board = vec![vec![10, 1, 10, 10]];
+    let (p_exact, p_unknow_exact, _, _) = cal_possibility_exact(&game_board, 2.0).unwrap();
+    assert_eq!(p_exact[0].1, BigRational::new(1.into(), 2.into()));
+    assert_eq!(p_unknow_exact, BigRational::from_integer(1.into()));
+}
+
+#[test]
+fn solve_explain_works() {
+    // 测试带解释的判雷引擎：第一轮用双集合判出两个雷，第二轮用单集合判出两个非雷
+    let game_board = vec![
+        vec![10, 10, 10, 10],
+        vec![1, 2, 2, 1],
+        vec![0, 0, 0, 0],
+    ];
+    let ans = solve_explain(&game_board);
+    assert_eq!(ans.len(), 4);
+    let d = ans.iter().find(|d| d.cell == (0, 2)).unwrap();
+    assert!(d.is_mine);
+    assert_eq!(d.round, 0);
+    match &d.proof {
+        Proof::Minus { a, b, relation } => {
+            assert_eq!(a.number, (1, 1));
+            assert_eq!(b.number, (1, 0));
+            assert_eq!(*relation, Relation::Contains);
+        }
+        _ => panic!("应当用双集合判出"),
+    }
+    let d = ans.iter().find(|d| d.cell == (0, 0)).unwrap();
+    assert!(!d.is_mine);
+    assert_eq!(d.round, 1);
+    assert!(matches!(d.proof, Proof::Direct(_)));
+    // 随机的局面上，判出的结果都正确；枚举给出的矛盾至少涉及两个数字
+    let mut enumerate_num = 0;
+    for seed in 0..50 {
+        let board = laymine_op_seed(16, 30, 99, 8, 15, seed);
+        let mut game_board = vec![vec![10; 30]; 16];
+        refresh_board(&board, &mut game_board, vec![(8, 15)]);
+        for d in solve_explain(&game_board) {
+            assert_eq!(board[d.cell.0][d.cell.1] == -1, d.is_mine);
+            if let Proof::Enumerate(constraints) = &d.proof {
+                assert!(constraints.len() >= 2);
+                enumerate_num += 1;
+            }
+        }
+    }
+    println!("用枚举判出的格子数：{:?}", enumerate_num);
+    assert!(enumerate_num > 0);
+}
+
+#[test]
+fn suggest_move_works() {
+    // 测试提示引擎：能判出非雷时只推荐非雷
+    let game_board = vec![
+        vec![10, 10, 10, 10],
+        vec![1, 2, 2, 1],
+        vec![0, 0, 0, 0],
+    ];
+    let ans = suggest_move(&game_board, 2.0).unwrap();
+    assert_eq!(
+        ans.iter().map(|m| m.cell).collect::<Vec<_>>(),
+        vec![(0, 0), (0, 3)]
+    );
+    assert!(ans.iter().all(|m| m.reason == MoveReason::Safe));
+    // 3x3、2个雷，角上的格子开空的概率最大
+    let ans = suggest_move(&vec![vec![10; 3]; 3], 2.0).unwrap();
+    assert_eq!(ans.len(), 9);
+    assert!([(0, 0), (0, 2), (2, 0), (2, 2)].contains(&ans[0].cell));
+    match ans[0].reason {
+        MoveReason::Guess {
+            mine_probability,
+            zero_probability,
+            expected_solvable,
+            ..
+        } => {
+            assert!((mine_probability - 2.0 / 9.0).abs() < 1e-9);
+            assert!((zero_probability - 5.0 / 14.0).abs() < 1e-9);
+            assert!((expected_solvable - 15.0 / 14.0).abs() < 1e-9);
+        }
+        _ => panic!("应当是猜雷"),
+    }
+    assert_eq!(ans[8].cell, (1, 1));
+    // 随机的局面上，一直打开推荐的非雷，直到需要猜雷
+    for seed in 0..10 {
+        let board = laymine_op_seed(16, 30, 99, 8, 15, seed);
+        let mut game_board = vec![vec![10; 30]; 16];
+        refresh_board(&board, &mut game_board, vec![(8, 15)]);
+        loop {
+            let ans = suggest_move(&game_board, 99.0).unwrap();
+            if ans.is_empty() || ans[0].reason != MoveReason::Safe {
+                println!("{:?}: {:?}", seed, ans.first());
+                break;
+            }
+            let cells: Vec<_> = ans.iter().map(|m| m.cell).collect();
+            assert!(cells.iter().all(|&(x, y)| board[x][y] != -1));
+            refresh_board(&board, &mut game_board, cells);
+        }
+    }
+}
+
+#[test]
+fn cal_number_distribution_onboard_works() {
+    // 测试数字分布、开空概率与信息增益。3x3、2个雷，中间的格子不是雷时一定是2
+    let game_board = vec![vec![10; 3]; 3];
+    let d = cal_number_distribution_onboard(&game_board, 2.0).unwrap();
+    assert!((d[1][1][2] - 7.0 / 9.0).abs() < 1e-9);
+    assert!((d[0][0].iter().sum::<f64>() - 7.0 / 9.0).abs() < 1e-9);
+    assert!((d[0][0][0] - 5.0 / 18.0).abs() < 1e-9);
+    assert!((d[0][0][1] - 7.0 / 9.0 * 15.0 / 28.0).abs() < 1e-9);
+    let gain = cal_information_gain_onboard(&game_board, 2.0).unwrap();
+    assert!(gain[1][1].abs() < 1e-9);
+    let entropy: f64 = [10.0, 15.0, 3.0]
+        .iter()
+        .map(|x: &f64| -x / 28.0 * (x / 28.0).log2())
+        .sum();
+    assert!((gain[0][0] - entropy).abs() < 1e-9);
+    // 开空概率与cal_is_op_possibility_cells一致
+    let game_board = vec![
+        vec![10, 10, 1, 1, 10, 1, 0, 0],
+        vec![10, 10, 1, 10, 10, 3, 2, 1],
+        vec![10, 10, 10, 10, 10, 10, 10, 10],
+        vec![10, 10, 10, 10, 10, 10, 10, 10],
+        vec![10, 10, 10, 10, 10, 10, 10, 10],
+        vec![10, 10, 10, 10, 2, 10, 10, 10],
+        vec![10, 10, 10, 10, 10, 10, 10, 10],
+        vec![10, 10, 10, 10, 10, 10, 10, 10],
+    ];
+    let op = cal_op_possibility_onboard(&game_board, 20.0).unwrap();
+    let cells = vec![[0, 0], [2, 2], [3, 6], [7, 7], [6, 4]];
+    let ans = cal_is_op_possibility_cells(&game_board, 20.0, &cells);
+    for (cell, q) in cells.iter().zip(ans) {
+        assert!((op[cell[0]][cell[1]] - q).abs() < 1e-9);
+    }
+    assert_eq!(op[0][2], 0.0);
+    let d = cal_number_distribution_onboard(&game_board, 20.0).unwrap();
+    let (p, _) = cal_possibility_onboard(&game_board, 20.0).unwrap();
+    for i in 0..8 {
+        for j in 0..8 {
+            if game_board[i][j] == 10 {
+                assert!((d[i][j].iter().sum::<f64>() + p[i][j] - 1.0).abs() < 1e-9);
+            }
+        }
+    }
+}
+
+#[test]
+fn solve_endgame_works() {
+    // 测试残局求解器：二选一
+    let (total, moves) = solve_endgame(&[vec![10, 10], vec![1, 1]], 1, 100).unwrap();
+    assert_eq!(total, 2);
+    assert!(moves.iter().all(|m| m.wins == 1 && m.win_probability == 0.5));
+    // 2x2、1个雷，打开的数字总是1，没有信息：3/4 * 2/3 * 1/2
+    let (total, moves) = solve_endgame(&vec![vec![10; 2]; 2], 1, 100).unwrap();
+    assert_eq!(total, 4);
+    assert_eq!(moves.len(), 4);
+    assert!(moves.iter().all(|m| m.wins == 1));
+    // 必然是雷的格子不列出；标过的雷计入雷数
+    let (total, moves) = solve_endgame(&[vec![10, 10, 10], vec![1, 2, 1]], 2, 100).unwrap();
+    assert_eq!(total, 1);
+    assert_eq!(moves.len(), 1);
+    assert_eq!(moves[0].cell, (0, 1));
+    assert_eq!(moves[0].win_probability, 1.0);
+    let (_, moves) = solve_endgame(&[vec![11, 10, 10], vec![1, 2, 1]], 2, 100).unwrap();
+    assert_eq!(moves[0].cell, (0, 1));
+    assert_eq!(
+        solve_endgame(&vec![vec![10; 3]; 3], 2, 10),
+        Err(ErrEndgame::TooManyConfigurations)
+    );
+    assert_eq!(
+        solve_endgame(&[vec![10, 10], vec![1, 1]], 2, 100),
+        Err(ErrEndgame::Contradictory)
+    );
+    // 胜率不超过不是雷的概率；开空会连锁打开，4x4、1个雷时点角上必胜
+    let (total, moves) = solve_endgame(&vec![vec![10; 4]; 4], 1, 100).unwrap();
+    assert_eq!(total, 16);
+    assert_eq!(moves[0].wins, 15);
+    let game_board = vec![vec![10; 4]; 4];
+    let (total, moves) = solve_endgame(&game_board, 3, 1000).unwrap();
+    assert_eq!(total, 560);
+    let (p, _) = cal_possibility_onboard(&game_board, 3.0).unwrap();
+    for m in &moves {
+        assert!(m.win_probability <= 1.0 - p[m.cell.0][m.cell.1] + 1e-9);
+    }
+    assert!(moves.windows(2).all(|w| w[0].wins >= w[1].wins));
+    println!("{:?}", moves[0]);
+}
+
+#[test]
+fn solve_global_works() {
+    // 测试结合雷数的判雷：局部只知道(0, 1)、(1, 1)中有1个雷，或(1, 0)、(1, 2)都是雷；下面两行是内部
+    let mut game_board = vec![vec![10; 3]; 4];
+    game_board[0] = vec![1, 10, 1];
+    let (a_s, x_s, b_s, _, _) = refresh_matrixs(&game_board);
+    let ans = solve_enumerate(&a_s, &x_s, &b_s);
+    assert!(ans.0.is_empty() && ans.1.is_empty());
+    // 只有1个雷时，内部都不是雷
+    let (not_mine, is_mine) = solve_global(&a_s, &x_s, &b_s, &game_board, 1);
+    assert!(is_mine.is_empty());
+    assert_eq!(not_mine.len(), 8);
+    assert!(not_mine.contains(&(1, 0)) && not_mine.contains(&(3, 2)));
+    // 8个雷时，内部6格全是雷，边缘必须有2个雷
+    let (mut not_mine, is_mine) = solve_global(&a_s, &x_s, &b_s, &game_board, 8);
+    not_mine.sort_unstable();
+    assert_eq!(not_mine, vec![(0, 1), (1, 1)]);
+    assert_eq!(is_mine.len(), 8);
+    let ans = solve_global(&a_s, &x_s, &b_s, &game_board, 3);
+    assert!(ans.0.is_empty() && ans.1.is_empty());
+    let mut board = game_board.clone();
+    mark_board_global(&mut board, 1);
+    assert_eq!(board[1][0], 12);
+    assert_eq!(board.iter().flatten().filter(|&&x| x == 12).count(), 8);
+    let mut g = GameBoard::new(1);
+    g.set_game_board(&game_board);
+    assert!(g.get_enum_not_mine().is_empty());
+    assert!(g.get_global_is_mine().is_empty());
+    assert_eq!(g.get_global_not_mine().len(), 8);
+
+    // 最后要数雷才能扫完
+    let board = vec![
+        vec![0, 1, -1, 2, -1],
+        vec![0, 1, 2, 3, 2],
+        vec![0, 0, 1, -1, 1],
+        vec![0, 0, 1, 1, 1],
+    ];
+    assert!(!is_solvable(&board, 0, 0));
+    assert!(is_solvable_global(&board, 0, 0));
+}
+
+#[test]
+fn validate_game_board_works() {
+    // 测试局面的自洽性检查
+    assert!(validate_game_board(&[vec![10, 10], vec![1, 1]], 1).is_empty());
+    assert_eq!(
+        validate_game_board(&[vec![9, 10], vec![1, 1]], 1),
+        vec![Contradiction::InvalidValue {
+            cell: (0, 0),
+            value: 9
+        }]
+    );
+    assert_eq!(
+        validate_game_board(&[vec![1, 11], vec![11, 10]], 2),
+        vec![Contradiction::OverFlagged {
+            cell: (0, 0),
+            number: 1,
+            flags: 2
+        }]
+    );
+    assert_eq!(
+        validate_game_board(&[vec![3, 10], vec![0, 0]], 1),
+        vec![Contradiction::NumberTooLarge {
+            cell: (0, 0),
+            number: 3,
+            covered: 1
+        }]
+    );
+    // 两个数字各自都没有矛盾，合在一起矛盾
+    assert_eq!(
+        validate_game_board(&[vec![10, 10], vec![1, 2]], 1),
+        vec![Contradiction::Unsatisfiable(vec![(0, 0), (0, 1)])]
+    );
+    assert_eq!(
+        validate_game_board(&[vec![10, 10], vec![1, 1]], 3),
+        vec![Contradiction::MineCountOutOfRange {
+            mine_num: 3,
+            min: 1,
+            max: 1
+        }]
+    );
+    // 数字证明(0, 1)不是雷
+    let game_board = vec![vec![10, 11, 10], vec![1, 2, 1]];
+    assert_eq!(
+        validate_game_board(&game_board, 2),
+        vec![Contradiction::WrongFlag((0, 1))]
+    );
+    // 概率计算引擎对矛盾的局面报错
+    assert_eq!(cal_possibility(&vec![vec![3, 10], vec![0, 0]], 2.0), Err(1));
+    let game_board = vec![vec![11, 11, 11], vec![2, 3, 2], vec![0, 0, 0]];
+    assert!(validate_game_board(&game_board, 3).is_empty());
+    assert_eq!(cal_possibility(&game_board, 2.0), Err(1));
+    // 失败后显示的雷（16）等标记与原来一样被忽略，概率计算引擎不报错
+    let game_board = vec![vec![16, 1, 0], vec![1, 1, 0], vec![10, 10, 10]];
+    assert!(matches!(validate_game_board(&game_board, 2)[0], Contradiction::InvalidValue { .. }));
+    assert!(cal_possibility(&game_board, 1.0).is_ok());
+    assert!(cal_possibility_onboard(&game_board, 1.0).is_ok());
+}
+
+// 把分段的矩阵展开成(格子, 雷数)的约束集合，用于比较段、行、列顺序不同的矩阵
+fn flatten_matrixs(
+    a_s: &[Vec<Vec<i32>>],
+    x_s: &[Vec<(usize, usize)>],
+    b_s: &[Vec<i32>],
+) -> Vec<(Vec<(usize, usize)>, i32)> {
+    let mut ans = vec![];
+    for i in 0..a_s.len() {
+        for j in 0..a_s[i].len() {
+            let mut cells: Vec<(usize, usize)> = (0..x_s[i].len())
+                .filter(|&k| a_s[i][j][k] == 1)
+                .map(|k| x_s[i][k])
+                .collect();
+            cells.sort();
+            ans.push((cells, b_s[i][j]));
+        }
+    }
+    ans.sort();
+    ans
+}
+
+#[test]
+fn constraint_system_works() {
+    // 测试增量的约束系统与refresh_matrixs的结果一致，以及撤销
+    let board = laymine_op_seed(16, 30, 99, 8, 15, 5);
+    let mut game_board = vec![vec![10; 30]; 16];
+    for i in 0..16 {
+        for j in 0..30 {
+            if board[i][j] >= 0 && (i * 7 + j * 3) % 5 < 2 {
+                game_board[i][j] = board[i][j];
+            }
+        }
+    }
+    let mut system = ConstraintSystem::from_game_board(&game_board);
+    assert_eq!(system.game_board(), game_board);
+    let (a_s, x_s, b_s, unknow_block, _) = refresh_matrixs(&game_board);
+    let (a_s_2, x_s_2, b_s_2, unknow_block_2, _) = system.matrixs();
+    assert_eq!(unknow_block, unknow_block_2);
+    assert_eq!(a_s.len(), a_s_2.len());
+    assert_eq!(
+        flatten_matrixs(&a_s, &x_s, &b_s),
+        flatten_matrixs(&a_s_2, &x_s_2, &b_s_2)
+    );
+    // 逐格打开剩下的非雷，每一步都与重建的结果一致，最后全部撤销
+    let checkpoint = system.checkpoint();
+    for i in 0..16 {
+        for j in 0..30 {
+            if board[i][j] >= 0 && game_board[i][j] == 10 && (i + j) % 4 == 0 {
+                system.reveal((i, j), board[i][j]);
+                let (a_s, x_s, b_s, unknow_block, _) = refresh_matrixs(&system.game_board());
+                let (a_s_2, x_s_2, b_s_2, unknow_block_2, _) = system.matrixs();
+                assert_eq!(unknow_block, unknow_block_2);
+                assert_eq!(
+                    flatten_matrixs(&a_s, &x_s, &b_s),
+                    flatten_matrixs(&a_s_2, &x_s_2, &b_s_2)
+                );
+            }
+        }
+    }
+    system.undo_to(checkpoint);
+    assert_eq!(system.game_board(), game_board);
+    let (a_s_2, x_s_2, b_s_2, unknow_block_2, _) = system.matrixs();
+    assert_eq!(unknow_block, unknow_block_2);
+    assert_eq!(
+        flatten_matrixs(&a_s, &x_s, &b_s),
+        flatten_matrixs(&a_s_2, &x_s_2, &b_s_2)
+    );
+    // 单集合判雷的结果与solve_direct一致
+    let mut system = ConstraintSystem::new(3, 3);
+    system.reveal((1, 0), 1);
+    system.reveal((1, 1), 1);
+    system.reveal((1, 2), 0);
+    for j in 0..3 {
+        system.reveal((2, j), 0);
+    }
+    let (mut not_mine, is_mine) = system.solve_direct();
+    not_mine.sort();
+    assert_eq!(is_mine, vec![(0, 0)]);
+    assert_eq!(not_mine, vec![(0, 1), (0, 2)]);
+    assert_eq!(system.get_flag_num(), 1);
+    assert_eq!(system.get((0, 0)), 11);
+    assert_eq!(system.get((0, 1)), 12);
+    system.undo_to(0);
+    assert_eq!(system.game_board(), vec![vec![10; 3]; 3]);
+}
+
+#[test]
+fn cal_possibility_exec_works() {
+    // 测试多线程、可取消的概率计算：结果与单线程相同，各段都报告了进度
+    let board = laymine_seed(30, 60, 500, 15, 30, 2024);
+    let mut game_board = vec![vec![10; 60]; 30];
+    refresh_board(&board, &mut game_board, vec![(15, 30)]);
+    for i in 0..30 {
+        for j in 0..60 {
+            if board[i][j] == 0 && (i + j) % 7 == 0 {
+                refresh_board(&board, &mut game_board, vec![(i, j)]);
+            }
+        }
+    }
+    let (p, p_unknow, range, max_len) = cal_possibility(&game_board, 500.0).unwrap();
+    let progress = std::sync::Arc::new(std::sync::Mutex::new(vec![]));
+    let progress_ = progress.clone();
+    let options = ExecOptions {
+        thread_num: 4,
+        progress: Some(std::sync::Arc::new(move |done, total| {
+            progress_.lock().unwrap().push((done, total))
+        })),
+        ..ExecOptions::default()
+    };
+    let (p_exec, p_unknow_exec, range_exec, max_len_exec) =
+        cal_possibility_exec(&game_board, 500.0, &options).unwrap();
+    assert_eq!(p_exec, p);
+    assert_eq!(p_unknow_exec, p_unknow);
+    assert_eq!((range_exec, max_len_exec), (range, max_len));
+    let progress = progress.lock().unwrap();
+    let total = progress.last().unwrap().1;
+    assert!(total > 1);
+    assert!(progress.contains(&(total, total)));
+    // 已取消时不计算
+    let cancel = CancelToken::new();
+    cancel.cancel();
+    let options = ExecOptions {
+        cancel: Some(cancel),
+        ..ExecOptions::single_thread()
+    };
+    assert_eq!(cal_possibility_exec(&game_board, 500.0, &options), Err(2));
+}
+
+#[test]
+fn cal_difficulty_works() {
+    // 测试局面难度评估
+    // 2*2的局面，要猜两次，先是1/3，再是1/2
+    let d = cal_difficulty(&vec![vec![-1, 1], vec![1, 1]], 1, 1);
+    assert_eq!(d.guesses, 2);
+    assert!((d.guess_survival - 1.0 / 3.0).abs() < 1e-9);
+    assert_eq!(d.minus_steps + d.enumerate_steps, 0);
+    assert!(d.score > 10.0);
+    // 单集合判雷就能扫完的局面
+    let board = vec![
+        vec![0, 0, 0, 0, 0],
+        vec![0, 0, 0, 0, 0],
+        vec![0, 0, 0, 1, 1],
+        vec![0, 0, 0, 1, -1],
+    ];
+    let d = cal_difficulty(&board, 0, 0);
+    assert_eq!(d.guesses, 0);
+    assert_eq!(d.guess_survival, 1.0);
+    assert_eq!(d.score, 0.0);
+    // 需要减法公式：1-2-1
+    let board = vec![
+        vec![-1, 2, -1, 1],
+        vec![1, 2, 1, 1],
+        vec![0, 0, 0, 0],
+    ];
+    let d = cal_difficulty(&board, 2, 0);
+    assert_eq!(d.guesses, 0);
+    assert!(d.minus_steps + d.enumerate_steps > 0);
+    assert!(d.score > 0.0);
+    // Board上的接口，按难度埋雷
+    let board = laymine_seed(16, 30, 99, 3, 20, 7);
+    assert_eq!(Board::new(board.clone()).get_difficulty(3, 20), cal_difficulty(&board, 3, 20));
+    let filter = LaymineFilter {
+        solvable: true,
+        difficulty: (3.0, 8.0),
+        ..LaymineFilter::default()
+    };
+    let (boards, _) = laymine_filtered_seed(9, 9, 10, 4, 4, &filter, 3, 100000, 7);
+    assert_eq!(boards.len(), 3);
+    for board in &boards {
+        let score = cal_difficulty(board, 4, 4).score;
+        assert!((3.0..=8.0).contains(&score));
+        assert!(is_solvable(board, 4, 4));
+    }
+}
+
+#[test]
+fn generate_puzzle_works() {
+    // 测试判雷练习题生成：题目中只有要求的方法能判出格子，答案就是所有能判出的格子，且答案都正确
+    for (tier, max_cells) in [
+        (PuzzleTier::Direct, 1),
+        (PuzzleTier::Minus, 1),
+        (PuzzleTier::Enumerate, 2),
+    ] {
+        let puzzle = generate_puzzle_seed(16, 30, 99, tier, max_cells, 1000, 7).unwrap();
+        assert!(!puzzle.solution.is_empty() && puzzle.solution.len() <= max_cells);
+        assert_eq!(solve_explain(&puzzle.game_board)[..puzzle.solution.len()], puzzle.solution[..]);
+        // 枚举也判不出答案以外的格子
+        let (matrix_a_s, matrix_x_s, matrix_b_s, _, _) = refresh_matrixs(&puzzle.game_board);
+        let (not_mine, is_mine) = solve_enumerate(&matrix_a_s, &matrix_x_s, &matrix_b_s);
+        let mut all: Vec<_> = not_mine.into_iter().chain(is_mine).collect();
+        all.sort_unstable();
+        all.dedup();
+        let mut cells: Vec<_> = puzzle.solution.iter().map(|d| d.cell).collect();
+        cells.sort_unstable();
+        assert_eq!(all, cells);
+        for d in &puzzle.solution {
+            assert_eq!(puzzle.board[d.cell.0][d.cell.1] == -1, d.is_mine);
+            assert_eq!(puzzle.game_board[d.cell.0][d.cell.1], 10);
+            match tier {
+                PuzzleTier::Direct => assert!(matches!(d.proof, Proof::Direct(_))),
+                PuzzleTier::Minus => assert!(matches!(d.proof, Proof::Minus { .. })),
+                PuzzleTier::Enumerate => assert!(matches!(d.proof, Proof::Enumerate(_))),
+            }
+        }
+        assert_eq!(
+            generate_puzzle_seed(16, 30, 99, tier, max_cells, 1000, 7),
+            Some(puzzle)
+        );
+    }
+}
+
+#[test]
+fn rearrange_mines_works() {
+    // 测试移雷：新的局面与已打开的格子一致、雷数不变，且能移时一定移成要求的状态
+    for seed in 0..20 {
+        let board = laymine_op_seed(16, 30, 99, 8, 15, seed);
+        let mut game_board = vec![vec![10; 30]; 16];
+        refresh_board(&board, &mut game_board, vec![(8, 15)]);
+        let (p, _, _, _) = cal_possibility(&game_board, 99.0).unwrap();
+        for ((x, y), q) in p.into_iter().step_by(5) {
+            for is_mine in [true, false] {
+                let new_board = rearrange_mines(&board, &game_board, (x, y), is_mine, seed);
+                if (is_mine && q == 0.0) || (!is_mine && q == 1.0) {
+                    assert_eq!(new_board, None);
+                    continue;
+                }
+                let new_board = new_board.unwrap();
+                assert_eq!(new_board[x][y] == -1, is_mine);
+                assert_eq!(new_board.iter().flatten().filter(|&&v| v == -1).count(), 99);
+                for i in 0..16 {
+                    for j in 0..30 {
+                        if game_board[i][j] < 10 {
+                            assert_eq!(new_board[i][j], board[i][j]);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
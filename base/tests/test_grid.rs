@@ -0,0 +1,90 @@
+use ms_toollib::{
+    cal_bbbv, cal_cell_nums, cal_isl, cal_op, laymine, laymine_seed, neighbours, BitBoard, Cell,
+    Grid,
+};
+use std::convert::TryFrom;
+
+// 测试格子与网格的类型
+
+#[test]
+fn cell_works() {
+    for v in [-1, 0, 8, 10, 11, 12, 14, 15, 16, 18] {
+        assert_eq!(Cell::try_from(v).unwrap().to_i32(), v);
+    }
+    assert_eq!(Cell::try_from(13), Err(13));
+    assert_eq!(Cell::from_i32(3), Some(Cell::Number(3)));
+    assert!(10 == Cell::Covered);
+    assert!(Cell::Flag.is_covered());
+    assert!(!Cell::Number(0).is_covered());
+}
+
+#[test]
+fn grid_works() {
+    assert_eq!(neighbours(16, 30, 0, 0).count(), 3);
+    assert_eq!(neighbours(16, 30, 0, 5).count(), 5);
+    assert_eq!(
+        neighbours(16, 30, 15, 29).collect::<Vec<_>>(),
+        vec![(14, 28), (14, 29), (15, 28)]
+    );
+    assert_eq!(neighbours(1, 1, 0, 0).count(), 0);
+
+    let board = laymine(16, 30, 99, 0, 0);
+    let grid = Grid::from_i32_board(&board).unwrap();
+    assert_eq!((grid.row(), grid.column()), (16, 30));
+    assert_eq!(grid.to_i32_board(), board);
+    // 数字等于周围的雷数
+    for ((x, y), &cell) in grid.iter() {
+        if let Cell::Number(n) = cell {
+            let mine_num = grid
+                .neighbours(x, y)
+                .filter(|&pos| grid[pos] == Cell::Mine)
+                .count();
+            assert_eq!(n as usize, mine_num);
+        }
+    }
+    assert_eq!(Grid::from_i32_board(&[vec![0, 13]]), Err((0, 1)));
+}
+
+#[test]
+fn bit_board_works() {
+    // 按位压缩的局面的指标与逐格计算的结果相同，含超过64列、需要跨u64的局面
+    for (row, column, mine_num) in [
+        (8, 8, 10),
+        (16, 30, 99),
+        (16, 30, 30),
+        (20, 70, 300),
+        (3, 130, 40),
+    ] {
+        for seed in 0..30 {
+            let board = laymine_seed(row, column, mine_num, 0, 0, seed);
+            let bit_board = BitBoard::from_board(&board);
+            assert_eq!(bit_board.get_mine_num(), mine_num);
+            assert_eq!(bit_board.to_board(), board);
+            assert_eq!(bit_board.cal_bbbv(), cal_bbbv(&board));
+            assert_eq!(bit_board.cal_op(), cal_op(board.clone()));
+            assert_eq!(bit_board.cal_isl(), cal_isl(&board));
+            assert_eq!(bit_board.cal_cell_nums(), cal_cell_nums(&board));
+        }
+    }
+    let mut bit_board = BitBoard::new(1, 3);
+    bit_board.set_mine((0, 1));
+    assert!(bit_board.is_mine((0, 1)));
+    assert_eq!(bit_board.to_board(), vec![vec![1, -1, 1]]);
+    assert_eq!(
+        (
+            bit_board.cal_op(),
+            bit_board.cal_isl(),
+            bit_board.cal_bbbv()
+        ),
+        (0, 2, 2)
+    );
+    bit_board.remove_mine((0, 1));
+    assert_eq!(
+        (
+            bit_board.cal_op(),
+            bit_board.cal_isl(),
+            bit_board.cal_bbbv()
+        ),
+        (1, 0, 1)
+    );
+}
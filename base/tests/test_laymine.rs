@@ -1,5 +1,15 @@
 // use ms_toollib::refresh_matrixs;
-use ms_toollib::{laymine_solvable, laymine_solvable_adjust, laymine_solvable_thread};
+use ms_toollib::{
+    cal_bbbv, cal_isl, cal_op, is_solvable, laymine_filtered, laymine_filtered_seed,
+    laymine_op_seed, laymine_seed, laymine_solvable, laymine_solvable_adjust,
+    laymine_solvable_adjust_seed, laymine_solvable_mcmc_seed, laymine_solvable_seed,
+    laymine_solvable_exec, laymine_solvable_thread, laymine_solvable_thread_seed, neighbours,
+    sample_3BVs_exp_exec, sample_3BVs_exp_seed, CancelToken, ExecOptions, ExecStatus, LaymineFilter,
+};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 // 测试各种埋雷类的函数
 
@@ -32,3 +42,251 @@ fn laymine_solvable_adjust_works() {
         print!("失败！！！");
     }
 }
+
+#[test]
+fn laymine_seed_works() {
+    // 测试可设种子的埋雷，相同种子埋出相同的局面
+    let board_1 = laymine_seed(16, 30, 99, 3, 4, 2022);
+    let board_2 = laymine_seed(16, 30, 99, 3, 4, 2022);
+    assert_eq!(board_1, board_2);
+    assert_ne!(board_1, laymine_seed(16, 30, 99, 3, 4, 2023));
+    assert_eq!(
+        board_1.iter().flatten().filter(|&&x| x == -1).count(),
+        99
+    );
+    let board_op = laymine_op_seed(16, 30, 99, 3, 4, 2022);
+    assert_eq!(board_op, laymine_op_seed(16, 30, 99, 3, 4, 2022));
+    assert_eq!(board_op[3][4], 0);
+    board_op.iter().for_each(|i| println!("{:?}", i));
+}
+
+#[test]
+fn laymine_solvable_seed_works() {
+    // 测试可设种子的无猜埋雷，多线程与单线程的结果相同
+    let game_board = laymine_solvable_seed(16, 30, 99, 0, 0, 100000, 7);
+    let game_board_thread = laymine_solvable_thread_seed(16, 30, 99, 0, 0, 100000, 7);
+    assert_eq!(game_board, game_board_thread);
+    let game_board = laymine_solvable_adjust_seed(16, 30, 120, 0, 0, 7);
+    assert_eq!(game_board, laymine_solvable_adjust_seed(16, 30, 120, 0, 0, 7));
+    game_board.0.iter().for_each(|i| println!("{:?}", i));
+}
+
+#[test]
+fn laymine_solvable_mcmc_works() {
+    // 测试MCMC无猜埋雷：高密度下埋出无猜局面，相同种子结果相同
+    let (board, flag, diagnostics) = laymine_solvable_mcmc_seed(16, 30, 150, 3, 20, 1000, 7);
+    assert!(flag);
+    assert!(is_solvable(&board, 3, 20));
+    assert_eq!(board.iter().flatten().filter(|&&x| x == -1).count(), 150);
+    assert_eq!(board[3][20], 0);
+    assert_eq!(diagnostics.steps, 1000);
+    assert!(diagnostics.accepted > 0 && diagnostics.accepted <= 1000);
+    assert!(diagnostics.bbbv_effective_sample_size >= 1.0);
+    println!("{:?}", diagnostics);
+    assert_eq!(
+        (board, flag, diagnostics),
+        laymine_solvable_mcmc_seed(16, 30, 150, 3, 20, 1000, 7)
+    );
+    // 小局面上枚举所有的无猜局面，MCMC埋出的局面接近均匀分布，而调整法远远偏离
+    let (row, column, mine_num) = (3, 5, 4);
+    let cells: Vec<(usize, usize)> = (0..row * column)
+        .map(|i| (i / column, i % column))
+        .filter(|&(i, j)| i > 1 || j > 1)
+        .collect();
+    let mut counts: HashMap<Vec<Vec<i32>>, usize> = HashMap::new();
+    for mask in 0u32..1 << cells.len() {
+        if mask.count_ones() as usize != mine_num {
+            continue;
+        }
+        let mut board = vec![vec![0; column]; row];
+        for (k, &(i, j)) in cells.iter().enumerate() {
+            if mask >> k & 1 == 1 {
+                board[i][j] = -1;
+            }
+        }
+        for i in 0..row {
+            for j in 0..column {
+                if board[i][j] >= 0 {
+                    board[i][j] = neighbours(row, column, i, j)
+                        .filter(|&(m, n)| board[m][n] == -1)
+                        .count() as i32;
+                }
+            }
+        }
+        if is_solvable(&board, 0, 0) {
+            counts.insert(board, 0);
+        }
+    }
+    let chi_square = |samples: &Vec<Vec<Vec<i32>>>| {
+        let mut counts = counts.clone();
+        for b in samples {
+            *counts.get_mut(b).unwrap() += 1;
+        }
+        let expect = samples.len() as f64 / counts.len() as f64;
+        counts
+            .values()
+            .map(|&c| (c as f64 - expect).powi(2) / expect)
+            .sum::<f64>()
+    };
+    let mut mcmc_samples = vec![];
+    let mut adjust_samples = vec![];
+    for seed in 0..1000 {
+        let (board, flag, _) = laymine_solvable_mcmc_seed(row, column, mine_num, 0, 0, 100, seed);
+        if flag {
+            mcmc_samples.push(board);
+        }
+        let (board, flag) = laymine_solvable_adjust_seed(row, column, mine_num, 0, 0, seed);
+        if flag && counts.contains_key(&board) {
+            adjust_samples.push(board);
+        }
+    }
+    // 自由度为counts.len() - 1，均匀分布下卡方值大约等于自由度
+    let df = (counts.len() - 1) as f64;
+    assert!(chi_square(&mcmc_samples) < 2.0 * df);
+    assert!(chi_square(&adjust_samples) > 10.0 * df);
+}
+
+#[test]
+fn laymine_filtered_works() {
+    // 测试按条件埋雷：删选法的局面都满足条件，结果可复现
+    let filter = LaymineFilter {
+        bbbv: (120, 140),
+        op: (5, usize::MAX),
+        ..LaymineFilter::default()
+    };
+    let (boards, stats) = laymine_filtered_seed(16, 30, 99, 3, 20, &filter, 5, 100000, 2024);
+    assert_eq!(boards.len(), 5);
+    assert_eq!(stats.accepted, 5);
+    assert!(!stats.exhausted);
+    assert!(stats.acceptance_rate() > 0.0 && stats.acceptance_rate() < 1.0);
+    for board in &boards {
+        assert_eq!(board.iter().flatten().filter(|&&v| v == -1).count(), 99);
+        assert_ne!(board[3][20], -1);
+        let bbbv = cal_bbbv(board);
+        assert!((120..=140).contains(&bbbv));
+        assert!(cal_op(board.clone()) >= 5);
+    }
+    assert_eq!(
+        laymine_filtered_seed(16, 30, 99, 3, 20, &filter, 5, 100000, 2024).0,
+        boards
+    );
+    // 条件苛刻时用完尝试次数
+    let filter = LaymineFilter {
+        bbbv: (300, 400),
+        ..LaymineFilter::default()
+    };
+    let (boards, stats) = laymine_filtered_seed(16, 30, 99, 3, 20, &filter, 1, 100, 2024);
+    assert!(boards.is_empty());
+    assert!(stats.exhausted);
+    assert_eq!(stats.attempts, 100);
+    // 罕见的条件：局部移动雷，并要求无猜
+    let filter = LaymineFilter {
+        bbbv: (200, usize::MAX),
+        isl: (0, 20),
+        solvable: true,
+        local_moves: 2000,
+        ..LaymineFilter::default()
+    };
+    let (boards, _) = laymine_filtered_seed(16, 30, 99, 3, 20, &filter, 1, 1000, 7);
+    assert_eq!(boards.len(), 1);
+    assert!(cal_bbbv(&boards[0]) >= 200);
+    assert!(cal_isl(&boards[0]) <= 20);
+    assert_eq!(boards[0][3][20], 0);
+    assert!(is_solvable(&boards[0], 3, 20));
+    // 多线程，限时
+    let mut filter = LaymineFilter::default();
+    filter.cell_nums[6] = (1, usize::MAX);
+    let (boards, stats) = laymine_filtered(16, 30, 99, 3, 20, &filter, 3, 5000);
+    assert_eq!(boards.len(), 3);
+    assert!(boards.iter().all(|b| b.iter().flatten().any(|&v| v == 6)));
+    let filter = LaymineFilter {
+        bbbv: (400, 500),
+        ..LaymineFilter::default()
+    };
+    let (_, stats_timeout) = laymine_filtered(16, 30, 99, 3, 20, &filter, 1, 200);
+    assert!(stats_timeout.exhausted);
+    assert!(stats_timeout.time_ms < 1000);
+    assert!(stats.attempts >= 3);
+}
+
+#[test]
+fn laymine_exec_works() {
+    // 测试执行层：结果与线程数无关，可以取消、限时，报告进度
+    let game_board = laymine_solvable_seed(16, 30, 99, 3, 20, 100000, 7);
+    for thread_num in [1, 3] {
+        let options = ExecOptions {
+            thread_num,
+            ..ExecOptions::default()
+        };
+        let (board, flag, status) = laymine_solvable_exec(16, 30, 99, 3, 20, 100000, 7, &options);
+        assert_eq!((board, flag), game_board);
+        assert_eq!(status, ExecStatus::Finished);
+    }
+    let cancel = CancelToken::new();
+    let cancel_ = cancel.clone();
+    let options = ExecOptions {
+        thread_num: 2,
+        cancel: Some(cancel.clone()),
+        // 试到第10次时取消
+        progress: Some(Arc::new(move |done, _| {
+            if done >= 10 {
+                cancel_.cancel();
+            }
+        })),
+        ..ExecOptions::default()
+    };
+    let (_, flag, status) = laymine_solvable_exec(16, 30, 200, 3, 20, 100000, 7, &options);
+    assert!(!flag);
+    assert_eq!(status, ExecStatus::Cancelled);
+    assert!(cancel.is_cancelled());
+    let options = ExecOptions {
+        deadline: Some(Instant::now() + Duration::from_millis(100)),
+        ..ExecOptions::default()
+    };
+    let (_, flag, status) = laymine_solvable_exec(16, 30, 200, 3, 20, usize::MAX, 7, &options);
+    assert!(!flag);
+    assert_eq!(status, ExecStatus::TimedOut);
+
+    let bbbvs = sample_3BVs_exp_seed(3, 20, 16000, 7);
+    let done = Arc::new(AtomicUsize::new(0));
+    let done_ = done.clone();
+    let options = ExecOptions {
+        thread_num: 3,
+        progress: Some(Arc::new(move |d, _| {
+            done_.fetch_max(d, Ordering::SeqCst);
+        })),
+        ..ExecOptions::default()
+    };
+    let (bbbvs_exec, status) = sample_3BVs_exp_exec(3, 20, 16000, 7, &options);
+    assert_eq!(bbbvs_exec, bbbvs);
+    assert_eq!(status, ExecStatus::Finished);
+    assert_eq!(done.load(Ordering::SeqCst), 16000);
+    assert_eq!(bbbvs.iter().sum::<usize>(), 16000);
+}
+
+#[test]
+fn laymine_seed_golden_works() {
+    // 测试固定种子埋出的局面不变，防止随机数生成器或埋雷算法改动后种子失效
+    assert_eq!(
+        laymine_seed(6, 6, 8, 2, 3, 2024),
+        vec![
+            vec![-1, 1, 0, 0, 0, 0],
+            vec![1, 2, 1, 1, 0, 0],
+            vec![0, 1, -1, 2, 1, 1],
+            vec![1, 3, 2, 3, -1, 1],
+            vec![-1, 3, -1, 3, 3, 2],
+            vec![-1, 3, 2, -1, 2, -1],
+        ]
+    );
+    assert_eq!(
+        laymine_op_seed(6, 6, 8, 2, 3, 2024),
+        vec![
+            vec![1, -1, 1, 1, -1, 2],
+            vec![1, 1, 1, 1, 2, -1],
+            vec![0, 0, 0, 0, 1, 1],
+            vec![0, 1, 1, 2, 1, 1],
+            vec![1, 2, -1, 3, -1, 2],
+            vec![-1, 2, 2, -1, 3, -1],
+        ]
+    );
+}
@@ -0,0 +1,97 @@
+use ms_toollib::{
+    find_patterns, laymine_op_seed, refresh_board, ErrPattern, Orientation, Pattern,
+    PatternRecognizer,
+};
+use std::collections::HashMap;
+
+// 测试定式识别
+
+#[test]
+fn find_patterns_works() {
+    // 雷在(0, 1)和(0, 3)
+    let game_board = vec![
+        vec![10, 10, 10, 10, 10],
+        vec![1, 1, 2, 1, 1],
+        vec![0, 0, 0, 0, 0],
+    ];
+    let ans = find_patterns(&game_board);
+    let m = ans.iter().find(|m| m.name == "1-2-1").unwrap();
+    assert_eq!(m.position, (0, 0));
+    assert_eq!(
+        m.orientation,
+        Orientation {
+            rotation: 0,
+            mirrored: false
+        }
+    );
+    assert_eq!(m.numbers, vec![(1, 1), (1, 2), (1, 3)]);
+    assert_eq!(m.mines, vec![(0, 1), (0, 3)]);
+    assert_eq!(m.safes, vec![(0, 0), (0, 2), (0, 4)]);
+    // 左右两端贴墙的1-1，一个是另一个的镜像
+    let walls: Vec<_> = ans.iter().filter(|m| m.name == "1-1 wall").collect();
+    assert_eq!(walls.len(), 2);
+    assert_eq!(walls[0].safes.len(), 1);
+    assert_ne!(walls[0].orientation, walls[1].orientation);
+    assert_eq!(ans.iter().filter(|m| m.name == "1-2").count(), 2);
+    assert!(ans.iter().all(|m| m.name != "1-1"));
+
+    // 标过的雷从数字中减去；竖着的1-2-1
+    let game_board = vec![
+        vec![0, 1, 10],
+        vec![1, 2, 10],
+        vec![11, 3, 10],
+        vec![1, 2, 10],
+        vec![0, 1, 10],
+    ];
+    let ans = find_patterns(&game_board);
+    let m = ans.iter().find(|m| m.name == "1-2-1").unwrap();
+    assert_eq!(m.numbers, vec![(1, 1), (2, 1), (3, 1)]);
+    assert_eq!(m.mines, vec![(1, 2), (3, 2)]);
+    assert_eq!(m.safes, vec![(0, 2), (2, 2), (4, 2)]);
+    assert_eq!(m.orientation.rotation % 2, 1);
+}
+
+#[test]
+fn pattern_recognizer_works() {
+    // 自定义的定式
+    assert_eq!(
+        Pattern::new("bad", "..m/#1#"),
+        Err(ErrPattern::ImpliedCellNotForced(0, 2))
+    );
+    assert_eq!(
+        Pattern::new("bad", "..z/#1#"),
+        Err(ErrPattern::InvalidCharacter('z'))
+    );
+    assert_eq!(
+        Pattern::new("bad", "..../#1#"),
+        Err(ErrPattern::InconsistentRowLength)
+    );
+    assert_eq!(
+        Pattern::new("bad", "#3#/#.#"),
+        Err(ErrPattern::Contradictory)
+    );
+    let mut recognizer = PatternRecognizer::empty();
+    recognizer.add_pattern(Pattern::new("2-2 corner", "#mm/#22/###").unwrap());
+    assert_eq!(recognizer.patterns().len(), 1);
+    let game_board = vec![vec![10, 10, 10, 10], vec![0, 2, 2, 1], vec![0, 0, 0, 0]];
+    assert!(recognizer.find(&game_board).is_empty());
+    let game_board = vec![vec![0, 10, 10], vec![0, 2, 2], vec![0, 0, 0]];
+    let ans = recognizer.find(&game_board);
+    assert_eq!(ans.len(), 1);
+    assert_eq!(ans[0].mines, vec![(0, 1), (0, 2)]);
+
+    // 随机局面上，定式推出的格子都正确
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for seed in 0..200 {
+        let board = laymine_op_seed(16, 30, 99, 8, 15, seed);
+        let mut game_board = vec![vec![10; 30]; 16];
+        refresh_board(&board, &mut game_board, vec![(8, 15)]);
+        for m in find_patterns(&game_board) {
+            assert!(m.mines.iter().all(|&(x, y)| board[x][y] == -1));
+            assert!(m.safes.iter().all(|&(x, y)| board[x][y] != -1));
+            *counts.entry(m.name).or_insert(0) += 1;
+        }
+    }
+    println!("{:?}", counts);
+    assert!(counts.len() >= 4);
+}
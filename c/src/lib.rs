@@ -2,6 +2,7 @@ extern crate libc;
 use ms_toollib::cal3BV as rs_cal3BV;
 use ms_toollib::cal_possibility_onboard as rs_cal_possibility_onboard;
 use ms_toollib::laymine as rs_laymine;
+use ms_toollib::laymine_seed as rs_laymine_seed;
 use ms_toollib::MinesweeperBoard as RustMinesweeperBoard;
 use std::alloc::{alloc, dealloc, Layout};
 use std::mem;
@@ -167,6 +168,19 @@ pub extern "C" fn laymine(
     vec_board_to_struct_board(b)
 }
 
+#[no_mangle]
+pub extern "C" fn laymine_seed(
+    row: usize,
+    column: usize,
+    MineNum: usize,
+    X0: usize,
+    Y0: usize,
+    seed: u64,
+) -> Board {
+    let b = rs_laymine_seed(row, column, MineNum, X0, Y0, seed);
+    vec_board_to_struct_board(b)
+}
+
 #[no_mangle]
 pub extern "C" fn cal_possibility_onboard(board_of_game: Board, mine_num: f64) -> BoardPossReturn {
     let rows_ptr = unsafe { slice::from_raw_parts(board_of_game.rows, board_of_game.n_row) };
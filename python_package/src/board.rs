@@ -1,6 +1,35 @@
 ﻿use itertools::Itertools;
 use ms_toollib::*;
+use pyo3::exceptions::PyTypeError;
 use pyo3::prelude::*;
+use pyo3::types::PyDict;
+
+fn parse_first_click_rule(rule: &str) -> PyResult<FirstClickRule> {
+    match rule {
+        "no_rule" => Ok(FirstClickRule::NoRule),
+        "arbiter" => Ok(FirstClickRule::Arbiter),
+        "win7" => Ok(FirstClickRule::Win7),
+        "xp" => Ok(FirstClickRule::Xp),
+        "relocate" => Ok(FirstClickRule::Relocate),
+        _ => Err(PyErr::new::<PyTypeError, _>(
+            "首次左键的规则应为\"no_rule\"、\"arbiter\"、\"win7\"、\"xp\"或\"relocate\"",
+        )),
+    }
+}
+
+fn parse_guess_rule(rule: &str) -> PyResult<GuessRule> {
+    match rule {
+        "standard" => Ok(GuessRule::Standard),
+        "strong_no_guess" => Ok(GuessRule::StrongNoGuess),
+        "weak_no_guess" => Ok(GuessRule::WeakNoGuess),
+        "quasi_no_guess" => Ok(GuessRule::QuasiNoGuess),
+        "strong_guessable" => Ok(GuessRule::StrongGuessable),
+        "weak_guessable" => Ok(GuessRule::WeakGuessable),
+        _ => Err(PyErr::new::<PyTypeError, _>(
+            "猜雷的规则应为\"standard\"、\"strong_no_guess\"、\"weak_no_guess\"、\"quasi_no_guess\"、\"strong_guessable\"或\"weak_guessable\"",
+        )),
+    }
+}
 
 #[pyclass(name = "MinesweeperBoard")]
 pub struct PyMinesweeperBoard {
@@ -20,6 +49,48 @@ impl PyMinesweeperBoard {
     pub fn reset(&mut self) {
         self.core.reset();
     }
+    // 首次左键的规则："no_rule"、"arbiter"、"win7"、"xp"、"relocate"，游戏开始前设置
+    pub fn set_first_click_rule(&mut self, rule: &str, seed: Option<u64>) -> PyResult<()> {
+        let rule = parse_first_click_rule(rule)?;
+        let ans = match seed {
+            Some(seed) => self.core.set_first_click_rule_seed(rule, seed),
+            None => self.core.set_first_click_rule(rule),
+        };
+        ans.map(|_| ())
+            .map_err(|_| PyErr::new::<PyTypeError, _>("游戏开始后不能设置首次左键的规则"))
+    }
+    // 猜雷的规则："standard"、"strong_no_guess"、"weak_no_guess"、"quasi_no_guess"、"strong_guessable"、
+    // "weak_guessable"，游戏开始前设置
+    pub fn set_guess_rule(&mut self, rule: &str) -> PyResult<()> {
+        let rule = parse_guess_rule(rule)?;
+        self.core
+            .set_guess_rule(rule)
+            .map(|_| ())
+            .map_err(|_| PyErr::new::<PyTypeError, _>("游戏开始后不能设置猜雷的规则"))
+    }
+    // 最多可以撤销的步数，为0时不记录（默认）
+    pub fn set_history_limit(&mut self, limit: usize) {
+        self.core.set_history_limit(limit);
+    }
+    pub fn undo(&mut self) -> bool {
+        self.core.undo()
+    }
+    pub fn redo(&mut self) -> bool {
+        self.core.redo()
+    }
+    // 快照编码成的二进制数据，可以存到文件里，以后用restore接着下
+    pub fn snapshot(&self) -> Vec<u8> {
+        self.core.snapshot().to_bytes()
+    }
+    pub fn restore(&mut self, data: Vec<u8>) -> PyResult<()> {
+        match MinesweeperBoardSnapshot::from_bytes(&data) {
+            Ok(snapshot) => {
+                self.core.restore(&snapshot);
+                Ok(())
+            }
+            Err(e) => Err(PyErr::new::<PyTypeError, _>(format!("无法解析快照：{:?}", e))),
+        }
+    }
     pub fn step_flow(&mut self, operation: Vec<(&str, (usize, usize))>) {
         self.core.step_flow(operation).unwrap();
     }
@@ -1510,6 +1581,24 @@ impl PyBaseVideo {
     pub fn reset(&mut self, row: usize, column: usize, pix_size: u8) {
         self.core.reset(row, column, pix_size);
     }
+    // 首次左键的规则，同时记录到mode
+    pub fn set_first_click_rule(&mut self, rule: &str, seed: Option<u64>) -> PyResult<()> {
+        let rule = parse_first_click_rule(rule)?;
+        let ans = match seed {
+            Some(seed) => self.core.set_first_click_rule_seed(rule, seed),
+            None => self.core.set_first_click_rule(rule),
+        };
+        ans.map(|_| ())
+            .map_err(|_| PyErr::new::<PyTypeError, _>("游戏开始后不能设置首次左键的规则"))
+    }
+    // 猜雷的规则，同时记录到mode
+    pub fn set_guess_rule(&mut self, rule: &str) -> PyResult<()> {
+        let rule = parse_guess_rule(rule)?;
+        self.core
+            .set_guess_rule(rule)
+            .map(|_| ())
+            .map_err(|_| PyErr::new::<PyTypeError, _>("游戏开始后不能设置猜雷的规则"))
+    }
     pub fn win_then_flag_all_mine(&mut self) {
         self.core.win_then_flag_all_mine();
     }
@@ -1883,6 +1972,16 @@ impl PyGameBoard {
         let c = GameBoard::new(mine_num);
         PyGameBoard { core: c }
     }
+    #[staticmethod]
+    pub fn from_str(s: &str) -> PyResult<PyGameBoard> {
+        match s.parse::<GameBoard>() {
+            Ok(c) => Ok(PyGameBoard { core: c }),
+            Err(e) => Err(PyErr::new::<PyTypeError, _>(format!("{:?}", e))),
+        }
+    }
+    fn __str__(&self) -> String {
+        self.core.to_string()
+    }
     #[setter]
     fn set_game_board(&mut self, board: Vec<Vec<i32>>) {
         self.core.set_game_board(&board);
@@ -1907,6 +2006,14 @@ impl PyGameBoard {
     fn get_enum_is_mine(&mut self) -> PyResult<Vec<(usize, usize)>> {
         Ok(self.core.get_enum_is_mine().to_vec())
     }
+    #[getter]
+    fn get_global_not_mine(&mut self) -> PyResult<Vec<(usize, usize)>> {
+        Ok(self.core.get_global_not_mine().to_vec())
+    }
+    #[getter]
+    fn get_global_is_mine(&mut self) -> PyResult<Vec<(usize, usize)>> {
+        Ok(self.core.get_global_is_mine().to_vec())
+    }
 }
 
 #[pyclass(name = "Board")]
@@ -1921,6 +2028,56 @@ impl PyBoard {
         let c = Board::new(board);
         PyBoard { core: c }
     }
+    #[staticmethod]
+    pub fn from_str(s: &str) -> PyResult<PyBoard> {
+        match s.parse::<Board>() {
+            Ok(c) => Ok(PyBoard { core: c }),
+            Err(e) => Err(PyErr::new::<PyTypeError, _>(format!("{:?}", e))),
+        }
+    }
+    fn __str__(&self) -> String {
+        self.core.to_string()
+    }
+    #[staticmethod]
+    pub fn from_mbf(raw_data: Vec<u8>) -> PyResult<PyBoard> {
+        match Board::from_mbf(&raw_data) {
+            Ok(c) => Ok(PyBoard { core: c }),
+            Err(e) => Err(PyErr::new::<PyTypeError, _>(format!("{:?}", e))),
+        }
+    }
+    #[staticmethod]
+    pub fn from_mbf_file(file_name: &str) -> PyResult<PyBoard> {
+        match Board::from_mbf_file(file_name) {
+            Ok(c) => Ok(PyBoard { core: c }),
+            Err(e) => Err(PyErr::new::<PyTypeError, _>(format!("{:?}", e))),
+        }
+    }
+    pub fn get_mbf_raw_data(&self) -> PyResult<Vec<u8>> {
+        match self.core.get_mbf_raw_data() {
+            Ok(t) => Ok(t),
+            Err(e) => Err(PyErr::new::<PyTypeError, _>(format!("{:?}", e))),
+        }
+    }
+    pub fn save_to_mbf_file(&self, file_name: &str) -> PyResult<String> {
+        match self.core.save_to_mbf_file(file_name) {
+            Ok(t) => Ok(t),
+            Err(e) => Err(PyErr::new::<PyTypeError, _>(format!("{:?}", e))),
+        }
+    }
+    pub fn get_difficulty(&self, py: Python, x0: usize, y0: usize) -> PyResult<PyObject> {
+        // 从(x0, y0)起手时局面的难度，返回字典
+        let d = self.core.get_difficulty(x0, y0);
+        let t = PyDict::new(py);
+        t.set_item("direct_steps", d.direct_steps)?;
+        t.set_item("minus_steps", d.minus_steps)?;
+        t.set_item("enumerate_steps", d.enumerate_steps)?;
+        t.set_item("max_enum_len", d.max_enum_len)?;
+        t.set_item("guesses", d.guesses)?;
+        t.set_item("guess_survival", d.guess_survival)?;
+        t.set_item("chording_chances", d.chording_chances)?;
+        t.set_item("score", d.score)?;
+        Ok(t.into())
+    }
     #[getter]
     fn get_bbbv(&mut self) -> PyResult<usize> {
         Ok(self.core.get_bbbv())
@@ -1,5 +1,6 @@
 use pyo3::exceptions::PyTypeError;
 use pyo3::prelude::*;
+use pyo3::types::PyDict;
 use pyo3::wrap_pyfunction;
 // use pyo3::PyTraverseError;
 // use pyo3::class::basic::PyObjectProtocol;
@@ -153,10 +154,15 @@ fn py_unsolvable_structure(boardCheck: Vec<Vec<i32>>) -> PyResult<bool> {
     Ok(unsolvable_structure(&boardCheck))
 }
 
-#[pyfunction]
+#[pyfunction(use_mine_num = "false")]
 #[pyo3(name = "is_solvable")]
-fn py_is_solvable(board: Vec<Vec<i32>>, x0: usize, y0: usize) -> PyResult<bool> {
-    Ok(is_solvable(&board, x0, y0))
+fn py_is_solvable(board: Vec<Vec<i32>>, x0: usize, y0: usize, use_mine_num: bool) -> PyResult<bool> {
+    // use_mine_num为True时，局部判不出时再结合剩余雷数判雷
+    if use_mine_num {
+        Ok(is_solvable_global(&board, x0, y0))
+    } else {
+        Ok(is_solvable(&board, x0, y0))
+    }
 }
 
 #[pyfunction(max_times = 1000000)]
@@ -208,6 +214,143 @@ pub fn py_laymine_solvable_adjust(
     Ok(laymine_solvable_adjust(row, column, mine_num, x0, y0))
 }
 
+#[pyfunction]
+#[pyo3(
+    name = "laymine_seed",
+    text_signature = "(row, column, mine_num, x0, y0, seed)"
+)]
+fn py_laymine_seed(
+    row: usize,
+    column: usize,
+    mine_num: usize,
+    x0: usize,
+    y0: usize,
+    seed: u64,
+) -> PyResult<Vec<Vec<i32>>> {
+    Ok(laymine_seed(row, column, mine_num, x0, y0, seed))
+}
+
+#[pyfunction]
+#[pyo3(
+    name = "laymine_op_seed",
+    text_signature = "(row, column, mine_num, x0, y0, seed)"
+)]
+fn py_laymine_op_seed(
+    row: usize,
+    column: usize,
+    mine_num: usize,
+    x0: usize,
+    y0: usize,
+    seed: u64,
+) -> PyResult<Vec<Vec<i32>>> {
+    Ok(laymine_op_seed(row, column, mine_num, x0, y0, seed))
+}
+
+#[pyfunction(max_times = 1000000)]
+#[pyo3(
+    name = "laymine_solvable_seed",
+    text_signature = "(row, column, mine_num, x0, y0, max_times, seed)"
+)]
+pub fn py_laymine_solvable_seed(
+    row: usize,
+    column: usize,
+    mine_num: usize,
+    x0: usize,
+    y0: usize,
+    max_times: usize,
+    seed: u64,
+) -> PyResult<(Vec<Vec<i32>>, bool)> {
+    Ok(laymine_solvable_seed(
+        row, column, mine_num, x0, y0, max_times, seed,
+    ))
+}
+
+#[pyfunction(max_times = 1000000)]
+#[pyo3(
+    name = "laymine_solvable_thread_seed",
+    text_signature = "(row, column, mine_num, x0, y0, max_times, seed)"
+)]
+pub fn py_laymine_solvable_thread_seed(
+    row: usize,
+    column: usize,
+    mine_num: usize,
+    x0: usize,
+    y0: usize,
+    max_times: usize,
+    seed: u64,
+) -> PyResult<(Vec<Vec<i32>>, bool)> {
+    Ok(laymine_solvable_thread_seed(
+        row, column, mine_num, x0, y0, max_times, seed,
+    ))
+}
+
+#[pyfunction]
+#[pyo3(
+    name = "laymine_solvable_adjust_seed",
+    text_signature = "(row, column, mine_num, x0, y0, seed)"
+)]
+pub fn py_laymine_solvable_adjust_seed(
+    row: usize,
+    column: usize,
+    mine_num: usize,
+    x0: usize,
+    y0: usize,
+    seed: u64,
+) -> PyResult<(Vec<Vec<i32>>, bool)> {
+    Ok(laymine_solvable_adjust_seed(row, column, mine_num, x0, y0, seed))
+}
+
+fn mcmc_diagnostics_to_dict(py: Python, diagnostics: &McmcDiagnostics) -> PyResult<PyObject> {
+    let t = PyDict::new(py);
+    t.set_item("steps", diagnostics.steps)?;
+    t.set_item("accepted", diagnostics.accepted)?;
+    t.set_item("unmoved_mines", diagnostics.unmoved_mines)?;
+    t.set_item(
+        "bbbv_effective_sample_size",
+        diagnostics.bbbv_effective_sample_size,
+    )?;
+    Ok(t.into())
+}
+
+#[pyfunction]
+#[pyo3(
+    name = "laymine_solvable_mcmc",
+    text_signature = "(row, column, mine_num, x0, y0, steps)"
+)]
+pub fn py_laymine_solvable_mcmc(
+    py: Python,
+    row: usize,
+    column: usize,
+    mine_num: usize,
+    x0: usize,
+    y0: usize,
+    steps: usize,
+) -> PyResult<(Vec<Vec<i32>>, bool, PyObject)> {
+    // 返回（局面，是否成功，混合诊断的字典）
+    let (board, flag, diagnostics) = laymine_solvable_mcmc(row, column, mine_num, x0, y0, steps);
+    Ok((board, flag, mcmc_diagnostics_to_dict(py, &diagnostics)?))
+}
+
+#[pyfunction]
+#[pyo3(
+    name = "laymine_solvable_mcmc_seed",
+    text_signature = "(row, column, mine_num, x0, y0, steps, seed)"
+)]
+pub fn py_laymine_solvable_mcmc_seed(
+    py: Python,
+    row: usize,
+    column: usize,
+    mine_num: usize,
+    x0: usize,
+    y0: usize,
+    steps: usize,
+    seed: u64,
+) -> PyResult<(Vec<Vec<i32>>, bool, PyObject)> {
+    let (board, flag, diagnostics) =
+        laymine_solvable_mcmc_seed(row, column, mine_num, x0, y0, steps, seed);
+    Ok((board, flag, mcmc_diagnostics_to_dict(py, &diagnostics)?))
+}
+
 #[pyfunction]
 #[pyo3(name = "cal_possibility")]
 fn py_cal_possibility(
@@ -224,6 +367,316 @@ fn py_cal_possibility(
     };
 }
 
+#[pyfunction]
+#[pyo3(name = "cal_possibility_exact")]
+fn py_cal_possibility_exact(
+    py: Python,
+    mut board_of_game: Vec<Vec<i32>>,
+    mine_num: f64,
+) -> PyResult<(Vec<((usize, usize), PyObject)>, PyObject, [usize; 3], usize)> {
+    // 与cal_possibility相同，但概率是精确的fractions.Fraction
+    mark_board(&mut board_of_game);
+    let fraction = py.import("fractions")?.getattr("Fraction")?;
+    let to_fraction = |r: &BigRational| -> PyResult<PyObject> {
+        Ok(fraction.call1((r.to_string(),))?.into())
+    };
+    match cal_possibility_exact(&board_of_game, mine_num) {
+        Ok((p, p_unknow, mine_num_range, max_len)) => {
+            let mut p_py = vec![];
+            for (pos, poss) in &p {
+                p_py.push((*pos, to_fraction(poss)?));
+            }
+            Ok((p_py, to_fraction(&p_unknow)?, mine_num_range, max_len))
+        }
+        Err(1) => Err(PyErr::new::<PyTypeError, _>("无解的局面")),
+        _ => Err(PyErr::new::<PyTypeError, _>("未知的错误")),
+    }
+}
+
+fn py_constraint(py: Python, c: &Constraint) -> PyResult<PyObject> {
+    let d = PyDict::new(py);
+    d.set_item("number", c.number)?;
+    d.set_item("cells", c.cells.clone())?;
+    d.set_item("mines", c.mines)?;
+    Ok(d.into())
+}
+
+fn py_deduction(py: Python, d: &Deduction) -> PyResult<PyObject> {
+    let t = PyDict::new(py);
+    t.set_item("cell", d.cell)?;
+    t.set_item("is_mine", d.is_mine)?;
+    t.set_item("round", d.round)?;
+    let mut constraints = vec![];
+    match &d.proof {
+        Proof::Direct(c) => {
+            t.set_item("method", "direct")?;
+            constraints.push(py_constraint(py, c)?);
+        }
+        Proof::Minus { a, b, relation } => {
+            t.set_item("method", "minus")?;
+            constraints.push(py_constraint(py, a)?);
+            constraints.push(py_constraint(py, b)?);
+            t.set_item(
+                "relation",
+                match relation {
+                    Relation::Contains => "contains",
+                    Relation::ContainedIn => "contained_in",
+                    Relation::Overlap => "overlap",
+                },
+            )?;
+        }
+        Proof::Enumerate(cs) => {
+            t.set_item("method", "enumerate")?;
+            for c in cs {
+                constraints.push(py_constraint(py, c)?);
+            }
+        }
+    }
+    t.set_item("constraints", constraints)?;
+    Ok(t.into())
+}
+
+#[pyfunction]
+#[pyo3(name = "solve_explain")]
+fn py_solve_explain(py: Python, board_of_game: Vec<Vec<i32>>) -> PyResult<Vec<PyObject>> {
+    // 每条推理是一个字典：cell、is_mine、round、method（"direct"、"minus"、"enumerate"）、
+    // constraints（证明用到的约束）、relation（仅双集合，"contains"、"contained_in"、"overlap"）
+    solve_explain(&board_of_game)
+        .iter()
+        .map(|d| py_deduction(py, d))
+        .collect()
+}
+
+fn parse_puzzle_tier(tier: &str) -> PyResult<PuzzleTier> {
+    match tier {
+        "direct" => Ok(PuzzleTier::Direct),
+        "minus" => Ok(PuzzleTier::Minus),
+        "enumerate" => Ok(PuzzleTier::Enumerate),
+        _ => Err(PyErr::new::<PyTypeError, _>(
+            "推理方法应为\"direct\"、\"minus\"或\"enumerate\"",
+        )),
+    }
+}
+
+fn puzzle_to_dict(py: Python, puzzle: Option<Puzzle>) -> PyResult<Option<PyObject>> {
+    // 字典：board、game_board、solution（推理的列表，同solve_explain）
+    let puzzle = match puzzle {
+        Some(p) => p,
+        None => return Ok(None),
+    };
+    let t = PyDict::new(py);
+    t.set_item("board", puzzle.board)?;
+    t.set_item("game_board", puzzle.game_board)?;
+    let solution = puzzle
+        .solution
+        .iter()
+        .map(|d| py_deduction(py, d))
+        .collect::<PyResult<Vec<_>>>()?;
+    t.set_item("solution", solution)?;
+    Ok(Some(t.into()))
+}
+
+#[pyfunction(max_cells = "1", max_times = "1000")]
+#[pyo3(
+    name = "generate_puzzle",
+    text_signature = "(row, column, mine_num, tier, max_cells=1, max_times=1000)"
+)]
+fn py_generate_puzzle(
+    py: Python,
+    row: usize,
+    column: usize,
+    mine_num: usize,
+    tier: &str,
+    max_cells: usize,
+    max_times: usize,
+) -> PyResult<Option<PyObject>> {
+    // tier为"direct"、"minus"或"enumerate"；生成不了时返回None
+    let tier = parse_puzzle_tier(tier)?;
+    let puzzle = py.allow_threads(|| generate_puzzle(row, column, mine_num, tier, max_cells, max_times));
+    puzzle_to_dict(py, puzzle)
+}
+
+#[pyfunction(max_cells = "1", max_times = "1000")]
+#[pyo3(
+    name = "generate_puzzle_seed",
+    text_signature = "(row, column, mine_num, tier, seed, max_cells=1, max_times=1000)"
+)]
+fn py_generate_puzzle_seed(
+    py: Python,
+    row: usize,
+    column: usize,
+    mine_num: usize,
+    tier: &str,
+    seed: u64,
+    max_cells: usize,
+    max_times: usize,
+) -> PyResult<Option<PyObject>> {
+    let tier = parse_puzzle_tier(tier)?;
+    let puzzle = py.allow_threads(|| {
+        generate_puzzle_seed(row, column, mine_num, tier, max_cells, max_times, seed)
+    });
+    puzzle_to_dict(py, puzzle)
+}
+
+#[pyfunction]
+#[pyo3(name = "rearrange_mines")]
+fn py_rearrange_mines(
+    board: Vec<Vec<i32>>,
+    game_board: Vec<Vec<i32>>,
+    cell: (usize, usize),
+    is_mine: bool,
+    seed: u64,
+) -> PyResult<Option<Vec<Vec<i32>>>> {
+    // 移不了雷时返回None
+    Ok(rearrange_mines(&board, &game_board, cell, is_mine, seed))
+}
+
+#[pyfunction]
+#[pyo3(name = "suggest_move")]
+fn py_suggest_move(py: Python, board_of_game: Vec<Vec<i32>>, mine_num: f64) -> PyResult<Vec<PyObject>> {
+    // 每条推荐是一个字典：cell、method（"safe"、"guess"），猜雷还有mine_probability、
+    // zero_probability、expected_solvable、score
+    let moves = match suggest_move(&board_of_game, mine_num) {
+        Ok(t) => t,
+        Err(_) => return Err(PyErr::new::<PyTypeError, _>("无解的局面")),
+    };
+    let mut ans = vec![];
+    for m in moves {
+        let t = PyDict::new(py);
+        t.set_item("cell", m.cell)?;
+        match m.reason {
+            MoveReason::Safe => t.set_item("method", "safe")?,
+            MoveReason::Guess {
+                mine_probability,
+                zero_probability,
+                expected_solvable,
+                score,
+            } => {
+                t.set_item("method", "guess")?;
+                t.set_item("mine_probability", mine_probability)?;
+                t.set_item("zero_probability", zero_probability)?;
+                t.set_item("expected_solvable", expected_solvable)?;
+                t.set_item("score", score)?;
+            }
+        }
+        ans.push(t.into());
+    }
+    Ok(ans)
+}
+
+#[pyfunction]
+#[pyo3(name = "validate_game_board")]
+fn py_validate_game_board(
+    py: Python,
+    board_of_game: Vec<Vec<i32>>,
+    mine_num: usize,
+) -> PyResult<Vec<PyObject>> {
+    // 每处矛盾是一个字典：kind，以及该种矛盾的其他字段
+    let mut ans = vec![];
+    for c in validate_game_board(&board_of_game, mine_num) {
+        let t = PyDict::new(py);
+        match c {
+            Contradiction::InvalidValue { cell, value } => {
+                t.set_item("kind", "invalid_value")?;
+                t.set_item("cell", cell)?;
+                t.set_item("value", value)?;
+            }
+            Contradiction::NumberTooLarge {
+                cell,
+                number,
+                covered,
+            } => {
+                t.set_item("kind", "number_too_large")?;
+                t.set_item("cell", cell)?;
+                t.set_item("number", number)?;
+                t.set_item("covered", covered)?;
+            }
+            Contradiction::OverFlagged {
+                cell,
+                number,
+                flags,
+            } => {
+                t.set_item("kind", "over_flagged")?;
+                t.set_item("cell", cell)?;
+                t.set_item("number", number)?;
+                t.set_item("flags", flags)?;
+            }
+            Contradiction::Unsatisfiable(cells) => {
+                t.set_item("kind", "unsatisfiable")?;
+                t.set_item("cells", cells)?;
+            }
+            Contradiction::MineCountOutOfRange { mine_num, min, max } => {
+                t.set_item("kind", "mine_count_out_of_range")?;
+                t.set_item("mine_num", mine_num)?;
+                t.set_item("min", min)?;
+                t.set_item("max", max)?;
+            }
+            Contradiction::WrongFlag(cell) => {
+                t.set_item("kind", "wrong_flag")?;
+                t.set_item("cell", cell)?;
+            }
+        }
+        ans.push(t.into());
+    }
+    Ok(ans)
+}
+
+#[pyfunction]
+#[pyo3(name = "solve_endgame")]
+fn py_solve_endgame(
+    board_of_game: Vec<Vec<i32>>,
+    mine_num: usize,
+    budget: usize,
+) -> PyResult<(usize, Vec<((usize, usize), usize, f64)>)> {
+    // 返回（分布的总数，点法），每种点法是（格子，能赢的分布数，胜率）
+    match solve_endgame(&board_of_game, mine_num, budget) {
+        Ok((total, moves)) => Ok((
+            total,
+            moves
+                .iter()
+                .map(|m| (m.cell, m.wins, m.win_probability))
+                .collect(),
+        )),
+        Err(e) => Err(PyErr::new::<PyTypeError, _>(format!("残局求解失败：{:?}", e))),
+    }
+}
+
+#[pyfunction(patterns = "vec![]")]
+#[pyo3(name = "find_patterns")]
+fn py_find_patterns(
+    py: Python,
+    board_of_game: Vec<Vec<i32>>,
+    patterns: Vec<(String, String)>,
+) -> PyResult<Vec<PyObject>> {
+    // patterns为追加的自定义定式，每个是（名称，模板）。每个结果是一个字典：name、position、
+    // rotation、mirrored、numbers、mines、safes
+    let mut recognizer = PatternRecognizer::new();
+    for (name, template) in &patterns {
+        match Pattern::new(name, template) {
+            Ok(p) => recognizer.add_pattern(p),
+            Err(e) => {
+                return Err(PyErr::new::<PyTypeError, _>(format!(
+                    "定式{}的模板有误：{:?}",
+                    name, e
+                )))
+            }
+        }
+    }
+    let mut ans = vec![];
+    for m in recognizer.find(&board_of_game) {
+        let t = PyDict::new(py);
+        t.set_item("name", m.name)?;
+        t.set_item("position", m.position)?;
+        t.set_item("rotation", m.orientation.rotation)?;
+        t.set_item("mirrored", m.orientation.mirrored)?;
+        t.set_item("numbers", m.numbers)?;
+        t.set_item("mines", m.mines)?;
+        t.set_item("safes", m.safes)?;
+        ans.push(t.into());
+    }
+    Ok(ans)
+}
+
 #[pyfunction]
 #[pyo3(name = "cal_possibility_onboard")]
 fn py_cal_possibility_onboard(
@@ -238,12 +691,341 @@ fn py_cal_possibility_onboard(
     };
 }
 
+#[pyfunction]
+#[pyo3(name = "cal_number_distribution_onboard")]
+fn py_cal_number_distribution_onboard(
+    mut board_of_game: Vec<Vec<i32>>,
+    mine_num: f64,
+) -> PyResult<Vec<Vec<[f64; 9]>>> {
+    // 每格9个概率，第n个是该格不是雷、且打开后显示n的概率
+    mark_board(&mut board_of_game);
+    Ok(cal_number_distribution_onboard(&board_of_game, mine_num).unwrap_or_default())
+}
+
+#[pyfunction]
+#[pyo3(name = "cal_op_possibility_onboard")]
+fn py_cal_op_possibility_onboard(
+    mut board_of_game: Vec<Vec<i32>>,
+    mine_num: f64,
+) -> PyResult<Vec<Vec<f64>>> {
+    mark_board(&mut board_of_game);
+    Ok(cal_op_possibility_onboard(&board_of_game, mine_num).unwrap_or_default())
+}
+
+#[pyfunction]
+#[pyo3(name = "cal_information_gain_onboard")]
+fn py_cal_information_gain_onboard(
+    mut board_of_game: Vec<Vec<i32>>,
+    mine_num: f64,
+) -> PyResult<Vec<Vec<f64>>> {
+    mark_board(&mut board_of_game);
+    Ok(cal_information_gain_onboard(&board_of_game, mine_num).unwrap_or_default())
+}
+
 #[pyfunction]
 #[pyo3(name = "sample_3BVs_exp")]
 fn py_sample_3BVs_exp(x0: usize, y0: usize, n: usize) -> PyResult<Vec<usize>> {
     Ok((&sample_3BVs_exp(x0, y0, n)).to_vec())
 }
 
+#[pyfunction]
+#[pyo3(name = "sample_3BVs_exp_seed")]
+fn py_sample_3BVs_exp_seed(x0: usize, y0: usize, n: usize, seed: u64) -> PyResult<Vec<usize>> {
+    Ok((&sample_3BVs_exp_seed(x0, y0, n, seed)).to_vec())
+}
+
+#[pyclass(name = "ExecOptions")]
+pub struct PyExecOptions {
+    thread_num: usize,
+    timeout_ms: Option<u64>,
+    progress: Option<PyObject>,
+    cancel: CancelToken,
+}
+
+#[pymethods]
+impl PyExecOptions {
+    #[new]
+    #[args(thread_num = "0", timeout_ms = "None", progress = "None")]
+    pub fn new(thread_num: usize, timeout_ms: Option<u64>, progress: Option<PyObject>) -> PyExecOptions {
+        // thread_num为0时用所有的CPU核；progress为回调函数progress(done, total)
+        PyExecOptions {
+            thread_num,
+            timeout_ms,
+            progress,
+            cancel: CancelToken::new(),
+        }
+    }
+    /// 取消正在进行的计算，可以在别的线程中调用
+    pub fn cancel(&self) {
+        self.cancel.cancel();
+    }
+    pub fn is_cancelled(&self) -> bool {
+        self.cancel.is_cancelled()
+    }
+}
+
+impl PyExecOptions {
+    // 每次计算开始时转换，限时从此时算起
+    fn to_exec_options(&self) -> ExecOptions {
+        let mut options = ExecOptions {
+            cancel: Some(self.cancel.clone()),
+            deadline: self
+                .timeout_ms
+                .map(|t| std::time::Instant::now() + std::time::Duration::from_millis(t)),
+            ..ExecOptions::default()
+        };
+        if self.thread_num > 0 {
+            options.thread_num = self.thread_num;
+        }
+        if let Some(f) = &self.progress {
+            let f = f.clone();
+            options.progress = Some(std::sync::Arc::new(move |done, total| {
+                Python::with_gil(|py| {
+                    if let Err(e) = f.call1(py, (done, total)) {
+                        e.print(py);
+                    }
+                });
+            }));
+        }
+        options
+    }
+}
+
+fn exec_status_str(status: ExecStatus) -> &'static str {
+    match status {
+        ExecStatus::Finished => "finished",
+        ExecStatus::Cancelled => "cancelled",
+        ExecStatus::TimedOut => "timed_out",
+    }
+}
+
+#[pyfunction]
+#[pyo3(
+    name = "laymine_solvable_exec",
+    text_signature = "(row, column, mine_num, x0, y0, max_times, seed, options)"
+)]
+pub fn py_laymine_solvable_exec(
+    py: Python,
+    row: usize,
+    column: usize,
+    mine_num: usize,
+    x0: usize,
+    y0: usize,
+    max_times: usize,
+    seed: u64,
+    options: &PyExecOptions,
+) -> PyResult<(Vec<Vec<i32>>, bool, &'static str)> {
+    // 返回（局面，是否成功，"finished"、"cancelled"或"timed_out"）
+    let options = options.to_exec_options();
+    let (board, flag, status) = py.allow_threads(|| {
+        laymine_solvable_exec(row, column, mine_num, x0, y0, max_times, seed, &options)
+    });
+    Ok((board, flag, exec_status_str(status)))
+}
+
+#[pyfunction]
+#[pyo3(name = "sample_3BVs_exp_exec", text_signature = "(x0, y0, n, seed, options)")]
+fn py_sample_3BVs_exp_exec(
+    py: Python,
+    x0: usize,
+    y0: usize,
+    n: usize,
+    seed: u64,
+    options: &PyExecOptions,
+) -> PyResult<(Vec<usize>, &'static str)> {
+    let options = options.to_exec_options();
+    let (bbbvs, status) = py.allow_threads(|| sample_3BVs_exp_exec(x0, y0, n, seed, &options));
+    Ok((bbbvs.to_vec(), exec_status_str(status)))
+}
+
+#[pyfunction]
+#[pyo3(name = "cal_possibility_exec", text_signature = "(board_of_game, mine_num, options)")]
+fn py_cal_possibility_exec(
+    py: Python,
+    mut board_of_game: Vec<Vec<i32>>,
+    mine_num: f64,
+    options: &PyExecOptions,
+) -> PyResult<(Vec<((usize, usize), f64)>, f64, [usize; 3], usize)> {
+    // 与cal_possibility相同，但多线程、可取消、可限时
+    mark_board(&mut board_of_game);
+    let options = options.to_exec_options();
+    match py.allow_threads(|| cal_possibility_exec(&board_of_game, mine_num, &options)) {
+        Ok(t) => Ok(t),
+        Err(1) => Err(PyErr::new::<PyTypeError, _>("无解的局面")),
+        Err(2) => Err(PyErr::new::<PyTypeError, _>("计算被取消或超时")),
+        _ => Err(PyErr::new::<PyTypeError, _>("未知的错误")),
+    }
+}
+
+#[pyfunction(solvable = "false", seed = "None")]
+#[pyo3(
+    name = "laymine_mbf_boards",
+    text_signature = "(row, column, mine_num, x0, y0, n, bbbv_range, solvable, seed)"
+)]
+fn py_laymine_mbf_boards(
+    row: usize,
+    column: usize,
+    mine_num: usize,
+    x0: usize,
+    y0: usize,
+    n: usize,
+    bbbv_range: (usize, usize),
+    solvable: bool,
+    seed: Option<u64>,
+) -> PyResult<Vec<Vec<u8>>> {
+    // 不设种子时每次生成的局面集都不同
+    Ok(match seed {
+        Some(seed) => laymine_mbf_boards_seed(
+            row, column, mine_num, x0, y0, n, bbbv_range, solvable, seed,
+        ),
+        None => laymine_mbf_boards(row, column, mine_num, x0, y0, n, bbbv_range, solvable),
+    })
+}
+
+fn make_laymine_filter(
+    bbbv: Option<(usize, usize)>,
+    op: Option<(usize, usize)>,
+    isl: Option<(usize, usize)>,
+    cell_nums: Option<Vec<(usize, usize)>>,
+    solvable: bool,
+    difficulty: Option<(f64, f64)>,
+    local_moves: usize,
+) -> PyResult<LaymineFilter> {
+    let mut filter = LaymineFilter {
+        solvable,
+        local_moves,
+        ..LaymineFilter::default()
+    };
+    if let Some(r) = difficulty {
+        filter.difficulty = r;
+    }
+    if let Some(r) = bbbv {
+        filter.bbbv = r;
+    }
+    if let Some(r) = op {
+        filter.op = r;
+    }
+    if let Some(r) = isl {
+        filter.isl = r;
+    }
+    if let Some(c) = cell_nums {
+        if c.len() != 9 {
+            return Err(PyErr::new::<PyTypeError, _>("cell_nums的长度必须为9"));
+        }
+        filter.cell_nums.copy_from_slice(&c);
+    }
+    Ok(filter)
+}
+
+fn laymine_filter_stats_to_dict(py: Python, stats: &LaymineFilterStats) -> PyResult<PyObject> {
+    let t = PyDict::new(py);
+    t.set_item("attempts", stats.attempts)?;
+    t.set_item("accepted", stats.accepted)?;
+    t.set_item("acceptance_rate", stats.acceptance_rate())?;
+    t.set_item("time_ms", stats.time_ms)?;
+    t.set_item("exhausted", stats.exhausted)?;
+    Ok(t.into())
+}
+
+#[pyfunction(
+    bbbv = "None",
+    op = "None",
+    isl = "None",
+    cell_nums = "None",
+    solvable = "false",
+    difficulty = "None",
+    local_moves = "0",
+    time_limit_ms = "1000"
+)]
+#[pyo3(
+    name = "laymine_filtered",
+    text_signature = "(row, column, mine_num, x0, y0, n, bbbv, op, isl, cell_nums, solvable, difficulty, local_moves, time_limit_ms)"
+)]
+fn py_laymine_filtered(
+    py: Python,
+    row: usize,
+    column: usize,
+    mine_num: usize,
+    x0: usize,
+    y0: usize,
+    n: usize,
+    bbbv: Option<(usize, usize)>,
+    op: Option<(usize, usize)>,
+    isl: Option<(usize, usize)>,
+    cell_nums: Option<Vec<(usize, usize)>>,
+    solvable: bool,
+    difficulty: Option<(f64, f64)>,
+    local_moves: usize,
+    time_limit_ms: u64,
+) -> PyResult<(Vec<Vec<Vec<i32>>>, PyObject)> {
+    let filter = make_laymine_filter(bbbv, op, isl, cell_nums, solvable, difficulty, local_moves)?;
+    // 多线程埋雷时释放GIL
+    let (boards, stats) = py.allow_threads(|| {
+        laymine_filtered(row, column, mine_num, x0, y0, &filter, n, time_limit_ms)
+    });
+    Ok((boards, laymine_filter_stats_to_dict(py, &stats)?))
+}
+
+#[pyfunction(
+    bbbv = "None",
+    op = "None",
+    isl = "None",
+    cell_nums = "None",
+    solvable = "false",
+    difficulty = "None",
+    local_moves = "0",
+    max_times = "100000",
+    seed = "0"
+)]
+#[pyo3(
+    name = "laymine_filtered_seed",
+    text_signature = "(row, column, mine_num, x0, y0, n, bbbv, op, isl, cell_nums, solvable, difficulty, local_moves, max_times, seed)"
+)]
+fn py_laymine_filtered_seed(
+    py: Python,
+    row: usize,
+    column: usize,
+    mine_num: usize,
+    x0: usize,
+    y0: usize,
+    n: usize,
+    bbbv: Option<(usize, usize)>,
+    op: Option<(usize, usize)>,
+    isl: Option<(usize, usize)>,
+    cell_nums: Option<Vec<(usize, usize)>>,
+    solvable: bool,
+    difficulty: Option<(f64, f64)>,
+    local_moves: usize,
+    max_times: usize,
+    seed: u64,
+) -> PyResult<(Vec<Vec<Vec<i32>>>, PyObject)> {
+    let filter = make_laymine_filter(bbbv, op, isl, cell_nums, solvable, difficulty, local_moves)?;
+    let (boards, stats) =
+        laymine_filtered_seed(row, column, mine_num, x0, y0, &filter, n, max_times, seed);
+    Ok((boards, laymine_filter_stats_to_dict(py, &stats)?))
+}
+
+#[pyfunction(start = "None")]
+#[pyo3(name = "encode_board_code", text_signature = "(board, start)")]
+fn py_encode_board_code(
+    board: Vec<Vec<i32>>,
+    start: Option<(usize, usize)>,
+) -> PyResult<String> {
+    match encode_board_code(&board, start) {
+        Ok(code) => Ok(code),
+        Err(e) => Err(PyErr::new::<PyTypeError, _>(format!("{:?}", e))),
+    }
+}
+
+#[pyfunction]
+#[pyo3(name = "decode_board_code", text_signature = "(code)")]
+fn py_decode_board_code(code: &str) -> PyResult<(Vec<Vec<i32>>, Option<(usize, usize)>)> {
+    match decode_board_code(code) {
+        Ok(t) => Ok(t),
+        Err(e) => Err(PyErr::new::<PyTypeError, _>(format!("{:?}", e))),
+    }
+}
+
 #[pyfunction]
 #[pyo3(name = "OBR_board", text_signature = "(data_vec, height, width)")]
 fn py_OBR_board(data_vec: Vec<usize>, height: usize, width: usize) -> PyResult<Vec<Vec<i32>>> {
@@ -255,10 +1037,14 @@ fn py_OBR_board(data_vec: Vec<usize>, height: usize, width: usize) -> PyResult<V
     }
 }
 
-#[pyfunction]
+#[pyfunction(mine_num = "None")]
 #[pyo3(name = "mark_board")]
-fn py_mark_board(mut board_of_game: Vec<Vec<i32>>) -> PyResult<Vec<Vec<i32>>> {
-    mark_board(&mut board_of_game);
+fn py_mark_board(mut board_of_game: Vec<Vec<i32>>, mine_num: Option<usize>) -> PyResult<Vec<Vec<i32>>> {
+    // 给出总雷数时，再结合雷数标雷、标非雷
+    match mine_num {
+        Some(n) => mark_board_global(&mut board_of_game, n),
+        None => mark_board(&mut board_of_game),
+    }
     Ok(board_of_game)
 }
 
@@ -322,8 +1108,36 @@ fn ms_toollib(_py: Python, m: &PyModule) -> PyResult<()> {
     m.add_function(wrap_pyfunction!(py_laymine_solvable_adjust, m)?)?;
     m.add_function(wrap_pyfunction!(py_cal_possibility, m)?)?;
     m.add_function(wrap_pyfunction!(py_sample_3BVs_exp, m)?)?;
+    m.add_function(wrap_pyfunction!(py_laymine_seed, m)?)?;
+    m.add_function(wrap_pyfunction!(py_laymine_op_seed, m)?)?;
+    m.add_function(wrap_pyfunction!(py_laymine_solvable_seed, m)?)?;
+    m.add_function(wrap_pyfunction!(py_laymine_solvable_thread_seed, m)?)?;
+    m.add_function(wrap_pyfunction!(py_laymine_solvable_adjust_seed, m)?)?;
+    m.add_function(wrap_pyfunction!(py_laymine_solvable_mcmc, m)?)?;
+    m.add_function(wrap_pyfunction!(py_laymine_solvable_mcmc_seed, m)?)?;
+    m.add_function(wrap_pyfunction!(py_sample_3BVs_exp_seed, m)?)?;
+    m.add_function(wrap_pyfunction!(py_encode_board_code, m)?)?;
+    m.add_function(wrap_pyfunction!(py_decode_board_code, m)?)?;
+    m.add_function(wrap_pyfunction!(py_laymine_mbf_boards, m)?)?;
+    m.add_function(wrap_pyfunction!(py_laymine_filtered, m)?)?;
+    m.add_function(wrap_pyfunction!(py_laymine_filtered_seed, m)?)?;
+    m.add_function(wrap_pyfunction!(py_laymine_solvable_exec, m)?)?;
+    m.add_function(wrap_pyfunction!(py_sample_3BVs_exp_exec, m)?)?;
+    m.add_function(wrap_pyfunction!(py_cal_possibility_exec, m)?)?;
     m.add_function(wrap_pyfunction!(py_OBR_board, m)?)?;
     m.add_function(wrap_pyfunction!(py_cal_possibility_onboard, m)?)?;
+    m.add_function(wrap_pyfunction!(py_cal_number_distribution_onboard, m)?)?;
+    m.add_function(wrap_pyfunction!(py_cal_op_possibility_onboard, m)?)?;
+    m.add_function(wrap_pyfunction!(py_cal_information_gain_onboard, m)?)?;
+    m.add_function(wrap_pyfunction!(py_cal_possibility_exact, m)?)?;
+    m.add_function(wrap_pyfunction!(py_solve_explain, m)?)?;
+    m.add_function(wrap_pyfunction!(py_generate_puzzle, m)?)?;
+    m.add_function(wrap_pyfunction!(py_generate_puzzle_seed, m)?)?;
+    m.add_function(wrap_pyfunction!(py_rearrange_mines, m)?)?;
+    m.add_function(wrap_pyfunction!(py_find_patterns, m)?)?;
+    m.add_function(wrap_pyfunction!(py_suggest_move, m)?)?;
+    m.add_function(wrap_pyfunction!(py_solve_endgame, m)?)?;
+    m.add_function(wrap_pyfunction!(py_validate_game_board, m)?)?;
     m.add_function(wrap_pyfunction!(py_mark_board, m)?)?;
     m.add_function(wrap_pyfunction!(py_is_guess_while_needless, m)?)?;
     m.add_function(wrap_pyfunction!(py_is_able_to_solve, m)?)?;
@@ -336,5 +1150,6 @@ fn ms_toollib(_py: Python, m: &PyModule) -> PyResult<()> {
     m.add_class::<PyBaseVideo>()?;
     m.add_class::<PyGameBoard>()?;
     m.add_class::<PyBoard>()?;
+    m.add_class::<PyExecOptions>()?;
     Ok(())
 }
@@ -31,6 +31,56 @@ impl MinesweeperBoard {
     pub fn set_board(&mut self, board: &str) {
         self.core.board = json2vec(board);
     }
+    // 首次左键的规则："no_rule"、"arbiter"、"win7"、"xp"、"relocate"，游戏开始前设置。规则不认识或游戏已经开始时返回false
+    pub fn set_first_click_rule(&mut self, rule: &str, seed: u64) -> bool {
+        let rule = match rule {
+            "no_rule" => ms::FirstClickRule::NoRule,
+            "arbiter" => ms::FirstClickRule::Arbiter,
+            "win7" => ms::FirstClickRule::Win7,
+            "xp" => ms::FirstClickRule::Xp,
+            "relocate" => ms::FirstClickRule::Relocate,
+            _ => return false,
+        };
+        self.core.set_first_click_rule_seed(rule, seed).is_ok()
+    }
+    // 猜雷的规则："standard"、"strong_no_guess"、"weak_no_guess"、"quasi_no_guess"、"strong_guessable"、
+    // "weak_guessable"，游戏开始前设置。规则不认识或游戏已经开始时返回false
+    pub fn set_guess_rule(&mut self, rule: &str) -> bool {
+        let rule = match rule {
+            "standard" => ms::GuessRule::Standard,
+            "strong_no_guess" => ms::GuessRule::StrongNoGuess,
+            "weak_no_guess" => ms::GuessRule::WeakNoGuess,
+            "quasi_no_guess" => ms::GuessRule::QuasiNoGuess,
+            "strong_guessable" => ms::GuessRule::StrongGuessable,
+            "weak_guessable" => ms::GuessRule::WeakGuessable,
+            _ => return false,
+        };
+        self.core.set_guess_rule(rule).is_ok()
+    }
+    // 最多可以撤销的步数，为0时不记录（默认）
+    pub fn set_history_limit(&mut self, limit: u32) {
+        self.core.set_history_limit(limit as usize);
+    }
+    pub fn undo(&mut self) -> bool {
+        self.core.undo()
+    }
+    pub fn redo(&mut self) -> bool {
+        self.core.redo()
+    }
+    // 快照编码成的二进制数据，可以保存下来，以后用restore接着下
+    pub fn snapshot(&self) -> Vec<u8> {
+        self.core.snapshot().to_bytes()
+    }
+    // 快照无法解析时返回false，局面不变
+    pub fn restore(&mut self, data: &[u8]) -> bool {
+        match ms::MinesweeperBoardSnapshot::from_bytes(data) {
+            Ok(snapshot) => {
+                self.core.restore(&snapshot);
+                true
+            }
+            Err(_) => false,
+        }
+    }
     // 直接设置游戏局面是不安全的！但在一些游戏中，结束时需要修改再展示
     #[wasm_bindgen(setter)]
     pub fn set_game_board(&mut self, game_board: &str) {
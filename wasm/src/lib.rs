@@ -99,6 +99,69 @@ pub fn cal_possibility_onboard(board_json: &str, mine_num: i32) -> String {
     }
 }
 
+#[wasm_bindgen]
+pub fn cal_number_distribution_onboard(board_json: &str, mine_num: i32) -> String {
+    let board_: serde_json::Value = serde_json::from_str(&board_json).unwrap();
+    let board__ = board_.as_array().unwrap();
+    let len_ = board__.len();
+    let mut board_of_game = vec![];
+    for i in 0..len_ {
+        board_of_game.push(
+            board__[i]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|x| x.as_i64().unwrap() as i32)
+                .collect::<Vec<_>>(),
+        );
+    }
+    ms::mark_board(&mut board_of_game);
+    let ans = ms::cal_number_distribution_onboard(&board_of_game, mine_num as f64).unwrap_or_default();
+    serde_json::to_string(&ans).unwrap()
+}
+
+#[wasm_bindgen]
+pub fn cal_op_possibility_onboard(board_json: &str, mine_num: i32) -> String {
+    let board_: serde_json::Value = serde_json::from_str(&board_json).unwrap();
+    let board__ = board_.as_array().unwrap();
+    let len_ = board__.len();
+    let mut board_of_game = vec![];
+    for i in 0..len_ {
+        board_of_game.push(
+            board__[i]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|x| x.as_i64().unwrap() as i32)
+                .collect::<Vec<_>>(),
+        );
+    }
+    ms::mark_board(&mut board_of_game);
+    let ans = ms::cal_op_possibility_onboard(&board_of_game, mine_num as f64).unwrap_or_default();
+    serde_json::to_string(&ans).unwrap()
+}
+
+#[wasm_bindgen]
+pub fn cal_information_gain_onboard(board_json: &str, mine_num: i32) -> String {
+    let board_: serde_json::Value = serde_json::from_str(&board_json).unwrap();
+    let board__ = board_.as_array().unwrap();
+    let len_ = board__.len();
+    let mut board_of_game = vec![];
+    for i in 0..len_ {
+        board_of_game.push(
+            board__[i]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|x| x.as_i64().unwrap() as i32)
+                .collect::<Vec<_>>(),
+        );
+    }
+    ms::mark_board(&mut board_of_game);
+    let ans = ms::cal_information_gain_onboard(&board_of_game, mine_num as f64).unwrap_or_default();
+    serde_json::to_string(&ans).unwrap()
+}
+
 #[wasm_bindgen]
 pub fn laymine_number(row: i32, column: i32, mine_num: i32, x0: i32, y0: i32) -> String {
     serde_json::to_string(&ms::laymine(
@@ -143,6 +206,499 @@ pub fn laymine_solvable(
     .unwrap()
 }
 
+#[wasm_bindgen]
+pub fn laymine_seed_number(row: i32, column: i32, mine_num: i32, x0: i32, y0: i32, seed: u64) -> String {
+    serde_json::to_string(&ms::laymine_seed(
+        row as usize,
+        column as usize,
+        mine_num as usize,
+        x0 as usize,
+        y0 as usize,
+        seed,
+    ))
+    .unwrap()
+}
+
+#[wasm_bindgen]
+pub fn laymine_op_seed_number(row: i32, column: i32, mine_num: i32, x0: i32, y0: i32, seed: u64) -> String {
+    serde_json::to_string(&ms::laymine_op_seed(
+        row as usize,
+        column as usize,
+        mine_num as usize,
+        x0 as usize,
+        y0 as usize,
+        seed,
+    ))
+    .unwrap()
+}
+
+#[wasm_bindgen]
+pub fn laymine_solvable_seed(
+    row: i32,
+    column: i32,
+    mine_num: i32,
+    x0: i32,
+    y0: i32,
+    max_times: i32,
+    seed: u64,
+) -> String {
+    serde_json::to_string(&ms::laymine_solvable_seed(
+        row as usize,
+        column as usize,
+        mine_num as usize,
+        x0 as usize,
+        y0 as usize,
+        max_times as usize,
+        seed,
+    ))
+    .unwrap()
+}
+
+/// 局面的难度评估。返回json，各项见ms_toollib的Difficulty
+#[wasm_bindgen]
+pub fn cal_difficulty(board_json: &str, x0: i32, y0: i32) -> String {
+    let board_: serde_json::Value = serde_json::from_str(&board_json).unwrap();
+    let board__ = board_.as_array().unwrap();
+    let len_ = board__.len();
+    let mut res = vec![];
+    for i in 0..len_ {
+        res.push(
+            board__[i]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|x| x.as_i64().unwrap() as i32)
+                .collect::<Vec<_>>(),
+        );
+    }
+    let d = ms::cal_difficulty(&res, x0 as usize, y0 as usize);
+    serde_json::json!({
+        "direct_steps": d.direct_steps,
+        "minus_steps": d.minus_steps,
+        "enumerate_steps": d.enumerate_steps,
+        "max_enum_len": d.max_enum_len,
+        "guesses": d.guesses,
+        "guess_survival": d.guess_survival,
+        "chording_chances": d.chording_chances,
+        "score": d.score,
+    })
+    .to_string()
+}
+
+/// 可设种子的按条件埋雷。条件为json，可以有bbbv、op、isl（都是[下限, 上限]的闭区间）、
+/// cell_nums（9个闭区间）、solvable、difficulty、local_moves，没有的项不限制。
+/// 返回json：boards，以及统计attempts、accepted、acceptance_rate、exhausted
+#[wasm_bindgen]
+pub fn laymine_filtered_seed(
+    row: i32,
+    column: i32,
+    mine_num: i32,
+    x0: i32,
+    y0: i32,
+    filter_json: &str,
+    n: i32,
+    max_times: i32,
+    seed: u64,
+) -> String {
+    let filter_: serde_json::Value = serde_json::from_str(filter_json).unwrap();
+    let range = |v: &serde_json::Value| -> (usize, usize) {
+        (v[0].as_u64().unwrap() as usize, v[1].as_u64().unwrap() as usize)
+    };
+    let mut filter = ms::LaymineFilter::default();
+    if !filter_["bbbv"].is_null() {
+        filter.bbbv = range(&filter_["bbbv"]);
+    }
+    if !filter_["op"].is_null() {
+        filter.op = range(&filter_["op"]);
+    }
+    if !filter_["isl"].is_null() {
+        filter.isl = range(&filter_["isl"]);
+    }
+    if let Some(c) = filter_["cell_nums"].as_array() {
+        for (i, r) in c.iter().enumerate().take(9) {
+            filter.cell_nums[i] = range(r);
+        }
+    }
+    filter.solvable = filter_["solvable"].as_bool().unwrap_or(false);
+    if !filter_["difficulty"].is_null() {
+        filter.difficulty = (
+            filter_["difficulty"][0].as_f64().unwrap(),
+            filter_["difficulty"][1].as_f64().unwrap(),
+        );
+    }
+    filter.local_moves = filter_["local_moves"].as_u64().unwrap_or(0) as usize;
+    let (boards, stats) = ms::laymine_filtered_seed(
+        row as usize,
+        column as usize,
+        mine_num as usize,
+        x0 as usize,
+        y0 as usize,
+        &filter,
+        n as usize,
+        max_times as usize,
+        seed,
+    );
+    serde_json::json!({
+        "boards": boards,
+        "attempts": stats.attempts,
+        "accepted": stats.accepted,
+        "acceptance_rate": stats.acceptance_rate(),
+        "exhausted": stats.exhausted,
+    })
+    .to_string()
+}
+
+/// MCMC无猜埋雷。返回json：board、flag，以及混合诊断steps、accepted、unmoved_mines、
+/// bbbv_effective_sample_size
+#[wasm_bindgen]
+pub fn laymine_solvable_mcmc_seed(
+    row: i32,
+    column: i32,
+    mine_num: i32,
+    x0: i32,
+    y0: i32,
+    steps: i32,
+    seed: u64,
+) -> String {
+    let (board, flag, diagnostics) = ms::laymine_solvable_mcmc_seed(
+        row as usize,
+        column as usize,
+        mine_num as usize,
+        x0 as usize,
+        y0 as usize,
+        steps as usize,
+        seed,
+    );
+    serde_json::json!({
+        "board": board,
+        "flag": flag,
+        "steps": diagnostics.steps,
+        "accepted": diagnostics.accepted,
+        "unmoved_mines": diagnostics.unmoved_mines,
+        "bbbv_effective_sample_size": diagnostics.bbbv_effective_sample_size,
+    })
+    .to_string()
+}
+
+
 
 
 
+/// 起手位置为负数时，局面码不含起手位置。编码失败时返回空字符串
+#[wasm_bindgen]
+pub fn encode_board_code(board_json: &str, x0: i32, y0: i32) -> String {
+    let board_: serde_json::Value = serde_json::from_str(&board_json).unwrap();
+    let board__ = board_.as_array().unwrap();
+    let len_ = board__.len();
+    let mut res = vec![];
+    for i in 0..len_ {
+        res.push(
+            board__[i]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|x| x.as_i64().unwrap() as i32)
+                .collect::<Vec<_>>(),
+        );
+    }
+    let start = if x0 < 0 || y0 < 0 {
+        None
+    } else {
+        Some((x0 as usize, y0 as usize))
+    };
+    ms::encode_board_code(&res, start).unwrap_or_default()
+}
+
+/// 返回json，即(局面, 起手位置)，解码失败时局面为空
+#[wasm_bindgen]
+pub fn decode_board_code(code: &str) -> String {
+    match ms::decode_board_code(code) {
+        Ok(t) => serde_json::to_string(&t).unwrap(),
+        Err(_) => serde_json::to_string(&(Vec::<Vec<i32>>::new(), None::<(usize, usize)>)).unwrap(),
+    }
+}
+
+fn constraint_json(c: &ms::Constraint) -> serde_json::Value {
+    serde_json::json!({"number": c.number, "cells": c.cells, "mines": c.mines})
+}
+
+fn deduction_json(d: &ms::Deduction) -> serde_json::Value {
+    let mut t = serde_json::json!({"cell": d.cell, "is_mine": d.is_mine, "round": d.round});
+    match &d.proof {
+        ms::Proof::Direct(c) => {
+            t["method"] = "direct".into();
+            t["constraints"] = serde_json::json!([constraint_json(c)]);
+        }
+        ms::Proof::Minus { a, b, relation } => {
+            t["method"] = "minus".into();
+            t["constraints"] = serde_json::json!([constraint_json(a), constraint_json(b)]);
+            t["relation"] = match relation {
+                ms::Relation::Contains => "contains",
+                ms::Relation::ContainedIn => "contained_in",
+                ms::Relation::Overlap => "overlap",
+            }
+            .into();
+        }
+        ms::Proof::Enumerate(cs) => {
+            t["method"] = "enumerate".into();
+            t["constraints"] = cs.iter().map(constraint_json).collect();
+        }
+    }
+    t
+}
+
+/// 返回json，即推理的列表。每条推理含cell、is_mine、round、method（"direct"、"minus"、"enumerate"）、
+/// constraints（证明用到的约束），双集合还有relation（"contains"、"contained_in"、"overlap"）
+#[wasm_bindgen]
+pub fn solve_explain(board_json: &str) -> String {
+    let board_: serde_json::Value = serde_json::from_str(&board_json).unwrap();
+    let board__ = board_.as_array().unwrap();
+    let len_ = board__.len();
+    let mut board_of_game = vec![];
+    for i in 0..len_ {
+        board_of_game.push(
+            board__[i]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|x| x.as_i64().unwrap() as i32)
+                .collect::<Vec<_>>(),
+        );
+    }
+    let ans: Vec<serde_json::Value> = ms::solve_explain(&board_of_game)
+        .iter()
+        .map(deduction_json)
+        .collect();
+    serde_json::to_string(&ans).unwrap()
+}
+
+/// 生成判雷练习题。tier为"direct"、"minus"或"enumerate"。返回json：board、game_board、
+/// solution（推理的列表，同solve_explain）。生成不了时返回null
+#[wasm_bindgen]
+pub fn generate_puzzle_seed(
+    row: i32,
+    column: i32,
+    mine_num: i32,
+    tier: &str,
+    max_cells: i32,
+    max_times: i32,
+    seed: u64,
+) -> String {
+    let tier = match tier {
+        "direct" => ms::PuzzleTier::Direct,
+        "minus" => ms::PuzzleTier::Minus,
+        _ => ms::PuzzleTier::Enumerate,
+    };
+    match ms::generate_puzzle_seed(
+        row as usize,
+        column as usize,
+        mine_num as usize,
+        tier,
+        max_cells as usize,
+        max_times as usize,
+        seed,
+    ) {
+        Some(puzzle) => serde_json::json!({
+            "board": puzzle.board,
+            "game_board": puzzle.game_board,
+            "solution": puzzle.solution.iter().map(deduction_json).collect::<Vec<_>>(),
+        })
+        .to_string(),
+        None => "null".to_string(),
+    }
+}
+
+/// 移雷，使(x, y)是雷或不是雷，且与游戏局面中已打开的格子一致。返回json，即新的局面；移不了时返回null
+#[wasm_bindgen]
+pub fn rearrange_mines(board_json: &str, game_board_json: &str, x: i32, y: i32, is_mine: bool, seed: u64) -> String {
+    let mut boards = vec![];
+    for json in [board_json, game_board_json] {
+        let board_: serde_json::Value = serde_json::from_str(json).unwrap();
+        let board__ = board_.as_array().unwrap();
+        let len_ = board__.len();
+        let mut board = vec![];
+        for i in 0..len_ {
+            board.push(
+                board__[i]
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .map(|x| x.as_i64().unwrap() as i32)
+                    .collect::<Vec<_>>(),
+            );
+        }
+        boards.push(board);
+    }
+    match ms::rearrange_mines(&boards[0], &boards[1], (x as usize, y as usize), is_mine, seed) {
+        Some(board) => serde_json::to_string(&board).unwrap(),
+        None => "null".to_string(),
+    }
+}
+
+/// 返回json，即排好序的推荐。每条含cell、method（"safe"、"guess"），猜雷还有mine_probability、
+/// zero_probability、expected_solvable、score。局面无解时返回空列表
+#[wasm_bindgen]
+pub fn suggest_move(board_json: &str, mine_num: f64) -> String {
+    let board_: serde_json::Value = serde_json::from_str(&board_json).unwrap();
+    let board__ = board_.as_array().unwrap();
+    let len_ = board__.len();
+    let mut board_of_game = vec![];
+    for i in 0..len_ {
+        board_of_game.push(
+            board__[i]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|x| x.as_i64().unwrap() as i32)
+                .collect::<Vec<_>>(),
+        );
+    }
+    let ans: Vec<serde_json::Value> = ms::suggest_move(&board_of_game, mine_num)
+        .unwrap_or_default()
+        .iter()
+        .map(|m| match m.reason {
+            ms::MoveReason::Safe => serde_json::json!({"cell": m.cell, "method": "safe"}),
+            ms::MoveReason::Guess {
+                mine_probability,
+                zero_probability,
+                expected_solvable,
+                score,
+            } => serde_json::json!({
+                "cell": m.cell,
+                "method": "guess",
+                "mine_probability": mine_probability,
+                "zero_probability": zero_probability,
+                "expected_solvable": expected_solvable,
+                "score": score,
+            }),
+        })
+        .collect();
+    serde_json::to_string(&ans).unwrap()
+}
+
+/// 检查游戏局面是否自洽。返回json，即矛盾的列表，每处矛盾含kind及该种矛盾的其他字段
+#[wasm_bindgen]
+pub fn validate_game_board(board_json: &str, mine_num: usize) -> String {
+    let board_: serde_json::Value = serde_json::from_str(&board_json).unwrap();
+    let board__ = board_.as_array().unwrap();
+    let len_ = board__.len();
+    let mut board_of_game = vec![];
+    for i in 0..len_ {
+        board_of_game.push(
+            board__[i]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|x| x.as_i64().unwrap() as i32)
+                .collect::<Vec<_>>(),
+        );
+    }
+    let ans: Vec<serde_json::Value> = ms::validate_game_board(&board_of_game, mine_num)
+        .into_iter()
+        .map(|c| match c {
+            ms::Contradiction::InvalidValue { cell, value } => {
+                serde_json::json!({"kind": "invalid_value", "cell": cell, "value": value})
+            }
+            ms::Contradiction::NumberTooLarge {
+                cell,
+                number,
+                covered,
+            } => serde_json::json!({
+                "kind": "number_too_large",
+                "cell": cell,
+                "number": number,
+                "covered": covered,
+            }),
+            ms::Contradiction::OverFlagged {
+                cell,
+                number,
+                flags,
+            } => serde_json::json!({
+                "kind": "over_flagged",
+                "cell": cell,
+                "number": number,
+                "flags": flags,
+            }),
+            ms::Contradiction::Unsatisfiable(cells) => {
+                serde_json::json!({"kind": "unsatisfiable", "cells": cells})
+            }
+            ms::Contradiction::MineCountOutOfRange { mine_num, min, max } => serde_json::json!({
+                "kind": "mine_count_out_of_range",
+                "mine_num": mine_num,
+                "min": min,
+                "max": max,
+            }),
+            ms::Contradiction::WrongFlag(cell) => {
+                serde_json::json!({"kind": "wrong_flag", "cell": cell})
+            }
+        })
+        .collect();
+    serde_json::to_string(&ans).unwrap()
+}
+
+/// 残局求解。返回json，含total（分布的总数）和moves，每种点法含cell、wins、win_probability。
+/// 求解失败（格子太多、超出预算、局面矛盾）时moves为空列表
+#[wasm_bindgen]
+pub fn solve_endgame(board_json: &str, mine_num: usize, budget: usize) -> String {
+    let board_: serde_json::Value = serde_json::from_str(&board_json).unwrap();
+    let board__ = board_.as_array().unwrap();
+    let len_ = board__.len();
+    let mut board_of_game = vec![];
+    for i in 0..len_ {
+        board_of_game.push(
+            board__[i]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|x| x.as_i64().unwrap() as i32)
+                .collect::<Vec<_>>(),
+        );
+    }
+    let (total, moves) = ms::solve_endgame(&board_of_game, mine_num, budget).unwrap_or_default();
+    let moves: Vec<serde_json::Value> = moves
+        .iter()
+        .map(|m| {
+            serde_json::json!({
+                "cell": m.cell,
+                "wins": m.wins,
+                "win_probability": m.win_probability,
+            })
+        })
+        .collect();
+    serde_json::json!({"total": total, "moves": moves}).to_string()
+}
+
+/// 用默认的定式目录识别定式。返回json，每个定式含name、position、rotation、mirrored、
+/// numbers、mines、safes
+#[wasm_bindgen]
+pub fn find_patterns(board_json: &str) -> String {
+    let board_: serde_json::Value = serde_json::from_str(&board_json).unwrap();
+    let board__ = board_.as_array().unwrap();
+    let len_ = board__.len();
+    let mut board_of_game = vec![];
+    for i in 0..len_ {
+        board_of_game.push(
+            board__[i]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|x| x.as_i64().unwrap() as i32)
+                .collect::<Vec<_>>(),
+        );
+    }
+    let ans: Vec<serde_json::Value> = ms::find_patterns(&board_of_game)
+        .iter()
+        .map(|m| {
+            serde_json::json!({
+                "name": m.name,
+                "position": m.position,
+                "rotation": m.orientation.rotation,
+                "mirrored": m.orientation.mirrored,
+                "numbers": m.numbers,
+                "mines": m.mines,
+                "safes": m.safes,
+            })
+        })
+        .collect();
+    serde_json::to_string(&ans).unwrap()
+}